@@ -763,6 +763,23 @@ fn create_spatial_ref(target_crs: &str) -> Result<SpatialRef, String> {
     }
 }
 
+/// Convert affine coefficients from `solve_affine` to a GDAL geotransform
+/// `[origin_x, pixel_width, row_rotation, origin_y, col_rotation, pixel_height]`
+///
+/// Our coeffs: geo_x = a0 + a1*pixel_x + a2*pixel_y
+///             geo_y = b0 + b1*pixel_x + b2*pixel_y
+/// which is exactly GDAL's forward geotransform, rotation terms included.
+fn affine_geotransform(coeffs: &[f64]) -> [f64; 6] {
+    [
+        coeffs[0], // origin_x (a0)
+        coeffs[1], // pixel_width (a1)
+        coeffs[2], // row_rotation (a2)
+        coeffs[3], // origin_y (b0)
+        coeffs[4], // col_rotation (b1)
+        coeffs[5], // pixel_height (b2)
+    ]
+}
+
 /// Apply affine georeferencing using geotransform
 fn apply_affine_georeference(
     app: &AppHandle,
@@ -778,25 +795,7 @@ fn apply_affine_georeference(
     emit_progress(app, "compute", 0.15, "Computing affine transformation...");
     let coeffs = solve_affine(gcps)?;
 
-    // Convert polynomial coefficients to GDAL geotransform
-    // GDAL geotransform: [origin_x, pixel_width, row_rotation, origin_y, col_rotation, pixel_height]
-    // Our coeffs: geo_x = a0 + a1*pixel_x + a2*pixel_y
-    //             geo_y = b0 + b1*pixel_x + b2*pixel_y
-    //
-    // At pixel (0,0): geo_x = a0, geo_y = b0 (this is the origin)
-    // pixel_width = a1 (change in geo_x per pixel in x direction)
-    // pixel_height = b2 (change in geo_y per pixel in y direction, usually negative)
-    // row_rotation = a2 (change in geo_x per pixel in y direction)
-    // col_rotation = b1 (change in geo_y per pixel in x direction)
-
-    let geotransform = [
-        coeffs[0], // origin_x (a0)
-        coeffs[1], // pixel_width (a1)
-        coeffs[2], // row_rotation (a2)
-        coeffs[3], // origin_y (b0)
-        coeffs[4], // col_rotation (b1)
-        coeffs[5], // pixel_height (b2)
-    ];
+    let geotransform = affine_geotransform(&coeffs);
 
     // Get the data type from the first band
     let first_band = src_ds
@@ -1266,6 +1265,7 @@ fn find_source_pixel(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gdal::geotransform::{geo_to_pixel, pixel_to_geo};
 
     fn create_test_gcps() -> Vec<GCPData> {
         // Simple affine transformation test GCPs
@@ -1333,6 +1333,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_affine_geotransform_round_trip() {
+        // Frame rotated by ~20° and sheared slightly
+        let gcps: Vec<GCPData> = [(0.0, 0.0), (800.0, 0.0), (0.0, 600.0), (800.0, 600.0)]
+            .iter()
+            .map(|&(pixel_x, pixel_y)| GCPData {
+                pixel_x,
+                pixel_y,
+                geo_x: 350000.0 + 0.47 * pixel_x + 0.18 * pixel_y,
+                geo_y: 5200000.0 + 0.17 * pixel_x - 0.47 * pixel_y,
+            })
+            .collect();
+        let gt = affine_geotransform(&solve_affine(&gcps).expect("Should solve affine"));
+
+        for gcp in &gcps {
+            let (x, y) = pixel_to_geo(&gt, gcp.pixel_x, gcp.pixel_y);
+            assert!((x - gcp.geo_x).abs() < 1e-6 && (y - gcp.geo_y).abs() < 1e-6);
+
+            let (col, row) = geo_to_pixel(&gt, gcp.geo_x, gcp.geo_y).unwrap();
+            assert!(
+                (col - gcp.pixel_x).abs() < 1e-6,
+                "{} vs {}",
+                col,
+                gcp.pixel_x
+            );
+            assert!(
+                (row - gcp.pixel_y).abs() < 1e-6,
+                "{} vs {}",
+                row,
+                gcp.pixel_y
+            );
+        }
+    }
+
     #[test]
     fn test_solve_affine_minimum_gcps() {
         let gcps = vec![
@@ -1,44 +1,126 @@
 #![allow(clippy::too_many_arguments)]
 
+use crate::gdal::band_math::{self, write_band_math, Expression};
+use crate::gdal::bounds::{cached_transform, dataset_footprint, dataset_geo_bounds, wgs84};
+use crate::gdal::colormap::{palette_color, read_palette, Colormap};
+use crate::gdal::complex::{is_complex, read_complex, ComplexPart, ComplexValue};
+use crate::gdal::contrast::{ContrastLut, LutCache, StretchMode};
 use crate::gdal::dataset_cache::DatasetCache;
+use crate::gdal::dimensions::{read_dimensions, DimensionSlice, RasterDimension};
+use crate::gdal::geotransform::{geo_to_pixel, pixel_size};
+use crate::gdal::nodata::{read_mask, read_masked, NodataRules};
+use crate::gdal::overviews::{
+    build_overviews as build_overviews_at, is_local_file, needs_overviews, overview_factors,
+    OverviewJobs, OverviewLocation, OverviewProgress, OverviewResampling,
+};
+use crate::gdal::region::{mask_outside, PixelPolygon, RegionCrs};
+use crate::gdal::statistics::{
+    compute_statistics, stored_statistics, GdalStatistics, StatsAccuracy,
+};
+use crate::gdal::stretch::{read_sample, sample_size, AutoStretch};
+use crate::gdal::subdatasets::{read_subdatasets, SubdatasetInfo};
+use crate::gdal::terrain::{
+    geographic_cell_size, map_neighbourhood, Gradient, HillshadeParams, TerrainDerivative,
+};
+use crate::gdal::tile_cache::{TileCache, TileCacheKey, TileCacheStats};
 use crate::gdal::tile_extractor::{
-    extract_rgb_tile, extract_tile, extract_tile_with_stretch, StretchParams, TileRequest,
+    extract_data_tile, extract_pixel_data_tile, extract_rgb_tile, extract_tile,
+    extract_tile_with_stretch, Resampling, StretchParams, TileFormat, TileRequest,
 };
+use gdal::cpl::CslStringList;
+use gdal::raster::RasterBand;
 use gdal::spatial_ref::{CoordTransform, SpatialRef};
-use gdal::Dataset;
+use gdal::{Dataset, DriverManager};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, Ordering};
-use tauri::State;
+use tauri::ipc::Response;
+use tauri::{AppHandle, Emitter, State};
 
 static FIRST_TILE_LOGGED: AtomicBool = AtomicBool::new(false);
 
-/// Open dataset with appropriate overview level for the given zoom
-fn open_dataset_for_zoom(path: &str, _z: u8) -> Result<Dataset, String> {
-    // For remote COGs (vsicurl), set GDAL config for proper access
-    let is_remote = path.starts_with("/vsicurl/");
-
-    if is_remote {
-        // Ensure GDAL config is set for remote access in this thread
-        // GDAL config options are thread-local, so we need to set them here
-        gdal::config::set_config_option("GDAL_DISABLE_READDIR_ON_OPEN", "EMPTY_DIR").ok();
-
-        // Longer timeout and retries for slow connections (e.g., Wyvern data)
-        gdal::config::set_config_option("GDAL_HTTP_TIMEOUT", "300").ok();
-        gdal::config::set_config_option("GDAL_HTTP_MAX_RETRY", "5").ok();
-        gdal::config::set_config_option("GDAL_HTTP_RETRY_DELAY", "2").ok();
-
-        // Enable caching with larger buffer for pixel-interleaved COGs
-        gdal::config::set_config_option("VSI_CACHE", "TRUE").ok();
-        gdal::config::set_config_option("VSI_CACHE_SIZE", "100000000").ok(); // 100MB cache
-        gdal::config::set_config_option("CPL_VSIL_CURL_CACHE_SIZE", "100000000").ok();
-
-        // For COGs, GDAL automatically uses appropriate overviews during read operations
-        // based on the requested window size. No need to explicitly select overview level.
-        Dataset::open(path).map_err(|e| format!("Failed to open remote: {}", e))
-    } else {
-        // Local files - just open normally
-        Dataset::open(path).map_err(|e| format!("Failed to open: {}", e))
+/// Longest side, in pixels, of the band samples used for stats and stretches
+const SAMPLE_SIZE: usize = 1024;
+
+/// Histogram bins behind the equalizing stretch lookup tables
+const LUT_BINS: usize = 256;
+
+/// Tile edge length, in pixels, when the frontend does not ask for another
+const DEFAULT_TILE_SIZE: usize = 256;
+
+/// Largest tile edge length the tile commands render
+const MAX_TILE_SIZE: usize = 2048;
+
+/// Edge length of a rendered tile: the map source's `tile_size` (default 256)
+/// times the display's `pixel_ratio`, so HiDPI screens get full-resolution
+/// tiles. Tile bounds come from z/x/y alone; the size only sets how many
+/// pixels cover them.
+fn resolve_tile_size(tile_size: Option<usize>, pixel_ratio: Option<f64>) -> Result<usize, String> {
+    let ratio = pixel_ratio.unwrap_or(1.0);
+    if !ratio.is_finite() || ratio <= 0.0 {
+        return Err(format!("Invalid pixel ratio: {}", ratio));
     }
+
+    let size = (tile_size.unwrap_or(DEFAULT_TILE_SIZE) as f64 * ratio).round() as usize;
+    if !(1..=MAX_TILE_SIZE).contains(&size) {
+        return Err(format!(
+            "Tile size must be between 1 and {} pixels, got {}",
+            MAX_TILE_SIZE, size
+        ));
+    }
+    Ok(size)
+}
+
+/// Band a single-band tile draws: for multidimensional rasters the band at
+/// `slice` (dimension name -> index or coordinate value), otherwise `band`
+/// (1 by default)
+fn resolve_band(
+    state: &DatasetCache,
+    id: &str,
+    band: Option<i32>,
+    slice: Option<&DimensionSlice>,
+) -> Result<i32, String> {
+    match slice {
+        Some(slice) => Ok(state.slice_band(id, slice)? as i32),
+        None => Ok(band.unwrap_or(1)),
+    }
+}
+
+/// Band a multi-band tile or profile reads for `band`: with a `slice` of a
+/// multidimensional raster, band numbers count within the bands the slice
+/// leaves free (e.g. positions along the last dimension at the selected time
+/// step); otherwise `band` is used as is
+fn resolve_slice_band(
+    state: &DatasetCache,
+    id: &str,
+    band: usize,
+    slice: Option<&DimensionSlice>,
+) -> Result<usize, String> {
+    let Some(slice) = slice else {
+        return Ok(band);
+    };
+    let bands = state.slice_bands(id, slice)?;
+    band.checked_sub(1)
+        .and_then(|i| bands.get(i).copied())
+        .ok_or_else(|| format!("Band {} is outside the slice's {} bands", band, bands.len()))
+}
+
+/// Serve a tile from the rendered-tile cache, rendering and caching it on a miss
+fn cached_tile(
+    cache: &TileCache,
+    key: TileCacheKey,
+    render: impl FnOnce() -> Result<Vec<u8>, String>,
+) -> Result<Response, String> {
+    if let Some(tile) = cache.get(&key) {
+        tracing::trace!("tile cache hit");
+        return Ok(Response::new(tile));
+    }
+
+    tracing::trace!("tile cache miss");
+    let tile = render()?;
+    cache.insert(key, tile.clone());
+    Ok(Response::new(tile))
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -48,13 +130,18 @@ pub struct RasterMetadata {
     pub width: usize,
     pub height: usize,
     pub bands: usize,
-    pub bounds: [f64; 4], // [minx, miny, maxx, maxy] in EPSG:4326 or pixel coords
+    pub bounds: [f64; 4], // [minx, miny, maxx, maxy] in EPSG:4326 (maxx > 180 across 180°) or pixel coords
     pub native_bounds: [f64; 4], // bounds in native CRS
     pub projection: String,
     pub pixel_size: [f64; 2],
     pub nodata: Option<f64>,
-    pub band_stats: Vec<BandStats>, // Stats for each band
-    pub is_georeferenced: bool,     // true if image has valid geotransform/projection
+    pub band_stats: Vec<BandStats>,       // Stats for each band
+    pub is_georeferenced: bool,           // true if image has valid geotransform/projection
+    pub palette: Option<Vec<[u8; 4]>>,    // RGBA colour table of band 1, indexed by pixel value
+    pub dimensions: Vec<RasterDimension>, // time/depth/level axes behind the bands, slowest first
+    pub is_complex: bool,                 // band 1 holds complex (e.g. SAR) values
+    pub overview_count: usize,            // overview levels of band 1
+    pub needs_overviews: bool, // large local file without overviews: slow when zoomed out
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -64,6 +151,25 @@ pub struct BandStats {
     pub max: f64,
     pub mean: f64,
     pub std_dev: f64,
+    pub valid_count: Option<u64>, // valid pixels in the whole band, when GDAL reports it
+    pub nodata_percent: Option<f64>, // share of nodata pixels, 0-100
+    pub approximate: bool,        // from a subsample rather than every pixel
+}
+
+impl BandStats {
+    /// Statistics GDAL computed or stored for a band of `width` x `height`
+    pub fn from_gdal(band: usize, stats: GdalStatistics, width: usize, height: usize) -> Self {
+        BandStats {
+            band,
+            min: stats.min,
+            max: stats.max,
+            mean: stats.mean,
+            std_dev: stats.std_dev,
+            valid_count: stats.valid_count(width, height),
+            nodata_percent: stats.nodata_percent(),
+            approximate: stats.approximate,
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -76,14 +182,15 @@ pub struct HistogramData {
     pub bin_edges: Vec<f64>,
 }
 
-/// Compute histogram bins from raw pixel values
+/// Compute histogram bins from raw pixel values, skipping masked (NaN) pixels,
+/// infinities and values the nodata rules reject
 /// Returns (counts, bin_edges)
 pub fn compute_histogram_bins(
     values: &[f64],
     min: f64,
     max: f64,
     bin_count: usize,
-    nodata: Option<f64>,
+    rules: &NodataRules,
 ) -> (Vec<u64>, Vec<f64>) {
     let mut counts = vec![0u64; bin_count];
     let range = max - min;
@@ -91,10 +198,8 @@ pub fn compute_histogram_bins(
     if range > 0.0 {
         for &value in values {
             // Skip nodata values
-            if let Some(nd) = nodata {
-                if (value - nd).abs() < 1e-10 {
-                    continue;
-                }
+            if !rules.is_valid(value) {
+                continue;
             }
 
             if value >= min && value <= max {
@@ -105,11 +210,7 @@ pub fn compute_histogram_bins(
         }
     } else {
         // All values are the same - count non-nodata values
-        let valid_count = if let Some(nd) = nodata {
-            values.iter().filter(|&&v| (v - nd).abs() >= 1e-10).count()
-        } else {
-            values.len()
-        };
+        let valid_count = values.iter().filter(|&&v| rules.is_valid(v)).count();
         counts[0] = valid_count as u64;
     }
 
@@ -122,122 +223,331 @@ pub fn compute_histogram_bins(
     (counts, bin_edges)
 }
 
-/// Check if a dataset has valid georeferencing
-fn is_georeferenced(dataset: &Dataset) -> bool {
-    // Check if there's a projection
-    let has_projection = !dataset.projection().is_empty();
+/// Compute band statistics from raw pixel values, skipping masked (NaN) pixels
+/// and values the nodata rules reject. `None` when no value is valid.
+pub fn compute_stats_from_values(
+    band: usize,
+    values: &[f64],
+    rules: &NodataRules,
+) -> Option<BandStats> {
+    let mut count = 0usize;
+    let mut min = f64::INFINITY;
+    let mut max = f64::NEG_INFINITY;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+
+    for &value in values.iter().filter(|&&v| rules.is_valid(v)) {
+        count += 1;
+        min = min.min(value);
+        max = max.max(value);
+        sum += value;
+        sum_sq += value * value;
+    }
 
-    // Check geotransform - identity would be [0, 1, 0, 0, 0, -1] or [0, 1, 0, 0, 0, 1]
-    let gt = match dataset.geo_transform() {
-        Ok(gt) => gt,
-        Err(_) => return false,
-    };
+    if count == 0 {
+        return None;
+    }
 
-    // Identity geotransform means no real georeferencing
-    let is_identity = (gt[0].abs() < 1e-10)
-        && ((gt[1] - 1.0).abs() < 1e-10)
-        && (gt[2].abs() < 1e-10)
-        && (gt[3].abs() < 1e-10)
-        && (gt[4].abs() < 1e-10)
-        && ((gt[5] + 1.0).abs() < 1e-10 || (gt[5] - 1.0).abs() < 1e-10);
+    let mean = sum / count as f64;
+    let variance = (sum_sq / count as f64 - mean * mean).max(0.0);
 
-    has_projection || !is_identity
+    Some(BandStats {
+        band,
+        min,
+        max,
+        mean,
+        std_dev: variance.sqrt(),
+        valid_count: None,
+        nodata_percent: None,
+        approximate: true,
+    })
+}
+
+/// Read a band for stats and histograms, decimated so large rasters stay
+/// fast. Masked pixels (nodata, mask or alpha band) are NaN; complex bands are
+/// read as `part`.
+fn read_band_sample(dataset: &Dataset, band: usize, part: ComplexPart) -> Result<Vec<f64>, String> {
+    let rasterband = dataset
+        .rasterband(band)
+        .map_err(|e| format!("Failed to get band {}: {}", band, e))?;
+
+    read_sample(
+        &rasterband,
+        (0, 0),
+        dataset.raster_size(),
+        SAMPLE_SIZE,
+        part,
+    )
 }
 
-/// Calculate bounds in native CRS
-fn calculate_native_bounds(dataset: &Dataset) -> Result<[f64; 4], String> {
+/// Read window of a raster: top-left pixel and size
+type PixelWindow = ((isize, isize), (usize, usize));
+
+/// Clamp the pixel-space extent of some points to a raster, as a read window.
+///
+/// Returns `None` when the extent misses the raster entirely.
+fn clamp_pixel_window(xs: &[f64], ys: &[f64], width: usize, height: usize) -> Option<PixelWindow> {
+    let min_x = xs.iter().cloned().fold(f64::INFINITY, f64::min).floor();
+    let max_x = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max).ceil();
+    let min_y = ys.iter().cloned().fold(f64::INFINITY, f64::min).floor();
+    let max_y = ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max).ceil();
+
+    let x0 = min_x.max(0.0);
+    let y0 = min_y.max(0.0);
+    let x1 = max_x.min(width as f64);
+    let y1 = max_y.min(height as f64);
+
+    if !(x1 > x0 && y1 > y0) {
+        return None;
+    }
+
+    Some((
+        (x0 as isize, y0 as isize),
+        ((x1 - x0) as usize, (y1 - y0) as usize),
+    ))
+}
+
+/// Transform from EPSG:4326 to a dataset's native CRS. None when the dataset
+/// has no projection or a geographic one, whose coordinates are used as they
+/// are.
+fn native_transform(dataset: &Dataset) -> Result<Option<Rc<CoordTransform>>, String> {
+    let projection = dataset.projection();
+    if projection.is_empty() {
+        return Ok(None);
+    }
+
+    let mut target_srs = SpatialRef::from_wkt(&projection)
+        .map_err(|e| format!("Failed to parse target SRS: {}", e))?;
+    if target_srs.is_geographic() {
+        return Ok(None);
+    }
+
+    // Set axis mapping to traditional GIS order (lng, lat) not (lat, lng)
+    target_srs
+        .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
+    cached_transform(&wgs84()?, &target_srs).map(Some)
+}
+
+/// Pixel (column, row) containing native coordinates
+fn native_to_pixel(gt: &[f64; 6], x: f64, y: f64) -> Result<(i32, i32), String> {
+    let (col, row) = geo_to_pixel(gt, x, y).ok_or("Invalid geotransform")?;
+    Ok((col.floor() as i32, row.floor() as i32))
+}
+
+/// Pixel coordinates of points given in `crs`; pixel input is used as given
+fn to_pixel_coords(
+    dataset: &Dataset,
+    mut xs: Vec<f64>,
+    mut ys: Vec<f64>,
+    crs: RegionCrs,
+) -> Result<(Vec<f64>, Vec<f64>), String> {
+    if crs == RegionCrs::Pixel {
+        return Ok((xs, ys));
+    }
+
     let gt = dataset
         .geo_transform()
         .map_err(|e| format!("Failed to get geotransform: {}", e))?;
+    if let Some(transform) = native_transform(dataset)? {
+        transform
+            .transform_coords(&mut xs, &mut ys, &mut [])
+            .map_err(|e| format!("Failed to transform coordinates: {}", e))?;
+    }
+
+    Ok(xs
+        .iter()
+        .zip(&ys)
+        .map(|(&x, &y)| geo_to_pixel(&gt, x, y).ok_or("Invalid geotransform"))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .unzip())
+}
 
+/// Pixel window of a dataset covered by a bbox, given as
+/// `[minX, minY, maxX, maxY]` in `crs`
+fn bbox_pixel_window(
+    dataset: &Dataset,
+    bbox: [f64; 4],
+    crs: RegionCrs,
+) -> Result<Option<PixelWindow>, String> {
     let (width, height) = dataset.raster_size();
 
-    // gt[0] = top left x, gt[3] = top left y
-    // gt[1] = pixel width, gt[5] = pixel height (usually negative)
-    let min_x = gt[0];
-    let max_x = gt[0] + (width as f64) * gt[1];
-    let max_y = gt[3];
-    let min_y = gt[3] + (height as f64) * gt[5];
+    // With rotation terms the window covers all four corners
+    let (px, py) = to_pixel_coords(
+        dataset,
+        vec![bbox[0], bbox[2], bbox[0], bbox[2]],
+        vec![bbox[1], bbox[1], bbox[3], bbox[3]],
+        crs,
+    )?;
+
+    Ok(clamp_pixel_window(&px, &py, width, height))
+}
 
-    Ok([min_x, min_y, max_x, max_y])
+/// Part of a dataset that stats, histograms and auto stretches sample: a
+/// read window, plus a polygon outside which samples are dropped
+struct SampleRegion {
+    window: (isize, isize),
+    window_size: (usize, usize),
+    polygon: Option<PixelPolygon>,
 }
 
-/// Transform bounds from native CRS to EPSG:4326
-fn transform_bounds_to_4326(
+/// Resolve an optional bbox and polygon (rings of `[x, y]`) to the region to
+/// sample; the whole raster when neither is given. Both are in `crs`, which
+/// defaults to EPSG:4326 for georeferenced rasters and pixels otherwise. A
+/// polygon's vertices are reprojected, its edges are not.
+fn sample_region(
     dataset: &Dataset,
-    native_bounds: [f64; 4],
-) -> Result<[f64; 4], String> {
-    let projection = dataset.projection();
+    bbox: Option<[f64; 4]>,
+    polygon: Option<&[Vec<[f64; 2]>]>,
+    crs: Option<RegionCrs>,
+) -> Result<SampleRegion, String> {
+    let (width, height) = dataset.raster_size();
+    let crs = RegionCrs::resolve(crs, is_georeferenced(dataset))?;
+
+    let polygon = polygon
+        .map(|rings| {
+            rings
+                .iter()
+                .map(|ring| -> Result<Vec<(f64, f64)>, String> {
+                    let (xs, ys) = ring.iter().map(|&[x, y]| (x, y)).unzip();
+                    let (px, py) = to_pixel_coords(dataset, xs, ys, crs)?;
+                    Ok(px.into_iter().zip(py).collect())
+                })
+                .collect::<Result<Vec<_>, String>>()
+                .map(PixelPolygon::new)
+        })
+        .transpose()?;
+
+    let window = match (bbox, &polygon) {
+        (Some(bbox), _) => bbox_pixel_window(dataset, bbox, crs)?,
+        (None, Some(polygon)) => {
+            let (xs, ys) = polygon.vertices();
+            clamp_pixel_window(&xs, &ys, width, height)
+        }
+        (None, None) => Some(((0, 0), (width, height))),
+    };
+    let (window, window_size) = window.ok_or("Region does not overlap the raster")?;
 
-    // If no projection or already in geographic CRS, assume it's lat/lon
-    if projection.is_empty() {
-        return Ok(native_bounds);
+    Ok(SampleRegion {
+        window,
+        window_size,
+        polygon,
+    })
+}
+
+/// Read a band sample within a region, decimated like `read_band_sample`
+fn read_region_sample(
+    dataset: &Dataset,
+    band: usize,
+    region: &SampleRegion,
+    part: ComplexPart,
+) -> Result<Vec<f64>, String> {
+    let rasterband = dataset
+        .rasterband(band)
+        .map_err(|e| format!("Failed to get band {}: {}", band, e))?;
+
+    let mut values = read_sample(
+        &rasterband,
+        region.window,
+        region.window_size,
+        SAMPLE_SIZE,
+        part,
+    )?;
+    if let Some(polygon) = &region.polygon {
+        let size = sample_size(region.window_size, SAMPLE_SIZE);
+        mask_outside(
+            &mut values,
+            region.window,
+            region.window_size,
+            size,
+            polygon,
+        );
     }
 
-    let source_srs = SpatialRef::from_wkt(&projection)
-        .map_err(|e| format!("Failed to parse source SRS: {}", e))?;
+    Ok(values)
+}
 
-    // Check if already geographic (lat/lon)
-    if source_srs.is_geographic() {
-        return Ok(native_bounds);
+/// Attach the dataset-wide lookup table an equalizing stretch needs, built
+/// with `compute_histogram_bins` over a sample of the whole band so every
+/// tile uses the same table. The sample is of the request's complex part and
+/// kept for later ranges; binning applies the nodata rules.
+fn with_contrast_lut(
+    luts: &LutCache,
+    dataset: &Dataset,
+    id: &str,
+    band: i32,
+    stretch: &StretchParams,
+    request: &TileRequest,
+) -> Result<StretchParams, String> {
+    if stretch.mode.is_linear() {
+        return Ok(stretch.clone());
     }
 
-    let mut target_srs = SpatialRef::from_epsg(4326)
-        .map_err(|e| format!("Failed to create EPSG:4326 SRS: {}", e))?;
+    let (rules, part) = (request.nodata, request.complex);
+    let key_error = |e: serde_json::Error| format!("Failed to build lookup table key: {}", e);
+    let sample_key = serde_json::to_string(&(band, part)).map_err(key_error)?;
+    let lut_key = serde_json::to_string(&(stretch.min, stretch.max, stretch.mode, rules))
+        .map_err(key_error)?;
+    let lut = luts.get_or_build(
+        id,
+        sample_key,
+        lut_key,
+        || read_band_sample(dataset, band as usize, part),
+        |values| {
+            let (counts, _) =
+                compute_histogram_bins(values, stretch.min, stretch.max, LUT_BINS, rules);
+            ContrastLut::from_histogram(&counts, stretch.mode)
+        },
+    )?;
+
+    Ok(StretchParams {
+        lut,
+        ..stretch.clone()
+    })
+}
+
+/// Check if a dataset has valid georeferencing
+fn is_georeferenced(dataset: &Dataset) -> bool {
+    // Check if there's a projection
+    let has_projection = !dataset.projection().is_empty();
 
-    // Force traditional GIS axis order (lon, lat) instead of authority-compliant (lat, lon)
-    target_srs
-        .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
+    // Check geotransform - identity would be [0, 1, 0, 0, 0, -1] or [0, 1, 0, 0, 0, 1]
+    let gt = match dataset.geo_transform() {
+        Ok(gt) => gt,
+        Err(_) => return false,
+    };
 
-    let transform = CoordTransform::new(&source_srs, &target_srs)
-        .map_err(|e| format!("Failed to create coordinate transform: {}", e))?;
-
-    // Transform corners (x=easting, y=northing in UTM)
-    let mut xs = vec![
-        native_bounds[0],
-        native_bounds[2],
-        native_bounds[0],
-        native_bounds[2],
-    ];
-    let mut ys = vec![
-        native_bounds[1],
-        native_bounds[1],
-        native_bounds[3],
-        native_bounds[3],
-    ];
-
-    transform
-        .transform_coords(&mut xs, &mut ys, &mut [])
-        .map_err(|e| format!("Failed to transform coordinates: {}", e))?;
-
-    // After transform with TraditionalGisOrder: xs = longitudes, ys = latitudes
-    let min_lon = xs.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_lon = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let min_lat = ys.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_lat = ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-
-    // Return as [minLon, minLat, maxLon, maxLat] for MapLibre
-    Ok([min_lon, min_lat, max_lon, max_lat])
+    // Identity geotransform means no real georeferencing
+    let is_identity = (gt[0].abs() < 1e-10)
+        && ((gt[1] - 1.0).abs() < 1e-10)
+        && (gt[2].abs() < 1e-10)
+        && (gt[3].abs() < 1e-10)
+        && (gt[4].abs() < 1e-10)
+        && ((gt[5] + 1.0).abs() < 1e-10 || (gt[5] - 1.0).abs() < 1e-10);
+
+    has_projection || !is_identity
 }
 
-/// Compute statistics for all bands
+/// Statistics for all bands: those stored with the file (or its `.aux.xml`)
+/// when present, otherwise computed from a sample, ignoring masked pixels
 fn compute_band_stats(dataset: &Dataset) -> Vec<BandStats> {
     let band_count = dataset.raster_count();
+    let (width, height) = dataset.raster_size();
     let mut stats = Vec::new();
 
     for i in 1..=band_count {
-        if let Ok(band) = dataset.rasterband(i) {
-            if let Ok(min_max) = band.compute_raster_min_max(true) {
-                let mean = (min_max.min + min_max.max) / 2.0;
-                let std_dev = (min_max.max - min_max.min) / 4.0;
-                stats.push(BandStats {
-                    band: i,
-                    min: min_max.min,
-                    max: min_max.max,
-                    mean,
-                    std_dev,
-                });
-            }
+        let stored = dataset
+            .rasterband(i)
+            .ok()
+            .and_then(|b| stored_statistics(&b));
+        if let Some(stored) = stored {
+            stats.push(BandStats::from_gdal(i, stored, width, height));
+        } else if let Ok(values) = read_band_sample(dataset, i, ComplexPart::default()) {
+            stats.extend(compute_stats_from_values(
+                i,
+                &values,
+                &NodataRules::default(),
+            ));
         }
     }
 
@@ -252,17 +562,52 @@ pub async fn open_raster(
 ) -> Result<RasterMetadata, String> {
     let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
 
+    // Containers such as netCDF or HDF5 files with several variables only
+    // list their arrays as subdatasets
+    if dataset.raster_count() == 0 {
+        let count = read_subdatasets(&dataset).len();
+        return Err(if count > 0 {
+            format!(
+                "File contains {} subdatasets; open one of them instead",
+                count
+            )
+        } else {
+            "File contains no raster bands".to_string()
+        });
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let metadata = raster_metadata(&dataset, id.clone(), path.clone())?;
+
+    // Store only the path; worker threads open their own pooled handles on demand
+    state.add(id.clone(), path);
+    state.set_dimensions(&id, metadata.dimensions.clone());
+
+    Ok(metadata)
+}
+
+/// List the subdatasets of a container file (netCDF variables, HDF5 arrays,
+/// extra GeoTIFF/NITF images). Each `name` opens with `open_raster` like a
+/// file path; files without subdatasets return an empty list.
+#[tauri::command]
+pub async fn list_subdatasets(path: String) -> Result<Vec<SubdatasetInfo>, String> {
+    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    Ok(read_subdatasets(&dataset))
+}
+
+/// Build the metadata the frontend needs to display a raster layer
+fn raster_metadata(dataset: &Dataset, id: String, path: String) -> Result<RasterMetadata, String> {
     let (width, height) = dataset.raster_size();
     let bands = dataset.raster_count();
-    let georeferenced = is_georeferenced(&dataset);
+    let georeferenced = is_georeferenced(dataset);
 
     let (bounds, native_bounds, pixel_size) = if georeferenced {
-        let native_bounds = calculate_native_bounds(&dataset)?;
-        let bounds = transform_bounds_to_4326(&dataset, native_bounds)?;
+        let native_bounds = dataset_footprint(dataset)?.bounds();
+        let bounds = dataset_geo_bounds(dataset)?;
         let gt = dataset
             .geo_transform()
             .map_err(|e| format!("Failed to get geotransform: {}", e))?;
-        (bounds, native_bounds, [gt[1].abs(), gt[5].abs()])
+        (bounds, native_bounds, pixel_size(&gt))
     } else {
         // Non-georeferenced: use pixel coordinates
         let pixel_bounds = [0.0, 0.0, width as f64, height as f64];
@@ -272,15 +617,23 @@ pub async fn open_raster(
     let projection = dataset.projection();
 
     let nodata = dataset.rasterband(1).ok().and_then(|b| b.no_data_value());
+    let palette = dataset.rasterband(1).ok().and_then(|b| read_palette(&b));
+    let complex = dataset.rasterband(1).is_ok_and(|b| is_complex(&b));
+    let overview_count = dataset
+        .rasterband(1)
+        .ok()
+        .and_then(|b| b.overview_count().ok())
+        .unwrap_or(0) as usize;
+    let missing_overviews = needs_overviews(width, height, overview_count) && is_local_file(&path);
 
     // Compute stats for all bands
-    let band_stats = compute_band_stats(&dataset);
+    let band_stats = compute_band_stats(dataset);
 
-    let id = uuid::Uuid::new_v4().to_string();
+    let dimensions = read_dimensions(&path, width, height, bands);
 
-    let metadata = RasterMetadata {
-        id: id.clone(),
-        path: path.clone(),
+    Ok(RasterMetadata {
+        id,
+        path,
         width,
         height,
         bands,
@@ -291,15 +644,165 @@ pub async fn open_raster(
         nodata,
         band_stats,
         is_georeferenced: georeferenced,
-    };
+        palette,
+        dimensions,
+        is_complex: complex,
+        overview_count,
+        needs_overviews: missing_overviews,
+    })
+}
 
-    // Store only the path, not the dataset (GDAL Dataset is not thread-safe)
-    state.add(id, path);
+/// Create a raster calculator layer from a band-math expression
+///
+/// `bN` in the expression is band N of layer `id`; `alias.bN` is band N of the
+/// layer registered under that alias in `inputs` (alias -> layer id), warped
+/// onto `id`'s grid when needed. The result is written once to a Float32
+/// GeoTIFF and registered like any opened raster, so every tile, stats,
+/// histogram, query and profile command works on it.
+#[tauri::command]
+pub async fn create_band_math_layer(
+    id: String,
+    expression: String,
+    inputs: Option<HashMap<String, String>>,
+    state: State<'_, DatasetCache>,
+) -> Result<RasterMetadata, String> {
+    let parsed = Expression::parse(&expression)?;
+
+    let primary = state.get_dataset(&id)?;
+    let input_datasets = inputs
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(alias, layer_id)| Ok((alias, state.get_dataset(&layer_id)?)))
+        .collect::<Result<HashMap<_, _>, String>>()?;
+    let input_refs = input_datasets
+        .iter()
+        .map(|(alias, dataset)| (alias.clone(), dataset.as_ref()))
+        .collect();
+
+    let dir = band_math::output_dir()?;
+    let new_id = uuid::Uuid::new_v4().to_string();
+    let output = dir.join(format!("{}.tif", new_id));
+
+    write_band_math(&parsed, &primary, &input_refs, &output)?;
+
+    let path = output.to_string_lossy().to_string();
+    let dataset =
+        Dataset::open(&output).map_err(|e| format!("Failed to open band math result: {}", e))?;
+    let metadata = raster_metadata(&dataset, new_id.clone(), path.clone())?;
+    state.add(new_id, path);
 
     Ok(metadata)
 }
 
+/// Export a raster layer (e.g. a band-math result) to a GeoTIFF
+#[tauri::command]
+pub async fn export_raster(
+    id: String,
+    output_path: String,
+    state: State<'_, DatasetCache>,
+) -> Result<(), String> {
+    let dataset = state.get_dataset(&id)?;
+
+    let driver = DriverManager::get_driver_by_name("GTiff")
+        .map_err(|e| format!("Failed to get GTiff driver: {}", e))?;
+    let options: CslStringList = ["COMPRESS=LZW", "TILED=YES", "BIGTIFF=IF_SAFER"]
+        .into_iter()
+        .collect();
+    dataset
+        .create_copy(&driver, &output_path, &options)
+        .map_err(|e| format!("Failed to export raster: {}", e))?;
+
+    Ok(())
+}
+
+/// Build overviews (reduced-resolution copies) for a layer's file, so
+/// zoomed-out tiles stop reading it at full resolution.
+///
+/// Levels halve the size until the smallest fits in one tile. Progress is
+/// emitted as `overview-progress` events and `cancel_overviews` stops the
+/// build, leaving the file as it was. Returns the number of levels built; the
+/// layer's handles, cached tiles and equalizing tables are refreshed so they
+/// use them. Read handles other threads hold on the file stay open while it
+/// is written.
+#[tauri::command]
+pub async fn build_overviews(
+    id: String,
+    location: Option<OverviewLocation>,
+    resampling: Option<OverviewResampling>,
+    app: AppHandle,
+    state: State<'_, DatasetCache>,
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+    jobs: State<'_, OverviewJobs>,
+) -> Result<usize, String> {
+    let path = state.get_path(&id).ok_or("Dataset not found")?;
+    if !is_local_file(&path) {
+        return Err("Overviews can only be built for local files".to_string());
+    }
+    let (width, height) = state.get_dataset(&id)?.raster_size();
+    let factors = overview_factors(width, height);
+    if factors.is_empty() {
+        return Err("Raster is small enough not to need overviews".to_string());
+    }
+
+    let cancel = jobs.start(&id)?;
+
+    // The build can take minutes; keep it off the async runtime's workers
+    let level_count = factors.len();
+    let job_id = id.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let mut last_emitted = -1.0;
+        build_overviews_at(
+            &path,
+            location.unwrap_or_default(),
+            resampling.unwrap_or_default(),
+            &factors,
+            |progress| {
+                // GDAL reports progress per block; pass on whole percents only
+                if progress - last_emitted >= 0.01 || progress >= 1.0 {
+                    last_emitted = progress;
+                    let _ = app.emit(
+                        "overview-progress",
+                        OverviewProgress {
+                            id: job_id.clone(),
+                            progress,
+                        },
+                    );
+                }
+                !cancel.load(Ordering::Relaxed)
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Failed to build overviews: {}", e));
+    jobs.finish(&id);
+    result??;
+
+    // Pooled read handles stay open during the build and only see the new
+    // levels once reopened; tiles and equalizing tables built from the old
+    // ones are dropped too
+    state.reopen(&id);
+    tile_cache.invalidate_layer(&id);
+    luts.invalidate_layer(&id);
+    Ok(level_count)
+}
+
+/// Stop a running `build_overviews` for a layer; returns whether one was running
+#[tauri::command]
+pub async fn cancel_overviews(id: String, jobs: State<'_, OverviewJobs>) -> Result<bool, String> {
+    Ok(jobs.cancel(&id))
+}
+
 /// Get a tile from a raster dataset with auto stretch
+///
+/// Like every tile command, the image bytes are returned as a raw binary IPC
+/// response (an `ArrayBuffer` on the frontend) instead of a JSON number array,
+/// and the tile is `tile_size` × `pixel_ratio` pixels square (256 by default),
+/// encoded as `format` (PNG by default).
+/// For multidimensional rasters a `slice` of the time/depth/level dimensions
+/// picks the band instead of `band`.
+/// Pixels the dataset masks out (nodata, mask or alpha band) are transparent,
+/// as are those matching the layer's `nodata` rules.
 #[tauri::command]
 pub async fn get_tile(
     id: String,
@@ -307,25 +810,69 @@ pub async fn get_tile(
     y: i32,
     z: u8,
     band: Option<i32>,
+    slice: Option<DimensionSlice>,
+    resampling: Option<Resampling>,
+    colormap: Option<Colormap>,
+    hillshade: Option<HillshadeParams>,
+    derivative: Option<TerrainDerivative>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-
-    // Open dataset with appropriate overview level for this zoom
-    let dataset = open_dataset_for_zoom(&path, z)?;
-
+    tile_cache: State<'_, TileCache>,
+) -> Result<Response, String> {
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
-        band: band.unwrap_or(1),
-        tile_size: 256,
+        band: resolve_band(&state, &id, band, slice.as_ref())?,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: complex.unwrap_or_default(),
     };
 
-    extract_tile(&dataset, &request)
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "auto",
+            request.tile_size,
+            request.format,
+            request.band,
+            request.complex,
+            request.resampling,
+            &nodata,
+            &colormap,
+            &hillshade,
+            derivative,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let ramp = colormap.as_ref().map(Colormap::build).transpose()?;
+        let dataset = state.get_dataset(&id)?;
+        extract_tile(
+            &dataset,
+            &request,
+            ramp.as_ref(),
+            hillshade.as_ref(),
+            derivative,
+        )
+    })
 }
 
 /// Get a tile with custom stretch parameters
+///
+/// With a `colormap` the stretched band is drawn through a colour ramp or class
+/// map instead of as grayscale. With `hillshade` the band is shaded as a DEM,
+/// and combined with the colormap for colour relief. With `derivative` the
+/// DEM's slope or aspect is rendered instead of the elevation.
 #[tauri::command]
 pub async fn get_tile_stretched(
     id: String,
@@ -333,36 +880,87 @@ pub async fn get_tile_stretched(
     y: i32,
     z: u8,
     band: Option<i32>,
+    slice: Option<DimensionSlice>,
     min: f64,
     max: f64,
     gamma: f64,
+    stretch_mode: Option<StretchMode>,
+    resampling: Option<Resampling>,
+    colormap: Option<Colormap>,
+    hillshade: Option<HillshadeParams>,
+    derivative: Option<TerrainDerivative>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<Response, String> {
     if !FIRST_TILE_LOGGED.swap(true, Ordering::Relaxed) {
         println!(
             "[TILE] First tile request: z={} x={} y={} path={}",
-            z, x, y, &path
+            z,
+            x,
+            y,
+            state.get_path(&id).unwrap_or_default()
         );
     }
 
-    let dataset = open_dataset_for_zoom(&path, z)?;
-
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
-        band: band.unwrap_or(1),
-        tile_size: 256,
+        band: resolve_band(&state, &id, band, slice.as_ref())?,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: complex.unwrap_or_default(),
     };
 
-    let stretch = StretchParams { min, max, gamma };
+    let stretch = StretchParams::new(min, max, gamma, stretch_mode.unwrap_or_default());
 
-    extract_tile_with_stretch(&dataset, &request, &stretch)
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "stretched",
+            request.tile_size,
+            request.format,
+            request.band,
+            request.complex,
+            &stretch,
+            request.resampling,
+            &nodata,
+            &colormap,
+            &hillshade,
+            derivative,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let ramp = colormap.as_ref().map(Colormap::build).transpose()?;
+        let dataset = state.get_dataset(&id)?;
+        let stretch = with_contrast_lut(&luts, &dataset, &id, request.band, &stretch, &request)?;
+        extract_tile_with_stretch(
+            &dataset,
+            &request,
+            &stretch,
+            ramp.as_ref(),
+            hillshade.as_ref(),
+            derivative,
+        )
+    })
 }
 
 /// Get an RGB composite tile
+///
+/// For multidimensional rasters the band numbers count within the bands
+/// `slice` leaves free (see `resolve_slice_band`).
 #[tauri::command]
 pub async fn get_rgb_tile(
     id: String,
@@ -372,163 +970,293 @@ pub async fn get_rgb_tile(
     red_band: i32,
     green_band: i32,
     blue_band: i32,
+    slice: Option<DimensionSlice>,
     red_min: f64,
     red_max: f64,
     red_gamma: f64,
+    red_mode: Option<StretchMode>,
     green_min: f64,
     green_max: f64,
     green_gamma: f64,
+    green_mode: Option<StretchMode>,
     blue_min: f64,
     blue_max: f64,
     blue_gamma: f64,
+    blue_mode: Option<StretchMode>,
+    resampling: Option<Resampling>,
+    nodata: Option<NodataRules>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<Response, String> {
     if !FIRST_TILE_LOGGED.swap(true, Ordering::Relaxed) {
         println!("[TILE] First RGB tile request: z={} x={} y={} bands=({},{},{}) stretch=({}-{},{}-{},{}-{}) path={}",
             z, x, y, red_band, green_band, blue_band,
-            red_min, red_max, green_min, green_max, blue_min, blue_max, &path);
+            red_min, red_max, green_min, green_max, blue_min, blue_max,
+            state.get_path(&id).unwrap_or_default());
     }
 
-    let dataset = open_dataset_for_zoom(&path, z)?;
+    let slice = slice.as_ref();
+    let red_band = resolve_slice_band(&state, &id, red_band as usize, slice)? as i32;
+    let green_band = resolve_slice_band(&state, &id, green_band as usize, slice)? as i32;
+    let blue_band = resolve_slice_band(&state, &id, blue_band as usize, slice)? as i32;
 
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
         band: 1, // Not used directly
-        tile_size: 256,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: ComplexPart::default(),
     };
 
-    let red_stretch = StretchParams {
-        min: red_min,
-        max: red_max,
-        gamma: red_gamma,
-    };
-    let green_stretch = StretchParams {
-        min: green_min,
-        max: green_max,
-        gamma: green_gamma,
-    };
-    let blue_stretch = StretchParams {
-        min: blue_min,
-        max: blue_max,
-        gamma: blue_gamma,
-    };
-
-    extract_rgb_tile(
-        &dataset,
-        &request,
-        red_band,
-        green_band,
-        blue_band,
-        &red_stretch,
-        &green_stretch,
-        &blue_stretch,
-    )
+    let red_stretch = StretchParams::new(red_min, red_max, red_gamma, red_mode.unwrap_or_default());
+    let green_stretch = StretchParams::new(
+        green_min,
+        green_max,
+        green_gamma,
+        green_mode.unwrap_or_default(),
+    );
+    let blue_stretch = StretchParams::new(
+        blue_min,
+        blue_max,
+        blue_gamma,
+        blue_mode.unwrap_or_default(),
+    );
+
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "rgb",
+            request.tile_size,
+            request.format,
+            [red_band, green_band, blue_band],
+            [&red_stretch, &green_stretch, &blue_stretch],
+            request.resampling,
+            &nodata,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let dataset = state.get_dataset(&id)?;
+        let red_stretch =
+            with_contrast_lut(&luts, &dataset, &id, red_band, &red_stretch, &request)?;
+        let green_stretch =
+            with_contrast_lut(&luts, &dataset, &id, green_band, &green_stretch, &request)?;
+        let blue_stretch =
+            with_contrast_lut(&luts, &dataset, &id, blue_band, &blue_stretch, &request)?;
+        extract_rgb_tile(
+            &dataset,
+            &request,
+            red_band,
+            green_band,
+            blue_band,
+            &red_stretch,
+            &green_stretch,
+            &blue_stretch,
+        )
+    })
 }
 
 /// Get statistics for a band
+///
+/// Without `accuracy` the band is sampled, whole or within `bbox` and/or
+/// `polygon` (in `bbox_crs`: EPSG:4326 or pixels, by default EPSG:4326 unless
+/// the raster is not georeferenced), leaving
+/// out masked pixels and values matching the layer's `nodata` rules. With
+/// it, GDAL computes approximate or exact statistics of the whole band over
+/// the file's own nodata, reported with the valid pixel count, and stores
+/// them in the file's `.aux.xml` (when writable) so later opens and other
+/// tools reuse them.
 #[tauri::command]
 pub async fn get_raster_stats(
     id: String,
     band: i32,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    accuracy: Option<StatsAccuracy>,
+    bbox: Option<[f64; 4]>,
+    polygon: Option<Vec<Vec<[f64; 2]>>>,
+    bbox_crs: Option<RegionCrs>,
     state: State<'_, DatasetCache>,
 ) -> Result<BandStats, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    let rules = nodata.unwrap_or_default();
+    let part = complex.unwrap_or_default();
+
+    if let Some(accuracy) = accuracy {
+        if rules != NodataRules::default()
+            || part != ComplexPart::default()
+            || bbox.is_some()
+            || polygon.is_some()
+            || bbox_crs.is_some()
+        {
+            return Err(
+                "Full statistics cover the whole band, with the file's own nodata \
+                 and complex amplitude only"
+                    .to_string(),
+            );
+        }
+        // A handle of its own, so the statistics are written out when it closes
+        let path = state.get_path(&id).ok_or("Dataset not found")?;
+        let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+        let rasterband = dataset
+            .rasterband(band as usize)
+            .map_err(|e| format!("Failed to get band {}: {}", band, e))?;
+        let stats = compute_statistics(&rasterband, accuracy)?;
+        let (width, height) = dataset.raster_size();
+        return Ok(BandStats::from_gdal(band as usize, stats, width, height));
+    }
 
-    let rasterband = dataset
-        .rasterband(band as usize)
-        .map_err(|e| format!("Failed to get band {}: {}", band, e))?;
+    let dataset = state.get_dataset(&id)?;
+    let region = sample_region(&dataset, bbox, polygon.as_deref(), bbox_crs)?;
+    let values = read_region_sample(&dataset, band as usize, &region, part)?;
+
+    compute_stats_from_values(band as usize, &values, &rules)
+        .ok_or_else(|| format!("Band {} has no valid pixels", band))
+}
 
-    // Try to get pre-computed statistics, otherwise compute them
-    let stats = rasterband
-        .compute_raster_min_max(true)
-        .map_err(|e| format!("Failed to compute statistics: {}", e))?;
+/// Histogram of a band's sample within a region, binned over the range of
+/// its valid values
+fn band_histogram(
+    dataset: &Dataset,
+    band: usize,
+    region: &SampleRegion,
+    bin_count: usize,
+    rules: &NodataRules,
+    part: ComplexPart,
+) -> Result<HistogramData, String> {
+    let values = read_region_sample(dataset, band, region, part)?;
 
-    let min = stats.min;
-    let max = stats.max;
-    // Estimate mean and std_dev from min/max
-    let mean = (min + max) / 2.0;
-    let std_dev = (max - min) / 4.0;
+    // Range of the valid values
+    let (min, max) = compute_stats_from_values(band, &values, rules)
+        .map_or((0.0, 0.0), |stats| (stats.min, stats.max));
 
-    Ok(BandStats {
-        band: band as usize,
+    // Compute histogram bins using extracted function
+    let (counts, bin_edges) = compute_histogram_bins(&values, min, max, bin_count, rules);
+
+    Ok(HistogramData {
+        band,
         min,
         max,
-        mean,
-        std_dev,
+        bin_count,
+        counts,
+        bin_edges,
     })
 }
 
 /// Get histogram for a band
+///
+/// The band is sampled whole or within `bbox` and/or `polygon`, in `bbox_crs`
+/// as for `get_raster_stats`. Masked pixels, NaN and values
+/// matching the layer's `nodata` rules are left out.
 #[tauri::command]
 pub async fn get_histogram(
     id: String,
     band: i32,
     num_bins: Option<usize>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    bbox: Option<[f64; 4]>,
+    polygon: Option<Vec<Vec<[f64; 2]>>>,
+    bbox_crs: Option<RegionCrs>,
     state: State<'_, DatasetCache>,
 ) -> Result<HistogramData, String> {
-    use gdal::raster::ResampleAlg;
-
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
-
-    let rasterband = dataset
-        .rasterband(band as usize)
-        .map_err(|e| format!("Failed to get band {}: {}", band, e))?;
-
-    // Get band statistics for range
-    let stats = rasterband
-        .compute_raster_min_max(true)
-        .map_err(|e| format!("Failed to compute statistics: {}", e))?;
-
-    let min = stats.min;
-    let max = stats.max;
-    let bin_count = num_bins.unwrap_or(256);
+    let dataset = state.get_dataset(&id)?;
+    let region = sample_region(&dataset, bbox, polygon.as_deref(), bbox_crs)?;
 
-    // For large rasters, use decimation to sample
-    let (width, height) = dataset.raster_size();
-    let max_sample_size = 1024;
-
-    let (read_width, read_height) = if width > max_sample_size || height > max_sample_size {
-        let scale = (max_sample_size as f64 / width.max(height) as f64).min(1.0);
-        (
-            (width as f64 * scale) as usize,
-            (height as f64 * scale) as usize,
-        )
-    } else {
-        (width, height)
-    };
-
-    // Read band data with resampling if needed
-    let nodata = rasterband.no_data_value();
-
-    let buffer = rasterband
-        .read_as::<f64>(
-            (0, 0),
-            (width, height),
-            (read_width, read_height),
-            Some(ResampleAlg::NearestNeighbour),
-        )
-        .map_err(|e| format!("Failed to read band data: {}", e))?;
+    band_histogram(
+        &dataset,
+        band as usize,
+        &region,
+        num_bins.unwrap_or(256),
+        &nodata.unwrap_or_default(),
+        complex.unwrap_or_default(),
+    )
+}
 
-    // Compute histogram bins using extracted function
-    let (counts, bin_edges) = compute_histogram_bins(buffer.data(), min, max, bin_count, nodata);
+/// Get histograms for several bands at once, e.g. the three of an RGB
+/// composite, each binned over its own range
+///
+/// Takes the same region and nodata arguments as `get_histogram`.
+#[tauri::command]
+pub async fn get_histograms(
+    id: String,
+    bands: Vec<usize>,
+    num_bins: Option<usize>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    bbox: Option<[f64; 4]>,
+    polygon: Option<Vec<Vec<[f64; 2]>>>,
+    bbox_crs: Option<RegionCrs>,
+    state: State<'_, DatasetCache>,
+) -> Result<Vec<HistogramData>, String> {
+    let dataset = state.get_dataset(&id)?;
+    let region = sample_region(&dataset, bbox, polygon.as_deref(), bbox_crs)?;
+    let rules = nodata.unwrap_or_default();
+
+    bands
+        .iter()
+        .map(|&band| {
+            band_histogram(
+                &dataset,
+                band,
+                &region,
+                num_bins.unwrap_or(256),
+                &rules,
+                complex.unwrap_or_default(),
+            )
+        })
+        .collect()
+}
 
-    Ok(HistogramData {
-        band: band as usize,
-        min,
-        max,
-        bin_count,
-        counts,
-        bin_edges,
-    })
+/// Compute auto stretch parameters for one or more bands
+///
+/// Samples each band, either whole or within `bbox` (the current viewport)
+/// and/or `polygon` (rings of `[x, y]`), in `bbox_crs` as for
+/// `get_raster_stats`, and returns one `StretchParams` per
+/// requested band in order, ready for the stretched and RGB tile commands.
+/// `method` defaults to a 2-98% percentile cut.
+#[tauri::command]
+pub async fn compute_auto_stretch(
+    id: String,
+    bands: Vec<usize>,
+    method: Option<AutoStretch>,
+    bbox: Option<[f64; 4]>,
+    polygon: Option<Vec<Vec<[f64; 2]>>>,
+    bbox_crs: Option<RegionCrs>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    state: State<'_, DatasetCache>,
+) -> Result<Vec<StretchParams>, String> {
+    let dataset = state.get_dataset(&id)?;
+    let method = method.unwrap_or_default();
+    let rules = nodata.unwrap_or_default();
+    let region = sample_region(&dataset, bbox, polygon.as_deref(), bbox_crs)?;
+
+    bands
+        .iter()
+        .map(|&band| {
+            let values = read_region_sample(&dataset, band, &region, complex.unwrap_or_default())?;
+
+            method
+                .compute(&values, &rules)
+                .ok_or_else(|| format!("Band {} has no valid pixels", band))
+        })
+        .collect()
 }
 
 /// Get a cross-layer RGB composite tile (bands from different datasets)
+///
+/// A `slice` applies to each layer's dimensions, as for `get_rgb_tile`.
 #[tauri::command]
 pub async fn get_cross_layer_rgb_tile(
     red_id: String,
@@ -537,69 +1265,126 @@ pub async fn get_cross_layer_rgb_tile(
     green_band: i32,
     blue_id: String,
     blue_band: i32,
+    slice: Option<DimensionSlice>,
     x: i32,
     y: i32,
     z: u8,
     red_min: f64,
     red_max: f64,
     red_gamma: f64,
+    red_mode: Option<StretchMode>,
     green_min: f64,
     green_max: f64,
     green_gamma: f64,
+    green_mode: Option<StretchMode>,
     blue_min: f64,
     blue_max: f64,
     blue_gamma: f64,
+    blue_mode: Option<StretchMode>,
+    resampling: Option<Resampling>,
+    nodata: Option<NodataRules>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<Response, String> {
     use crate::gdal::tile_extractor::{extract_cross_layer_rgb_tile, StretchParams, TileRequest};
 
-    let red_path = state.get_path(&red_id).ok_or("Red dataset not found")?;
-    let green_path = state.get_path(&green_id).ok_or("Green dataset not found")?;
-    let blue_path = state.get_path(&blue_id).ok_or("Blue dataset not found")?;
-
-    let red_ds =
-        Dataset::open(&red_path).map_err(|e| format!("Failed to open red raster: {}", e))?;
-    let green_ds =
-        Dataset::open(&green_path).map_err(|e| format!("Failed to open green raster: {}", e))?;
-    let blue_ds =
-        Dataset::open(&blue_path).map_err(|e| format!("Failed to open blue raster: {}", e))?;
+    // Each layer counts band numbers within its own bands of the slice
+    let slice = slice.as_ref();
+    let red_band = resolve_slice_band(&state, &red_id, red_band as usize, slice)
+        .map_err(|e| format!("Red layer: {}", e))? as i32;
+    let green_band = resolve_slice_band(&state, &green_id, green_band as usize, slice)
+        .map_err(|e| format!("Green layer: {}", e))? as i32;
+    let blue_band = resolve_slice_band(&state, &blue_id, blue_band as usize, slice)
+        .map_err(|e| format!("Blue layer: {}", e))? as i32;
 
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
         band: 1,
-        tile_size: 256,
-    };
-
-    let red_stretch = StretchParams {
-        min: red_min,
-        max: red_max,
-        gamma: red_gamma,
-    };
-    let green_stretch = StretchParams {
-        min: green_min,
-        max: green_max,
-        gamma: green_gamma,
-    };
-    let blue_stretch = StretchParams {
-        min: blue_min,
-        max: blue_max,
-        gamma: blue_gamma,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: ComplexPart::default(),
     };
 
-    extract_cross_layer_rgb_tile(
-        &red_ds,
-        red_band,
-        &green_ds,
-        green_band,
-        &blue_ds,
-        blue_band,
-        &request,
-        &red_stretch,
-        &green_stretch,
-        &blue_stretch,
-    )
+    let red_stretch = StretchParams::new(red_min, red_max, red_gamma, red_mode.unwrap_or_default());
+    let green_stretch = StretchParams::new(
+        green_min,
+        green_max,
+        green_gamma,
+        green_mode.unwrap_or_default(),
+    );
+    let blue_stretch = StretchParams::new(
+        blue_min,
+        blue_max,
+        blue_gamma,
+        blue_mode.unwrap_or_default(),
+    );
+
+    let key = TileCacheKey::new(
+        &[&red_id, &green_id, &blue_id],
+        z,
+        x,
+        y,
+        &(
+            "cross_rgb",
+            request.tile_size,
+            request.format,
+            [red_band, green_band, blue_band],
+            [&red_stretch, &green_stretch, &blue_stretch],
+            request.resampling,
+            &nodata,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let red_ds = state
+            .get_dataset(&red_id)
+            .map_err(|e| format!("Red layer: {}", e))?;
+        let green_ds = state
+            .get_dataset(&green_id)
+            .map_err(|e| format!("Green layer: {}", e))?;
+        let blue_ds = state
+            .get_dataset(&blue_id)
+            .map_err(|e| format!("Blue layer: {}", e))?;
+        let red_stretch =
+            with_contrast_lut(&luts, &red_ds, &red_id, red_band, &red_stretch, &request)?;
+        let green_stretch = with_contrast_lut(
+            &luts,
+            &green_ds,
+            &green_id,
+            green_band,
+            &green_stretch,
+            &request,
+        )?;
+        let blue_stretch = with_contrast_lut(
+            &luts,
+            &blue_ds,
+            &blue_id,
+            blue_band,
+            &blue_stretch,
+            &request,
+        )?;
+
+        extract_cross_layer_rgb_tile(
+            &red_ds,
+            red_band,
+            &green_ds,
+            green_band,
+            &blue_ds,
+            blue_band,
+            &request,
+            &red_stretch,
+            &green_stretch,
+            &blue_stretch,
+        )
+    })
 }
 
 /// Get a tile for non-georeferenced images (using pixel coordinates)
@@ -610,27 +1395,62 @@ pub async fn get_pixel_tile(
     y: i32,
     z: u8,
     band: Option<i32>,
+    slice: Option<DimensionSlice>,
     min: f64,
     max: f64,
     gamma: f64,
+    stretch_mode: Option<StretchMode>,
+    resampling: Option<Resampling>,
+    colormap: Option<Colormap>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<Response, String> {
     use crate::gdal::tile_extractor::{extract_pixel_tile, StretchParams, TileRequest};
 
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
-
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
-        band: band.unwrap_or(1),
-        tile_size: 256,
+        band: resolve_band(&state, &id, band, slice.as_ref())?,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: complex.unwrap_or_default(),
     };
 
-    let stretch = StretchParams { min, max, gamma };
+    let stretch = StretchParams::new(min, max, gamma, stretch_mode.unwrap_or_default());
 
-    extract_pixel_tile(&dataset, &request, &stretch)
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "pixel",
+            request.tile_size,
+            request.format,
+            request.band,
+            request.complex,
+            &stretch,
+            request.resampling,
+            &nodata,
+            &colormap,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let ramp = colormap.as_ref().map(Colormap::build).transpose()?;
+        let dataset = state.get_dataset(&id)?;
+        let stretch = with_contrast_lut(&luts, &dataset, &id, request.band, &stretch, &request)?;
+        extract_pixel_tile(&dataset, &request, &stretch, ramp.as_ref())
+    })
 }
 
 /// Get an RGB tile for non-georeferenced images (using pixel coordinates)
@@ -643,56 +1463,96 @@ pub async fn get_pixel_rgb_tile(
     red_band: i32,
     green_band: i32,
     blue_band: i32,
+    slice: Option<DimensionSlice>,
     red_min: f64,
     red_max: f64,
     red_gamma: f64,
+    red_mode: Option<StretchMode>,
     green_min: f64,
     green_max: f64,
     green_gamma: f64,
+    green_mode: Option<StretchMode>,
     blue_min: f64,
     blue_max: f64,
     blue_gamma: f64,
+    blue_mode: Option<StretchMode>,
+    resampling: Option<Resampling>,
+    nodata: Option<NodataRules>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<Response, String> {
     use crate::gdal::tile_extractor::{extract_pixel_rgb_tile, StretchParams, TileRequest};
 
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    let slice = slice.as_ref();
+    let red_band = resolve_slice_band(&state, &id, red_band as usize, slice)? as i32;
+    let green_band = resolve_slice_band(&state, &id, green_band as usize, slice)? as i32;
+    let blue_band = resolve_slice_band(&state, &id, blue_band as usize, slice)? as i32;
 
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
         band: 1, // Not used directly
-        tile_size: 256,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: ComplexPart::default(),
     };
 
-    let red_stretch = StretchParams {
-        min: red_min,
-        max: red_max,
-        gamma: red_gamma,
-    };
-    let green_stretch = StretchParams {
-        min: green_min,
-        max: green_max,
-        gamma: green_gamma,
-    };
-    let blue_stretch = StretchParams {
-        min: blue_min,
-        max: blue_max,
-        gamma: blue_gamma,
-    };
-
-    extract_pixel_rgb_tile(
-        &dataset,
-        &request,
-        red_band,
-        green_band,
-        blue_band,
-        &red_stretch,
-        &green_stretch,
-        &blue_stretch,
-    )
+    let red_stretch = StretchParams::new(red_min, red_max, red_gamma, red_mode.unwrap_or_default());
+    let green_stretch = StretchParams::new(
+        green_min,
+        green_max,
+        green_gamma,
+        green_mode.unwrap_or_default(),
+    );
+    let blue_stretch = StretchParams::new(
+        blue_min,
+        blue_max,
+        blue_gamma,
+        blue_mode.unwrap_or_default(),
+    );
+
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "pixel_rgb",
+            request.tile_size,
+            request.format,
+            [red_band, green_band, blue_band],
+            [&red_stretch, &green_stretch, &blue_stretch],
+            request.resampling,
+            &nodata,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let dataset = state.get_dataset(&id)?;
+        let red_stretch =
+            with_contrast_lut(&luts, &dataset, &id, red_band, &red_stretch, &request)?;
+        let green_stretch =
+            with_contrast_lut(&luts, &dataset, &id, green_band, &green_stretch, &request)?;
+        let blue_stretch =
+            with_contrast_lut(&luts, &dataset, &id, blue_band, &blue_stretch, &request)?;
+        extract_pixel_rgb_tile(
+            &dataset,
+            &request,
+            red_band,
+            green_band,
+            blue_band,
+            &red_stretch,
+            &green_stretch,
+            &blue_stretch,
+        )
+    })
 }
 
 /// Get a cross-layer RGB tile for non-georeferenced images (using pixel coordinates)
@@ -704,80 +1564,272 @@ pub async fn get_cross_layer_pixel_rgb_tile(
     green_band: i32,
     blue_id: String,
     blue_band: i32,
+    slice: Option<DimensionSlice>,
     x: i32,
     y: i32,
     z: u8,
     red_min: f64,
     red_max: f64,
     red_gamma: f64,
+    red_mode: Option<StretchMode>,
     green_min: f64,
     green_max: f64,
     green_gamma: f64,
+    green_mode: Option<StretchMode>,
     blue_min: f64,
     blue_max: f64,
     blue_gamma: f64,
+    blue_mode: Option<StretchMode>,
+    resampling: Option<Resampling>,
+    nodata: Option<NodataRules>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    format: Option<TileFormat>,
     state: State<'_, DatasetCache>,
-) -> Result<Vec<u8>, String> {
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<Response, String> {
     use crate::gdal::tile_extractor::{
         extract_cross_layer_pixel_rgb_tile, StretchParams, TileRequest,
     };
 
-    let red_path = state.get_path(&red_id).ok_or("Red dataset not found")?;
-    let green_path = state.get_path(&green_id).ok_or("Green dataset not found")?;
-    let blue_path = state.get_path(&blue_id).ok_or("Blue dataset not found")?;
-
-    let red_ds =
-        Dataset::open(&red_path).map_err(|e| format!("Failed to open red raster: {}", e))?;
-    let green_ds =
-        Dataset::open(&green_path).map_err(|e| format!("Failed to open green raster: {}", e))?;
-    let blue_ds =
-        Dataset::open(&blue_path).map_err(|e| format!("Failed to open blue raster: {}", e))?;
+    // Each layer counts band numbers within its own bands of the slice
+    let slice = slice.as_ref();
+    let red_band = resolve_slice_band(&state, &red_id, red_band as usize, slice)
+        .map_err(|e| format!("Red layer: {}", e))? as i32;
+    let green_band = resolve_slice_band(&state, &green_id, green_band as usize, slice)
+        .map_err(|e| format!("Green layer: {}", e))? as i32;
+    let blue_band = resolve_slice_band(&state, &blue_id, blue_band as usize, slice)
+        .map_err(|e| format!("Blue layer: {}", e))? as i32;
 
+    let nodata = nodata.unwrap_or_default();
     let request = TileRequest {
         x,
         y,
         z,
         band: 1,
-        tile_size: 256,
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: format.unwrap_or_default(),
+        nodata: &nodata,
+        complex: ComplexPart::default(),
     };
 
-    let red_stretch = StretchParams {
-        min: red_min,
-        max: red_max,
-        gamma: red_gamma,
-    };
-    let green_stretch = StretchParams {
-        min: green_min,
-        max: green_max,
-        gamma: green_gamma,
+    let red_stretch = StretchParams::new(red_min, red_max, red_gamma, red_mode.unwrap_or_default());
+    let green_stretch = StretchParams::new(
+        green_min,
+        green_max,
+        green_gamma,
+        green_mode.unwrap_or_default(),
+    );
+    let blue_stretch = StretchParams::new(
+        blue_min,
+        blue_max,
+        blue_gamma,
+        blue_mode.unwrap_or_default(),
+    );
+
+    let key = TileCacheKey::new(
+        &[&red_id, &green_id, &blue_id],
+        z,
+        x,
+        y,
+        &(
+            "cross_pixel_rgb",
+            request.tile_size,
+            request.format,
+            [red_band, green_band, blue_band],
+            [&red_stretch, &green_stretch, &blue_stretch],
+            request.resampling,
+            &nodata,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let red_ds = state
+            .get_dataset(&red_id)
+            .map_err(|e| format!("Red layer: {}", e))?;
+        let green_ds = state
+            .get_dataset(&green_id)
+            .map_err(|e| format!("Green layer: {}", e))?;
+        let blue_ds = state
+            .get_dataset(&blue_id)
+            .map_err(|e| format!("Blue layer: {}", e))?;
+        let red_stretch =
+            with_contrast_lut(&luts, &red_ds, &red_id, red_band, &red_stretch, &request)?;
+        let green_stretch = with_contrast_lut(
+            &luts,
+            &green_ds,
+            &green_id,
+            green_band,
+            &green_stretch,
+            &request,
+        )?;
+        let blue_stretch = with_contrast_lut(
+            &luts,
+            &blue_ds,
+            &blue_id,
+            blue_band,
+            &blue_stretch,
+            &request,
+        )?;
+
+        extract_cross_layer_pixel_rgb_tile(
+            &red_ds,
+            red_band,
+            &green_ds,
+            green_band,
+            &blue_ds,
+            blue_band,
+            &request,
+            &red_stretch,
+            &green_stretch,
+            &blue_stretch,
+        )
+    })
+}
+
+/// Get the warped, unstretched values of one or more bands as a data tile
+///
+/// Lets the frontend stretch tiles itself, so dragging min/max/gamma never
+/// re-runs the warp. Masked pixels and values matching the layer's `nodata`
+/// rules are NaN; the payload layout is described on `encode_data_tile`.
+/// For multidimensional rasters the band numbers count within the bands
+/// `slice` leaves free, so a slice of every dimension draws with `bands: [1]`.
+#[tauri::command]
+pub async fn get_data_tile(
+    id: String,
+    x: i32,
+    y: i32,
+    z: u8,
+    bands: Vec<usize>,
+    slice: Option<DimensionSlice>,
+    resampling: Option<Resampling>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    state: State<'_, DatasetCache>,
+    tile_cache: State<'_, TileCache>,
+) -> Result<Response, String> {
+    let bands = bands
+        .into_iter()
+        .map(|band| resolve_slice_band(&state, &id, band, slice.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let nodata = nodata.unwrap_or_default();
+    let request = TileRequest {
+        x,
+        y,
+        z,
+        band: 1, // Not used directly
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: TileFormat::default(), // Not used: data tiles are not images
+        nodata: &nodata,
+        complex: complex.unwrap_or_default(),
     };
-    let blue_stretch = StretchParams {
-        min: blue_min,
-        max: blue_max,
-        gamma: blue_gamma,
+
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "data",
+            request.tile_size,
+            &bands,
+            request.complex,
+            request.resampling,
+            &nodata,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let dataset = state.get_dataset(&id)?;
+        extract_data_tile(&dataset, &request, &bands)
+    })
+}
+
+/// Get a data tile for non-georeferenced images (using pixel coordinates)
+#[tauri::command]
+pub async fn get_pixel_data_tile(
+    id: String,
+    x: i32,
+    y: i32,
+    z: u8,
+    bands: Vec<usize>,
+    slice: Option<DimensionSlice>,
+    resampling: Option<Resampling>,
+    nodata: Option<NodataRules>,
+    complex: Option<ComplexPart>,
+    tile_size: Option<usize>,
+    pixel_ratio: Option<f64>,
+    state: State<'_, DatasetCache>,
+    tile_cache: State<'_, TileCache>,
+) -> Result<Response, String> {
+    let bands = bands
+        .into_iter()
+        .map(|band| resolve_slice_band(&state, &id, band, slice.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let nodata = nodata.unwrap_or_default();
+    let request = TileRequest {
+        x,
+        y,
+        z,
+        band: 1, // Not used directly
+        tile_size: resolve_tile_size(tile_size, pixel_ratio)?,
+        resampling: resampling.unwrap_or_default(),
+        format: TileFormat::default(), // Not used: data tiles are not images
+        nodata: &nodata,
+        complex: complex.unwrap_or_default(),
     };
 
-    extract_cross_layer_pixel_rgb_tile(
-        &red_ds,
-        red_band,
-        &green_ds,
-        green_band,
-        &blue_ds,
-        blue_band,
-        &request,
-        &red_stretch,
-        &green_stretch,
-        &blue_stretch,
-    )
+    let key = TileCacheKey::new(
+        &[&id],
+        z,
+        x,
+        y,
+        &(
+            "pixel_data",
+            request.tile_size,
+            &bands,
+            request.complex,
+            request.resampling,
+            &nodata,
+        ),
+    );
+    cached_tile(&tile_cache, key, || {
+        let dataset = state.get_dataset(&id)?;
+        extract_pixel_data_tile(&dataset, &request, &bands)
+    })
 }
 
 /// Close a dataset and remove from cache
+///
+/// Pooled handles for the layer are dropped by each worker thread on its next
+/// dataset access, and its rendered tiles are evicted from the tile cache.
+/// Band-math layers also delete their computed GeoTIFF.
 #[tauri::command]
-pub async fn close_dataset(id: String, state: State<'_, DatasetCache>) -> Result<(), String> {
+pub async fn close_dataset(
+    id: String,
+    state: State<'_, DatasetCache>,
+    tile_cache: State<'_, TileCache>,
+    luts: State<'_, LutCache>,
+) -> Result<(), String> {
     state.remove(&id);
+    tile_cache.invalidate_layer(&id);
+    luts.invalidate_layer(&id);
     Ok(())
 }
 
+/// Get rendered-tile cache usage (hits, misses, memory)
+#[tauri::command]
+pub async fn get_tile_cache_stats(
+    tile_cache: State<'_, TileCache>,
+) -> Result<TileCacheStats, String> {
+    Ok(tile_cache.stats())
+}
+
 /// Pixel value query result
 #[derive(Clone, Serialize, Deserialize)]
 pub struct PixelValueResult {
@@ -785,6 +1837,7 @@ pub struct PixelValueResult {
     pub y: i32,
     pub values: Vec<PixelBandValue>,
     pub is_valid: bool,
+    pub terrain: Option<TerrainValue>, // derived from `terrain_band` when requested
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -792,18 +1845,138 @@ pub struct PixelBandValue {
     pub band: usize,
     pub value: f64,
     pub is_nodata: bool,
+    pub color: Option<[u8; 4]>, // class colour (RGBA) for paletted bands
+    pub complex: Option<ComplexValue>, // real/imaginary parts of complex bands
+}
+
+/// Slope and aspect of a DEM at a queried pixel
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TerrainValue {
+    pub band: usize,
+    pub slope_degrees: f64,
+    pub slope_percent: f64,
+    pub aspect: Option<f64>, // None on flat ground
+}
+
+/// Read one band's value at a pixel, and whether it is nodata according to
+/// the band's mask and the layer's rules. Complex bands also return their
+/// parts, with the amplitude as the value.
+fn read_pixel(
+    band: &RasterBand,
+    pixel_x: i32,
+    pixel_y: i32,
+    rules: &NodataRules,
+) -> Result<(f64, bool, Option<ComplexValue>), String> {
+    let window = (pixel_x as isize, pixel_y as isize);
+    let complex = if is_complex(band) {
+        let [re, im] = read_complex(band, window, (1, 1), (1, 1), None)?[0];
+        Some(ComplexValue::new(re, im))
+    } else {
+        None
+    };
+    let value = match complex {
+        Some(complex) => complex.amplitude,
+        None => band
+            .read_as::<f64>(window, (1, 1), (1, 1), None)
+            .map_err(|e| format!("Failed to read pixel value: {}", e))?
+            .data()[0],
+    };
+
+    let masked = read_mask(band, window, (1, 1), (1, 1))?.is_some_and(|mask| !mask[0]);
+    Ok((value, masked || !rules.is_valid(value), complex))
+}
+
+/// Compute slope and aspect at a pixel from its 3x3 neighbourhood.
+///
+/// Cell sizes come from the geotransform; for geographic rasters they are in
+/// degrees and are converted to metres at `geographic_lat` so slopes are not
+/// off by a factor of ~100 000.
+fn terrain_at_pixel(
+    dataset: &Dataset,
+    band_idx: usize,
+    pixel_x: i32,
+    pixel_y: i32,
+    gt: &[f64; 6],
+    geographic_lat: Option<f64>,
+    rules: &NodataRules,
+) -> Result<Option<TerrainValue>, String> {
+    let band = dataset
+        .rasterband(band_idx)
+        .map_err(|e| format!("Failed to get band {}: {}", band_idx, e))?;
+    let (width, height) = dataset.raster_size();
+
+    // Read the window clipped to the raster; cells outside it stay NaN and
+    // fall back to the centre value
+    let x0 = (pixel_x - 1).max(0);
+    let y0 = (pixel_y - 1).max(0);
+    let x1 = (pixel_x + 1).min(width as i32 - 1);
+    let y1 = (pixel_y + 1).min(height as i32 - 1);
+    let (w, h) = ((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+    let values = read_masked(&band, (x0 as isize, y0 as isize), (w, h), (w, h), None)
+        .map_err(|e| format!("Failed to read pixel window: {}", e))?;
+
+    let mut grid = [f64::NAN; 9];
+    for (i, &value) in values.iter().enumerate() {
+        let col = (x0 - pixel_x + 1) as usize + i % w;
+        let row = (y0 - pixel_y + 1) as usize + i / w;
+        grid[row * 3 + col] = value;
+    }
+
+    let [size_x, size_y] = pixel_size(gt);
+    let (cell_x, cell_y) = match geographic_lat {
+        Some(lat) => geographic_cell_size(size_x, size_y, lat),
+        None => (size_x, size_y),
+    };
+
+    let gradient = map_neighbourhood(
+        &grid,
+        1,
+        |v| rules.is_valid(v),
+        |_, window| Gradient::horn(window, cell_x, cell_y),
+    )
+    .swap_remove(0);
+
+    Ok(gradient.map(|gradient| TerrainValue {
+        band: band_idx,
+        slope_degrees: gradient.slope_degrees(),
+        slope_percent: gradient.slope_percent(),
+        aspect: gradient.aspect(),
+    }))
+}
+
+/// Bands a pixel query reads: those of `slice` for multidimensional rasters,
+/// otherwise all of them
+fn query_bands(
+    state: &DatasetCache,
+    id: &str,
+    band_count: usize,
+    slice: Option<&DimensionSlice>,
+) -> Result<Vec<usize>, String> {
+    match slice {
+        Some(slice) => state.slice_bands(id, slice),
+        None => Ok((1..=band_count).collect()),
+    }
 }
 
 /// Query pixel values at a specific geographic coordinate
+///
+/// With `terrain_band` the slope and aspect of that band (a DEM) are returned
+/// alongside the raw values. Values are flagged as nodata from the dataset's
+/// masks and the layer's `nodata` rules. For multidimensional rasters a
+/// `slice` narrows the values to the selected time/depth/level; dimensions it
+/// leaves out are returned in full, e.g. a time series at a fixed depth.
 #[tauri::command]
 pub async fn query_pixel_value(
     id: String,
     lng: f64,
     lat: f64,
+    terrain_band: Option<usize>,
+    nodata: Option<NodataRules>,
+    slice: Option<DimensionSlice>,
     state: State<'_, DatasetCache>,
 ) -> Result<PixelValueResult, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    let dataset = state.get_dataset(&id)?;
+    let rules = nodata.unwrap_or_default();
 
     let gt = dataset
         .geo_transform()
@@ -813,45 +1986,17 @@ pub async fn query_pixel_value(
     let (width, height) = dataset.raster_size();
 
     // Transform coordinates from EPSG:4326 to native CRS if needed
-    let (native_x, native_y) = if !projection.is_empty() {
-        let mut source_srs = SpatialRef::from_epsg(4326)
-            .map_err(|e| format!("Failed to create EPSG:4326 SRS: {}", e))?;
-
-        let mut target_srs = SpatialRef::from_wkt(&projection)
-            .map_err(|e| format!("Failed to parse target SRS: {}", e))?;
-
-        if !target_srs.is_geographic() {
-            // Set axis mapping to traditional GIS order (lng, lat) not (lat, lng)
-            source_srs.set_axis_mapping_strategy(
-                gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder,
-            );
-            target_srs.set_axis_mapping_strategy(
-                gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder,
-            );
-
-            let transform = CoordTransform::new(&source_srs, &target_srs)
-                .map_err(|e| format!("Failed to create coordinate transform: {}", e))?;
-
-            let mut xs = vec![lng];
-            let mut ys = vec![lat];
-
-            transform
-                .transform_coords(&mut xs, &mut ys, &mut [])
-                .map_err(|e| format!("Failed to transform coordinates: {}", e))?;
+    let (mut xs, mut ys) = ([lng], [lat]);
+    if let Some(transform) = native_transform(&dataset)? {
+        transform
+            .transform_coords(&mut xs, &mut ys, &mut [])
+            .map_err(|e| format!("Failed to transform coordinates: {}", e))?;
+    }
 
-            (xs[0], ys[0])
-        } else {
-            (lng, lat)
-        }
-    } else {
-        (lng, lat)
-    };
+    let (native_x, native_y) = (xs[0], ys[0]);
 
-    // Convert geographic coordinates to pixel coordinates
-    // Inverse geotransform: pixel_x = (geo_x - gt[0]) / gt[1]
-    //                       pixel_y = (geo_y - gt[3]) / gt[5]
-    let pixel_x = ((native_x - gt[0]) / gt[1]).floor() as i32;
-    let pixel_y = ((native_y - gt[3]) / gt[5]).floor() as i32;
+    // Inverse geotransform, rotation terms included
+    let (pixel_x, pixel_y) = native_to_pixel(&gt, native_x, native_y)?;
 
     // Check if pixel is within bounds
     if pixel_x < 0 || pixel_x >= width as i32 || pixel_y < 0 || pixel_y >= height as i32 {
@@ -860,40 +2005,53 @@ pub async fn query_pixel_value(
             y: pixel_y,
             values: vec![],
             is_valid: false,
+            terrain: None,
         });
     }
 
-    // Read values from all bands at this pixel
-    let band_count = dataset.raster_count();
+    // Read values from the slice's bands (all bands without one) at this pixel
+    let band_indices = query_bands(&state, &id, dataset.raster_count(), slice.as_ref())?;
     let mut values = Vec::new();
 
-    for band_idx in 1..=band_count {
+    for band_idx in band_indices {
         let band = dataset
             .rasterband(band_idx)
             .map_err(|e| format!("Failed to get band {}: {}", band_idx, e))?;
 
-        let nodata = band.no_data_value();
-
-        // Read single pixel
-        let buffer = band
-            .read_as::<f64>((pixel_x as isize, pixel_y as isize), (1, 1), (1, 1), None)
-            .map_err(|e| format!("Failed to read pixel value: {}", e))?;
-
-        let value = buffer.data()[0];
-        let is_nodata = nodata.is_some_and(|nd| (value - nd).abs() < 1e-10);
+        let (value, is_nodata, complex) = read_pixel(&band, pixel_x, pixel_y, &rules)?;
+        let color = read_palette(&band).and_then(|palette| palette_color(&palette, value));
 
         values.push(PixelBandValue {
             band: band_idx,
             value,
             is_nodata,
+            color,
+            complex,
         });
     }
 
+    let terrain = match terrain_band {
+        Some(band_idx) => {
+            let geographic = SpatialRef::from_wkt(&projection).is_ok_and(|srs| srs.is_geographic());
+            terrain_at_pixel(
+                &dataset,
+                band_idx,
+                pixel_x,
+                pixel_y,
+                &gt,
+                geographic.then_some(native_y),
+                &rules,
+            )?
+        }
+        None => None,
+    };
+
     Ok(PixelValueResult {
         x: pixel_x,
         y: pixel_y,
         values,
         is_valid: true,
+        terrain,
     })
 }
 
@@ -918,51 +2076,57 @@ pub struct ProfileResult {
     pub elevation_loss: f64,
 }
 
+/// Value of a profile sample, or `None` outside the raster and where the
+/// band's mask or the layer's rules mark the pixel as nodata
+fn profile_value(
+    band: &RasterBand,
+    pixel_x: i32,
+    pixel_y: i32,
+    rules: &NodataRules,
+) -> Result<Option<f64>, String> {
+    let (width, height) = band.size();
+    if pixel_x < 0 || pixel_x >= width as i32 || pixel_y < 0 || pixel_y >= height as i32 {
+        return Ok(None);
+    }
+
+    let value = read_masked(
+        band,
+        (pixel_x as isize, pixel_y as isize),
+        (1, 1),
+        (1, 1),
+        None,
+    )
+    .map_err(|e| format!("Failed to read: {}", e))?[0];
+    Ok(rules.is_valid(value).then_some(value))
+}
+
 /// Get elevation profile along a line
+///
+/// Values come from band 1, or for multidimensional rasters the band at
+/// `slice`. Samples are invalid where the dataset's masks or the layer's
+/// `nodata` rules hide them, as in tiles and statistics.
 #[tauri::command]
 pub async fn get_elevation_profile(
     id: String,
     coords: Vec<[f64; 2]>, // Array of [lng, lat] pairs
     num_samples: Option<usize>,
+    nodata: Option<NodataRules>,
+    slice: Option<DimensionSlice>,
     state: State<'_, DatasetCache>,
 ) -> Result<ProfileResult, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    let dataset = state.get_dataset(&id)?;
+    let rules = nodata.unwrap_or_default();
 
     let gt = dataset
         .geo_transform()
         .map_err(|e| format!("Failed to get geotransform: {}", e))?;
 
-    let projection = dataset.projection();
-    let (width, height) = dataset.raster_size();
     let band = dataset
-        .rasterband(1)
+        .rasterband(resolve_band(&state, &id, None, slice.as_ref())? as usize)
         .map_err(|e| format!("Failed to get band: {}", e))?;
-    let nodata = band.no_data_value();
 
-    // Create coordinate transform if needed
-    let needs_transform = !projection.is_empty() && {
-        let srs = SpatialRef::from_wkt(&projection).ok();
-        srs.is_some_and(|s| !s.is_geographic())
-    };
-
-    let transform = if needs_transform {
-        let mut source_srs = SpatialRef::from_epsg(4326)
-            .map_err(|e| format!("Failed to create EPSG:4326 SRS: {}", e))?;
-        let mut target_srs = SpatialRef::from_wkt(&projection)
-            .map_err(|e| format!("Failed to parse target SRS: {}", e))?;
-        // Set axis mapping to traditional GIS order (lng, lat) not (lat, lng)
-        source_srs
-            .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
-        target_srs
-            .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
-        Some(
-            CoordTransform::new(&source_srs, &target_srs)
-                .map_err(|e| format!("Failed to create transform: {}", e))?,
-        )
-    } else {
-        None
-    };
+    // Coordinate transform, if needed
+    let transform = native_transform(&dataset)?;
 
     // Calculate total distance and sample points along the line
     let samples = num_samples.unwrap_or(100);
@@ -1035,24 +2199,12 @@ pub async fn get_elevation_profile(
         };
 
         // Convert to pixel coordinates
-        let pixel_x = ((native_x - gt[0]) / gt[1]).floor() as i32;
-        let pixel_y = ((native_y - gt[3]) / gt[5]).floor() as i32;
-
-        let (elevation, is_valid) =
-            if pixel_x >= 0 && pixel_x < width as i32 && pixel_y >= 0 && pixel_y < height as i32 {
-                let buffer = band
-                    .read_as::<f64>((pixel_x as isize, pixel_y as isize), (1, 1), (1, 1), None)
-                    .map_err(|e| format!("Failed to read: {}", e))?;
-                let value = buffer.data()[0];
-                let is_nodata = nodata.is_some_and(|nd| (value - nd).abs() < 1e-10);
-                if is_nodata {
-                    (0.0, false)
-                } else {
-                    (value, true)
-                }
-            } else {
-                (0.0, false)
-            };
+        let (pixel_x, pixel_y) = native_to_pixel(&gt, native_x, native_y)?;
+
+        let (elevation, is_valid) = match profile_value(&band, pixel_x, pixel_y, &rules)? {
+            Some(value) => (value, true),
+            None => (0.0, false),
+        };
 
         if is_valid {
             min_elev = min_elev.min(elevation);
@@ -1116,16 +2268,16 @@ pub async fn get_elevation_profile_pixels(
     id: String,
     pixel_coords: Vec<[i32; 2]>, // Array of [x, y] pixel pairs
     num_samples: Option<usize>,
+    nodata: Option<NodataRules>,
+    slice: Option<DimensionSlice>,
     state: State<'_, DatasetCache>,
 ) -> Result<ProfileResult, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    let dataset = state.get_dataset(&id)?;
+    let rules = nodata.unwrap_or_default();
 
-    let (width, height) = dataset.raster_size();
     let band = dataset
-        .rasterband(1)
+        .rasterband(resolve_band(&state, &id, None, slice.as_ref())? as usize)
         .map_err(|e| format!("Failed to get band: {}", e))?;
-    let nodata = band.no_data_value();
 
     // Calculate total distance and sample points along the line
     let samples = num_samples.unwrap_or(100);
@@ -1191,21 +2343,10 @@ pub async fn get_elevation_profile_pixels(
             (x.round() as i32, y.round() as i32)
         };
 
-        let (elevation, is_valid) =
-            if pixel_x >= 0 && pixel_x < width as i32 && pixel_y >= 0 && pixel_y < height as i32 {
-                let buffer = band
-                    .read_as::<f64>((pixel_x as isize, pixel_y as isize), (1, 1), (1, 1), None)
-                    .map_err(|e| format!("Failed to read: {}", e))?;
-                let value = buffer.data()[0];
-                let is_nodata = nodata.is_some_and(|nd| (value - nd).abs() < 1e-10);
-                if is_nodata {
-                    (0.0, false)
-                } else {
-                    (value, true)
-                }
-            } else {
-                (0.0, false)
-            };
+        let (elevation, is_valid) = match profile_value(&band, pixel_x, pixel_y, &rules)? {
+            Some(value) => (value, true),
+            None => (0.0, false),
+        };
 
         if is_valid {
             min_elev = min_elev.min(elevation);
@@ -1247,10 +2388,12 @@ pub async fn query_pixel_value_at_pixel(
     id: String,
     pixel_x: i32,
     pixel_y: i32,
+    nodata: Option<NodataRules>,
+    slice: Option<DimensionSlice>,
     state: State<'_, DatasetCache>,
 ) -> Result<PixelValueResult, String> {
-    let path = state.get_path(&id).ok_or("Dataset not found")?;
-    let dataset = Dataset::open(&path).map_err(|e| format!("Failed to open raster: {}", e))?;
+    let dataset = state.get_dataset(&id)?;
+    let rules = nodata.unwrap_or_default();
 
     let (width, height) = dataset.raster_size();
 
@@ -1261,40 +2404,38 @@ pub async fn query_pixel_value_at_pixel(
             y: pixel_y,
             values: vec![],
             is_valid: false,
+            terrain: None,
         });
     }
 
-    // Read values from all bands at this pixel
-    let band_count = dataset.raster_count();
+    // Read values from the slice's bands (all bands without one) at this pixel
+    let band_indices = query_bands(&state, &id, dataset.raster_count(), slice.as_ref())?;
     let mut values = Vec::new();
 
-    for band_idx in 1..=band_count {
+    for band_idx in band_indices {
         let band = dataset
             .rasterband(band_idx)
             .map_err(|e| format!("Failed to get band {}: {}", band_idx, e))?;
 
-        let nodata = band.no_data_value();
-
-        // Read single pixel
-        let buffer = band
-            .read_as::<f64>((pixel_x as isize, pixel_y as isize), (1, 1), (1, 1), None)
-            .map_err(|e| format!("Failed to read pixel value: {}", e))?;
-
-        let value = buffer.data()[0];
-        let is_nodata = nodata.is_some_and(|nd| (value - nd).abs() < 1e-10);
+        let (value, is_nodata, complex) = read_pixel(&band, pixel_x, pixel_y, &rules)?;
+        let color = read_palette(&band).and_then(|palette| palette_color(&palette, value));
 
         values.push(PixelBandValue {
             band: band_idx,
             value,
             is_nodata,
+            color,
+            complex,
         });
     }
 
+    // No ground distances without a geotransform, so no slope/aspect
     Ok(PixelValueResult {
         x: pixel_x,
         y: pixel_y,
         values,
         is_valid: true,
+        terrain: None,
     })
 }
 
@@ -1305,7 +2446,8 @@ mod tests {
     #[test]
     fn test_histogram_bins_uniform_distribution() {
         let values: Vec<f64> = (0..100).map(|i| i as f64).collect();
-        let (counts, bin_edges) = compute_histogram_bins(&values, 0.0, 99.0, 10, None);
+        let (counts, bin_edges) =
+            compute_histogram_bins(&values, 0.0, 99.0, 10, &NodataRules::default());
 
         assert_eq!(counts.len(), 10);
         assert_eq!(bin_edges.len(), 11);
@@ -1322,13 +2464,12 @@ mod tests {
     #[test]
     fn test_histogram_bins_all_same_value() {
         let values = vec![42.0; 100];
-        let (counts, bin_edges) = compute_histogram_bins(&values, 42.0, 42.0, 10, None);
+        let (counts, bin_edges) =
+            compute_histogram_bins(&values, 42.0, 42.0, 10, &NodataRules::default());
 
         // When range is 0, all values go in first bin
         assert_eq!(counts[0], 100);
-        for i in 1..10 {
-            assert_eq!(counts[i], 0);
-        }
+        assert!(counts[1..].iter().all(|&count| count == 0));
 
         // Bin edges should all be the same value
         for edge in &bin_edges {
@@ -1339,7 +2480,11 @@ mod tests {
     #[test]
     fn test_histogram_bins_with_nodata() {
         let values = vec![1.0, 2.0, -9999.0, 3.0, -9999.0, 4.0, 5.0];
-        let (counts, _) = compute_histogram_bins(&values, 1.0, 5.0, 5, Some(-9999.0));
+        let rules = NodataRules {
+            values: vec![-9999.0],
+            range: None,
+        };
+        let (counts, _) = compute_histogram_bins(&values, 1.0, 5.0, 5, &rules);
 
         // Should only count 5 valid values (skip 2 nodata)
         let total: u64 = counts.iter().sum();
@@ -1349,7 +2494,7 @@ mod tests {
     #[test]
     fn test_histogram_bins_values_outside_range() {
         let values = vec![-10.0, 0.0, 5.0, 10.0, 100.0];
-        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, None);
+        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, &NodataRules::default());
 
         // Only values 0, 5, 10 are within range
         let total: u64 = counts.iter().sum();
@@ -1359,7 +2504,8 @@ mod tests {
     #[test]
     fn test_histogram_bins_single_value() {
         let values = vec![5.0];
-        let (counts, _bin_edges) = compute_histogram_bins(&values, 0.0, 10.0, 10, None);
+        let (counts, _bin_edges) =
+            compute_histogram_bins(&values, 0.0, 10.0, 10, &NodataRules::default());
 
         let total: u64 = counts.iter().sum();
         assert_eq!(total, 1);
@@ -1372,7 +2518,7 @@ mod tests {
     #[test]
     fn test_histogram_bins_min_max_edge_values() {
         let values = vec![0.0, 10.0];
-        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, None);
+        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, &NodataRules::default());
 
         // Min value should be in first bin
         assert!(counts[0] > 0);
@@ -1386,7 +2532,8 @@ mod tests {
     #[test]
     fn test_histogram_bin_edges_count() {
         let values = vec![0.0, 5.0, 10.0];
-        let (counts, bin_edges) = compute_histogram_bins(&values, 0.0, 10.0, 256, None);
+        let (counts, bin_edges) =
+            compute_histogram_bins(&values, 0.0, 10.0, 256, &NodataRules::default());
 
         // Should have bin_count bins and bin_count + 1 edges
         assert_eq!(counts.len(), 256);
@@ -1396,7 +2543,7 @@ mod tests {
     #[test]
     fn test_histogram_empty_values() {
         let values: Vec<f64> = vec![];
-        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, None);
+        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, &NodataRules::default());
 
         let total: u64 = counts.iter().sum();
         assert_eq!(total, 0);
@@ -1405,9 +2552,103 @@ mod tests {
     #[test]
     fn test_histogram_all_nodata() {
         let values = vec![-9999.0; 10];
-        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, Some(-9999.0));
+        let rules = NodataRules {
+            values: vec![-9999.0],
+            range: None,
+        };
+        let (counts, _) = compute_histogram_bins(&values, 0.0, 10.0, 10, &rules);
 
         let total: u64 = counts.iter().sum();
         assert_eq!(total, 0);
     }
+
+    #[test]
+    fn test_histogram_skips_masked_and_range() {
+        let values = vec![f64::NAN, 0.0, 1.0, 2.0, 250.0, 255.0];
+        let rules = NodataRules {
+            values: vec![],
+            range: Some([250.0, 255.0]),
+        };
+        let (counts, _) = compute_histogram_bins(&values, 0.0, 255.0, 16, &rules);
+
+        // Zero is data; NaN (masked) and the hidden range are not
+        let total: u64 = counts.iter().sum();
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn test_histogram_skips_nan_and_infinity() {
+        let values = vec![f64::NAN, f64::INFINITY, 1.0, f64::NEG_INFINITY, 9.0];
+        let (counts, _) = compute_histogram_bins(&values, 1.0, 9.0, 4, &NodataRules::default());
+        assert_eq!(counts, vec![1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_stats_from_values() {
+        let values = vec![2.0, 4.0, f64::NAN, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let stats = compute_stats_from_values(1, &values, &NodataRules::default()).unwrap();
+        assert_eq!(stats.min, 2.0);
+        assert_eq!(stats.max, 9.0);
+        assert!((stats.mean - 5.0).abs() < 1e-12);
+        assert!((stats.std_dev - 2.0).abs() < 1e-12);
+
+        let rules = NodataRules {
+            values: vec![2.0, 4.0, 5.0, 7.0, 9.0],
+            range: None,
+        };
+        assert!(compute_stats_from_values(1, &values, &rules).is_none());
+    }
+
+    #[test]
+    fn test_clamp_pixel_window() {
+        // Partly off the raster: clamped to its edges
+        let window = clamp_pixel_window(&[-10.5, 50.2], &[20.0, 120.0], 100, 100);
+        assert_eq!(window, Some(((0, 20), (51, 80))));
+
+        // Flipped y (north-up inverse geotransform) gives the same window
+        let window = clamp_pixel_window(&[10.0, 20.0], &[30.0, 10.0], 100, 100);
+        assert_eq!(window, Some(((10, 10), (10, 20))));
+
+        // Entirely outside
+        assert_eq!(
+            clamp_pixel_window(&[150.0, 200.0], &[0.0, 10.0], 100, 100),
+            None
+        );
+    }
+
+    #[test]
+    fn test_resolve_tile_size() {
+        assert_eq!(resolve_tile_size(None, None), Ok(256));
+        assert_eq!(resolve_tile_size(Some(512), None), Ok(512));
+        assert_eq!(resolve_tile_size(Some(256), Some(2.0)), Ok(512));
+        assert_eq!(resolve_tile_size(Some(256), Some(1.5)), Ok(384));
+
+        assert!(resolve_tile_size(Some(0), None).is_err());
+        assert!(resolve_tile_size(Some(4096), None).is_err());
+        assert!(resolve_tile_size(None, Some(f64::NAN)).is_err());
+        assert!(resolve_tile_size(None, Some(-1.0)).is_err());
+    }
+
+    #[test]
+    fn test_profile_value_masks_and_rules() {
+        let dataset = DriverManager::get_driver_by_name("MEM")
+            .unwrap()
+            .create_with_band_type::<f64, _>("", 3, 1, 1)
+            .unwrap();
+        let mut band = dataset.rasterband(1).unwrap();
+        let mut values = gdal::raster::Buffer::new((3, 1), vec![0.0, 5.0, 7.0]);
+        band.write((0, 0), (3, 1), &mut values).unwrap();
+        band.set_no_data_value(Some(7.0)).unwrap();
+        let rules = NodataRules {
+            values: vec![5.0],
+            range: None,
+        };
+
+        // Zero is data; the rules, the band's nodata and the edge are not
+        assert_eq!(profile_value(&band, 0, 0, &rules).unwrap(), Some(0.0));
+        assert_eq!(profile_value(&band, 1, 0, &rules).unwrap(), None);
+        assert_eq!(profile_value(&band, 2, 0, &rules).unwrap(), None);
+        assert_eq!(profile_value(&band, 3, 0, &rules).unwrap(), None);
+        assert_eq!(profile_value(&band, 0, -1, &rules).unwrap(), None);
+    }
 }
@@ -22,9 +22,12 @@
 //! remote COG files. COGs are accessed via the `/vsicurl/` virtual filesystem,
 //! which enables efficient tile-based streaming without downloading entire files.
 
+use crate::gdal::bounds::{dataset_geo_bounds, Footprint};
+use crate::gdal::colormap::read_palette;
 use crate::gdal::dataset_cache::DatasetCache;
-use gdal::spatial_ref::{CoordTransform, SpatialRef};
-use gdal::{Dataset, Metadata};
+use crate::gdal::geotransform::pixel_size;
+use crate::gdal::statistics::stored_range_statistics;
+use gdal::Dataset;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tauri::State;
@@ -1238,6 +1241,12 @@ pub async fn open_stac_asset(
         get_georef_info(&dataset)?;
     let mut projection = dataset.projection();
     let nodata = dataset.rasterband(1).ok().and_then(|b| b.no_data_value());
+    let palette = dataset.rasterband(1).ok().and_then(|b| read_palette(&b));
+    let overview_count = dataset
+        .rasterband(1)
+        .ok()
+        .and_then(|b| b.overview_count().ok())
+        .unwrap_or(0) as usize;
     let band_stats = get_default_band_stats(&dataset);
 
     // If the file has no internal georeferencing but we have a STAC bbox,
@@ -1303,6 +1312,11 @@ pub async fn open_stac_asset(
         nodata,
         band_stats,
         is_georeferenced,
+        palette,
+        dimensions: Vec::new(),
+        is_complex: false,
+        overview_count,
+        needs_overviews: false, // remote assets are read as they are
     };
 
     state.add(id, final_path);
@@ -1482,16 +1496,11 @@ fn get_georef_info(dataset: &Dataset) -> Result<([f64; 4], [f64; 4], [f64; 2], b
 
     if is_georeferenced {
         let gt = gt.unwrap();
-        let native_bounds = [
-            gt[0],                         // minx
-            gt[3] + height as f64 * gt[5], // miny (gt[5] is negative)
-            gt[0] + width as f64 * gt[1],  // maxx
-            gt[3],                         // maxy
-        ];
+        let native_bounds = Footprint::grid(gt, width, height).bounds();
 
         // Transform to EPSG:4326
-        let bounds = transform_bounds_to_4326(dataset, native_bounds)?;
-        let pixel_size = [gt[1].abs(), gt[5].abs()];
+        let bounds = dataset_geo_bounds(dataset)?;
+        let pixel_size = pixel_size(&gt);
 
         Ok((bounds, native_bounds, pixel_size, true))
     } else {
@@ -1501,87 +1510,33 @@ fn get_georef_info(dataset: &Dataset) -> Result<([f64; 4], [f64; 4], [f64; 2], b
     }
 }
 
-/// Transform bounds from native CRS to EPSG:4326
-fn transform_bounds_to_4326(
-    dataset: &Dataset,
-    native_bounds: [f64; 4],
-) -> Result<[f64; 4], String> {
-    let projection = dataset.projection();
-    if projection.is_empty() {
-        return Ok(native_bounds);
-    }
-
-    let mut source_srs =
-        SpatialRef::from_wkt(&projection).map_err(|e| format!("Invalid projection: {}", e))?;
-
-    let mut target_srs =
-        SpatialRef::from_epsg(4326).map_err(|e| format!("Failed to create EPSG:4326: {}", e))?;
-
-    // Set axis mapping to traditional GIS order (lon, lat)
-    source_srs
-        .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
-    target_srs
-        .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
-
-    let transform = CoordTransform::new(&source_srs, &target_srs)
-        .map_err(|e| format!("Failed to create transform: {}", e))?;
-
-    // Transform corner points
-    let mut xs = vec![
-        native_bounds[0],
-        native_bounds[2],
-        native_bounds[0],
-        native_bounds[2],
-    ];
-    let mut ys = vec![
-        native_bounds[1],
-        native_bounds[1],
-        native_bounds[3],
-        native_bounds[3],
-    ];
-
-    transform
-        .transform_coords(&mut xs, &mut ys, &mut [])
-        .map_err(|e| format!("Failed to transform coordinates: {}", e))?;
-
-    // Find bounding box of transformed corners
-    let minx = xs.iter().cloned().fold(f64::INFINITY, f64::min);
-    let maxx = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let miny = ys.iter().cloned().fold(f64::INFINITY, f64::min);
-    let maxy = ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-
-    Ok([minx, miny, maxx, maxy])
-}
-
 /// Get band stats for remote files
-/// Tries to read statistics from raster metadata (fast, no pixel I/O)
-/// Falls back to data-type based defaults if metadata not available
+/// Uses statistics stored in raster metadata (fast, no pixel I/O), often
+/// embedded in COG files; falls back to data-type based defaults
 fn get_default_band_stats(dataset: &Dataset) -> Vec<BandStats> {
     use gdal::raster::GdalDataType;
 
+    let (width, height) = dataset.raster_size();
     let mut stats = Vec::new();
 
     for i in 1..=dataset.raster_count() {
         if let Ok(band) = dataset.rasterband(i) {
-            // Try to get statistics from metadata (STATISTICS_MINIMUM, STATISTICS_MAXIMUM)
-            // These are often embedded in COG files and don't require reading pixel data
-            let metadata_stats = get_band_metadata_stats(&band);
-
-            let (min, max, mean, std_dev) =
-                if let Some((m_min, m_max, m_mean, m_std)) = metadata_stats {
-                    (m_min, m_max, m_mean, m_std)
-                } else {
-                    // Fall back to data-type based defaults
-                    match band.band_type() {
-                        GdalDataType::UInt8 => (0.0, 255.0, 128.0, 64.0),
-                        GdalDataType::Int8 => (-128.0, 127.0, 0.0, 64.0),
-                        GdalDataType::UInt16 => (0.0, 10000.0, 3000.0, 2000.0),
-                        GdalDataType::Int16 => (-10000.0, 10000.0, 0.0, 2000.0),
-                        GdalDataType::UInt32 => (0.0, 10000.0, 3000.0, 2000.0),
-                        GdalDataType::Float32 | GdalDataType::Float64 => (0.0, 1.0, 0.3, 0.2),
-                        _ => (0.0, 10000.0, 3000.0, 2000.0),
-                    }
-                };
+            // COGs often carry statistics, or at least a range, so no pixels
+            // need to be read
+            if let Some(stored) = stored_range_statistics(&band) {
+                stats.push(BandStats::from_gdal(i, stored, width, height));
+                continue;
+            }
+
+            let (min, max, mean, std_dev) = match band.band_type() {
+                GdalDataType::UInt8 => (0.0, 255.0, 128.0, 64.0),
+                GdalDataType::Int8 => (-128.0, 127.0, 0.0, 64.0),
+                GdalDataType::UInt16 => (0.0, 10000.0, 3000.0, 2000.0),
+                GdalDataType::Int16 => (-10000.0, 10000.0, 0.0, 2000.0),
+                GdalDataType::UInt32 => (0.0, 10000.0, 3000.0, 2000.0),
+                GdalDataType::Float32 | GdalDataType::Float64 => (0.0, 1.0, 0.3, 0.2),
+                _ => (0.0, 10000.0, 3000.0, 2000.0),
+            };
 
             stats.push(BandStats {
                 band: i,
@@ -1589,6 +1544,9 @@ fn get_default_band_stats(dataset: &Dataset) -> Vec<BandStats> {
                 max,
                 mean,
                 std_dev,
+                valid_count: None,
+                nodata_percent: None,
+                approximate: true,
             });
         }
     }
@@ -1596,34 +1554,6 @@ fn get_default_band_stats(dataset: &Dataset) -> Vec<BandStats> {
     stats
 }
 
-/// Try to extract statistics from band metadata
-/// Returns (min, max, mean, std_dev) if available
-fn get_band_metadata_stats(band: &gdal::raster::RasterBand) -> Option<(f64, f64, f64, f64)> {
-    // Try to get pre-computed statistics from metadata
-    let min: Option<f64> = band
-        .metadata_item("STATISTICS_MINIMUM", "")
-        .and_then(|s: String| s.parse::<f64>().ok());
-    let max: Option<f64> = band
-        .metadata_item("STATISTICS_MAXIMUM", "")
-        .and_then(|s: String| s.parse::<f64>().ok());
-    let mean: Option<f64> = band
-        .metadata_item("STATISTICS_MEAN", "")
-        .and_then(|s: String| s.parse::<f64>().ok());
-    let std_dev: Option<f64> = band
-        .metadata_item("STATISTICS_STDDEV", "")
-        .and_then(|s: String| s.parse::<f64>().ok());
-
-    // Only return if we have at least min and max
-    match (min, max) {
-        (Some(mi), Some(ma)) => {
-            let me = mean.unwrap_or((mi + ma) / 2.0);
-            let sd = std_dev.unwrap_or((ma - mi) / 4.0);
-            Some((mi, ma, me, sd))
-        }
-        _ => None,
-    }
-}
-
 // ============================================================================
 // Tests
 // ============================================================================
@@ -1974,6 +1904,9 @@ mod tests {
             max: 255.0,
             mean: 128.0,
             std_dev: 64.0,
+            valid_count: None,
+            nodata_percent: None,
+            approximate: true,
         };
 
         assert_eq!(stats.band, 1);
@@ -1996,6 +1929,11 @@ mod tests {
             band_stats: vec![],
             nodata: None,
             is_georeferenced: true,
+            palette: None,
+            dimensions: vec![],
+            is_complex: false,
+            overview_count: 0,
+            needs_overviews: false,
         };
 
         assert_eq!(metadata.id, "test-id");
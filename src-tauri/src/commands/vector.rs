@@ -1,3 +1,4 @@
+use crate::gdal::bounds::{footprint_to_4326, geo_bounds, Footprint};
 use chrono::{Datelike, Timelike};
 use gdal::spatial_ref::{CoordTransform, SpatialRef};
 use gdal::vector::LayerAccess;
@@ -12,7 +13,7 @@ pub struct VectorMetadata {
     pub name: String,
     pub feature_count: usize,
     pub geometry_type: String,
-    pub bounds: [f64; 4], // [minx, miny, maxx, maxy] in EPSG:4326
+    pub bounds: [f64; 4], // [minx, miny, maxx, maxy] in EPSG:4326, maxx > 180 across 180°
     pub fields: Vec<FieldInfo>,
 }
 
@@ -62,11 +63,17 @@ pub async fn open_vector(path: String) -> Result<VectorLayerData, String> {
         .map_err(|e| format!("Failed to get extent: {}", e))?;
 
     let native_bounds = [extent.MinX, extent.MinY, extent.MaxX, extent.MaxY];
-    let bounds = transform_vector_bounds(&layer, native_bounds)?;
+    let mut bounds = transform_vector_bounds(&layer, native_bounds)?;
 
     // Convert features to GeoJSON
     let geojson = convert_to_geojson(&dataset, 0)?;
 
+    // Features either side of the antimeridian give a near-global extent;
+    // their coordinates show whether they are really that spread out
+    if bounds[2] - bounds[0] > 180.0 {
+        bounds = geojson_bounds(&geojson).unwrap_or(bounds);
+    }
+
     let id = uuid::Uuid::new_v4().to_string();
 
     let metadata = VectorMetadata {
@@ -92,41 +99,54 @@ fn transform_vector_bounds(
         None => return Ok(native_bounds), // Assume already geographic
     };
 
-    if spatial_ref.is_geographic() {
-        return Ok(native_bounds);
+    footprint_to_4326(&spatial_ref, &Footprint::Extent(native_bounds))
+}
+
+/// Bounds of every position in a GeoJSON FeatureCollection, crossing the
+/// antimeridian where that gives the tighter range; None without positions
+fn geojson_bounds(geojson: &Value) -> Option<[f64; 4]> {
+    let mut lons = Vec::new();
+    let mut lats = Vec::new();
+    for feature in geojson["features"].as_array()? {
+        collect_geometry_positions(&feature["geometry"], &mut lons, &mut lats);
+    }
+
+    if lons.is_empty() {
+        None
+    } else {
+        Some(geo_bounds(&lons, &lats))
+    }
+}
+
+/// Collect the positions of a geometry, including geometry collections
+fn collect_geometry_positions(geometry: &Value, lons: &mut Vec<f64>, lats: &mut Vec<f64>) {
+    if let Some(geometries) = geometry["geometries"].as_array() {
+        for g in geometries {
+            collect_geometry_positions(g, lons, lats);
+        }
     }
+    collect_positions(&geometry["coordinates"], lons, lats);
+}
 
-    let mut target_srs =
-        SpatialRef::from_epsg(4326).map_err(|e| format!("Failed to create EPSG:4326: {}", e))?;
-    target_srs
-        .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
-
-    let transform = CoordTransform::new(&spatial_ref, &target_srs)
-        .map_err(|e| format!("Failed to create transform: {}", e))?;
-
-    let mut xs = vec![
-        native_bounds[0],
-        native_bounds[2],
-        native_bounds[0],
-        native_bounds[2],
-    ];
-    let mut ys = vec![
-        native_bounds[1],
-        native_bounds[1],
-        native_bounds[3],
-        native_bounds[3],
-    ];
-
-    transform
-        .transform_coords(&mut xs, &mut ys, &mut [])
-        .map_err(|e| format!("Failed to transform: {}", e))?;
-
-    let min_lon = xs.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_lon = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let min_lat = ys.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_lat = ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-
-    Ok([min_lon, min_lat, max_lon, max_lat])
+/// Collect positions from nested GeoJSON coordinate arrays
+fn collect_positions(coords: &Value, lons: &mut Vec<f64>, lats: &mut Vec<f64>) {
+    let Some(items) = coords.as_array() else {
+        return;
+    };
+    match (
+        items.first().and_then(Value::as_f64),
+        items.get(1).and_then(Value::as_f64),
+    ) {
+        (Some(lon), Some(lat)) => {
+            lons.push(lon);
+            lats.push(lat);
+        }
+        _ => {
+            for item in items {
+                collect_positions(item, lons, lats);
+            }
+        }
+    }
 }
 
 /// Convert OGR layer to GeoJSON FeatureCollection
@@ -0,0 +1,796 @@
+//! Raster calculator: parse band-math expressions such as `(b8 - b4) / (b8 + b4)`
+//! and evaluate them into a single-band Float32 GeoTIFF.
+//!
+//! `bN` refers to band N of the primary layer and `alias.bN` to band N of
+//! another layer passed in under that alias. Layers on a different grid are
+//! warped onto the primary layer's grid.
+
+use crate::gdal::nodata::read_masked;
+use crate::gdal::warp::warp_bands;
+use gdal::cpl::CslStringList;
+use gdal::raster::Buffer;
+use gdal::{Dataset, DriverManager};
+use gdal_sys::GDALResampleAlg;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Rows evaluated per block, bounding memory use for large rasters
+const BLOCK_ROWS: usize = 256;
+
+/// A band referenced by an expression
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BandRef {
+    /// Input alias, or `None` for the primary layer
+    pub layer: Option<String>,
+    /// 1-based band index
+    pub band: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Function {
+    Abs,
+    Sqrt,
+    Log,
+    Log10,
+    Exp,
+    Min,
+    Max,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "abs" => Some(Function::Abs),
+            "sqrt" => Some(Function::Sqrt),
+            "log" => Some(Function::Log),
+            "log10" => Some(Function::Log10),
+            "exp" => Some(Function::Exp),
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            _ => None,
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Function::Min | Function::Max => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Number(f64),
+    /// Index into [`Expression::band_refs`]
+    Band(usize),
+    Neg(Box<Expr>),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Call(Function, Vec<Expr>),
+}
+
+/// A parsed band-math expression
+#[derive(Clone, Debug)]
+pub struct Expression {
+    root: Expr,
+    band_refs: Vec<BandRef>,
+}
+
+impl Expression {
+    /// Parse an expression.
+    ///
+    /// Supports numbers, band references, `+ - * / ^`, comparisons
+    /// (`< <= > >= == !=`, giving 1 or 0), parentheses and the functions
+    /// `abs sqrt log log10 exp min max`.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            band_refs: Vec::new(),
+        };
+        let root = parser.comparison()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("Unexpected '{}' in expression", token));
+        }
+        if parser.band_refs.is_empty() {
+            return Err("Expression does not reference any band".to_string());
+        }
+        Ok(Self {
+            root,
+            band_refs: parser.band_refs,
+        })
+    }
+
+    /// Bands the expression reads, each listed once in order of first use
+    pub fn band_refs(&self) -> &[BandRef] {
+        &self.band_refs
+    }
+
+    /// Evaluate for one pixel; `values[i]` is the value of `band_refs()[i]`
+    pub fn eval(&self, values: &[f64]) -> f64 {
+        eval(&self.root, values)
+    }
+}
+
+fn eval(expr: &Expr, values: &[f64]) -> f64 {
+    let truth = |b: bool| if b { 1.0 } else { 0.0 };
+    match expr {
+        Expr::Number(n) => *n,
+        Expr::Band(i) => values[*i],
+        Expr::Neg(e) => -eval(e, values),
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (eval(a, values), eval(b, values));
+            match op {
+                BinaryOp::Add => a + b,
+                BinaryOp::Sub => a - b,
+                BinaryOp::Mul => a * b,
+                BinaryOp::Div => a / b,
+                BinaryOp::Pow => a.powf(b),
+                BinaryOp::Lt => truth(a < b),
+                BinaryOp::Le => truth(a <= b),
+                BinaryOp::Gt => truth(a > b),
+                BinaryOp::Ge => truth(a >= b),
+                BinaryOp::Eq => truth(a == b),
+                BinaryOp::Ne => truth(a != b),
+            }
+        }
+        Expr::Call(function, args) => {
+            let a = eval(&args[0], values);
+            match function {
+                Function::Abs => a.abs(),
+                Function::Sqrt => a.sqrt(),
+                Function::Log => a.ln(),
+                Function::Log10 => a.log10(),
+                Function::Exp => a.exp(),
+                Function::Min => a.min(eval(&args[1], values)),
+                Function::Max => a.max(eval(&args[1], values)),
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Ident(s) => write!(f, "{}", s),
+            Token::Op(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    const OPERATORS: [&str; 15] = [
+        "<=", ">=", "==", "!=", "+", "-", "*", "/", "^", "<", ">", "(", ")", ",", ".",
+    ];
+
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() || (c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())) {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            let number = rest[..end]
+                .parse()
+                .map_err(|_| format!("Invalid number '{}'", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let end = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..end].to_string()));
+            rest = &rest[end..];
+        } else if let Some(op) = OPERATORS.iter().find(|op| rest.starts_with(*op)) {
+            tokens.push(Token::Op(op));
+            rest = &rest[op.len()..];
+        } else {
+            return Err(format!("Unexpected character '{}' in expression", c));
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser; precedence from loosest to tightest is
+/// comparison, `+ -`, `* /`, unary minus, `^` (right-associative)
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    band_refs: Vec<BandRef>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Consume the operator if it is next
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(next)) if *next == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<(), String> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(match self.peek() {
+                Some(token) => format!("Expected '{}' but found '{}'", op, token),
+                None => format!("Expected '{}' at end of expression", op),
+            })
+        }
+    }
+
+    fn binary_level(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Result<Expr, String>,
+    ) -> Result<Expr, String> {
+        let mut lhs = operand(self)?;
+        'outer: loop {
+            for (symbol, op) in ops {
+                if self.eat(symbol) {
+                    lhs = Expr::Binary(*op, Box::new(lhs), Box::new(operand(self)?));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn comparison(&mut self) -> Result<Expr, String> {
+        self.binary_level(
+            &[
+                ("<=", BinaryOp::Le),
+                (">=", BinaryOp::Ge),
+                ("==", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+                ("<", BinaryOp::Lt),
+                (">", BinaryOp::Gt),
+            ],
+            Self::additive,
+        )
+    }
+
+    fn additive(&mut self) -> Result<Expr, String> {
+        self.binary_level(
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Sub)],
+            Self::multiplicative,
+        )
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, String> {
+        self.binary_level(&[("*", BinaryOp::Mul), ("/", BinaryOp::Div)], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat("-") {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Result<Expr, String> {
+        let base = self.primary()?;
+        if self.eat("^") {
+            // Right-associative, and binds tighter than a unary minus on its left
+            let exponent = self.unary()?;
+            return Ok(Expr::Binary(
+                BinaryOp::Pow,
+                Box::new(base),
+                Box::new(exponent),
+            ));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::Op("(")) => {
+                let expr = self.comparison()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                if self.eat("(") {
+                    return self.call(&name);
+                }
+                if self.eat(".") {
+                    return match self.next() {
+                        Some(Token::Ident(band)) => self.band(Some(name), &band),
+                        _ => Err(format!("Expected a band after '{}.'", name)),
+                    };
+                }
+                self.band(None, &name)
+            }
+            Some(token) => Err(format!("Unexpected '{}' in expression", token)),
+            None => Err("Unexpected end of expression".to_string()),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr, String> {
+        let function =
+            Function::from_name(name).ok_or_else(|| format!("Unknown function '{}'", name))?;
+
+        let mut args = vec![self.comparison()?];
+        while self.eat(",") {
+            args.push(self.comparison()?);
+        }
+        self.expect(")")?;
+
+        if args.len() != function.arity() {
+            return Err(format!(
+                "{}() takes {} argument(s), {} given",
+                name,
+                function.arity(),
+                args.len()
+            ));
+        }
+        Ok(Expr::Call(function, args))
+    }
+
+    fn band(&mut self, layer: Option<String>, name: &str) -> Result<Expr, String> {
+        let band = name
+            .strip_prefix('b')
+            .and_then(|n| n.parse::<usize>().ok())
+            .filter(|&n| n > 0)
+            .ok_or_else(|| format!("Unknown name '{}' (bands are b1, b2, ...)", name))?;
+
+        let band_ref = BandRef { layer, band };
+        let index = match self.band_refs.iter().position(|r| *r == band_ref) {
+            Some(index) => index,
+            None => {
+                self.band_refs.push(band_ref);
+                self.band_refs.len() - 1
+            }
+        };
+        Ok(Expr::Band(index))
+    }
+}
+
+/// Values of one band over a block of rows, with the band's nodata value
+type BandBlock = (Vec<f64>, Option<f64>);
+
+/// Datasets feeding one expression, grouped so each is read once per block
+struct Source<'a> {
+    dataset: &'a Dataset,
+    /// Bands to read from the dataset
+    bands: Vec<usize>,
+    /// Position in `Expression::band_refs` of each entry in `bands`
+    refs: Vec<usize>,
+    /// Whether the dataset shares the primary grid and can be read directly
+    aligned: bool,
+}
+
+/// Whether two datasets share size, geotransform and projection
+fn same_grid(a: &Dataset, b: &Dataset) -> bool {
+    let transforms_match = match (a.geo_transform(), b.geo_transform()) {
+        (Ok(ga), Ok(gb)) => ga.iter().zip(gb.iter()).all(|(x, y)| (x - y).abs() < 1e-9),
+        (Err(_), Err(_)) => true,
+        _ => false,
+    };
+    a.raster_size() == b.raster_size() && transforms_match && a.projection() == b.projection()
+}
+
+/// Lock file held in an instance's output directory for as long as it runs
+const LOCK_FILE: &str = ".lock";
+
+/// This instance's output directory and its held lock file
+struct OutputDir {
+    path: PathBuf,
+    _lock: File,
+}
+
+static OUTPUT_DIR: Mutex<Option<OutputDir>> = Mutex::new(None);
+
+/// Directory under which every running instance keeps its band-math outputs
+fn outputs_root() -> PathBuf {
+    std::env::temp_dir().join("heimdall-band-math")
+}
+
+/// Directory holding this instance's band-math GeoTIFFs, created on first
+/// use.
+///
+/// Each instance writes to a directory of its own and locks a file in it
+/// until it exits, so [`remove_stale_outputs`] can tell which directories
+/// are no longer in use.
+pub fn output_dir() -> Result<PathBuf, String> {
+    let mut dir = OUTPUT_DIR.lock().unwrap();
+    if let Some(dir) = dir.as_ref() {
+        return Ok(dir.path.clone());
+    }
+
+    let path = outputs_root().join(uuid::Uuid::new_v4().to_string());
+    std::fs::create_dir_all(&path)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+    let lock = File::create(path.join(LOCK_FILE))
+        .map_err(|e| format!("Failed to create temp directory lock: {}", e))?;
+    lock.try_lock()
+        .map_err(|e| format!("Failed to lock temp directory: {}", e))?;
+
+    *dir = Some(OutputDir {
+        path: path.clone(),
+        _lock: lock,
+    });
+    Ok(path)
+}
+
+/// Delete a band-math layer's GeoTIFF once the layer is closed; paths that
+/// are not band-math outputs are left alone.
+///
+/// Returns `false` if the file is still there, e.g. because another thread
+/// still has it open on a platform that refuses to delete open files
+/// (Windows).
+pub fn remove_output(path: &str) -> bool {
+    if !Path::new(path).starts_with(outputs_root()) {
+        return true;
+    }
+    match std::fs::remove_file(path) {
+        Ok(()) => true,
+        Err(e) => e.kind() == ErrorKind::NotFound,
+    }
+}
+
+/// Delete the output directories of instances that have exited, along with
+/// any GeoTIFFs they could not delete while their handles were open. Run at
+/// startup, before this instance creates its own.
+pub fn remove_stale_outputs() {
+    let Ok(entries) = std::fs::read_dir(outputs_root()) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // A directory without a lock file may be one another instance is
+        // still setting up
+        let Ok(lock) = File::open(path.join(LOCK_FILE)) else {
+            continue;
+        };
+        if lock.try_lock().is_ok() {
+            drop(lock);
+            std::fs::remove_dir_all(&path).ok();
+        }
+    }
+}
+
+/// Evaluate `expression` over the primary dataset's grid and write the result
+/// to a single-band Float32 GeoTIFF at `output`.
+///
+/// Pixels where any input is nodata, or where the result is not finite (e.g.
+/// division by zero), are written as NaN, which is the output's nodata value.
+pub fn write_band_math(
+    expression: &Expression,
+    primary: &Dataset,
+    inputs: &HashMap<String, &Dataset>,
+    output: &Path,
+) -> Result<(), String> {
+    let (width, height) = primary.raster_size();
+    let primary_gt = primary.geo_transform().ok();
+
+    // Group band references by dataset and check they exist
+    let mut sources: Vec<(Option<&str>, Source)> = Vec::new();
+    for (index, band_ref) in expression.band_refs().iter().enumerate() {
+        let alias = band_ref.layer.as_deref();
+        let dataset = match alias {
+            Some(alias) => *inputs
+                .get(alias)
+                .ok_or_else(|| format!("Unknown layer '{}' in expression", alias))?,
+            None => primary,
+        };
+        if band_ref.band > dataset.raster_count() {
+            return Err(format!(
+                "Band b{} does not exist ({} has {} bands)",
+                band_ref.band,
+                alias.unwrap_or("layer"),
+                dataset.raster_count()
+            ));
+        }
+
+        match sources.iter_mut().find(|(a, _)| *a == alias) {
+            Some((_, source)) => {
+                source.bands.push(band_ref.band);
+                source.refs.push(index);
+            }
+            None => {
+                let aligned = alias.is_none() || same_grid(primary, dataset);
+                if !aligned && (primary_gt.is_none() || primary.projection().is_empty()) {
+                    return Err(format!(
+                        "Layer '{}' is on a different grid and the primary layer is not georeferenced",
+                        alias.unwrap_or_default()
+                    ));
+                }
+                sources.push((
+                    alias,
+                    Source {
+                        dataset,
+                        bands: vec![band_ref.band],
+                        refs: vec![index],
+                        aligned,
+                    },
+                ));
+            }
+        }
+    }
+
+    let driver = DriverManager::get_driver_by_name("GTiff")
+        .map_err(|e| format!("Failed to get GTiff driver: {}", e))?;
+    let options: CslStringList = ["COMPRESS=LZW", "TILED=YES", "BIGTIFF=IF_SAFER"]
+        .into_iter()
+        .collect();
+    let mut output_ds = driver
+        .create_with_band_type_with_options::<f32, _>(output, width, height, 1, &options)
+        .map_err(|e| format!("Failed to create output file: {}", e))?;
+
+    if let Some(gt) = primary_gt {
+        output_ds
+            .set_geo_transform(&gt)
+            .map_err(|e| format!("Failed to set geotransform: {}", e))?;
+    }
+    let projection = primary.projection();
+    if !projection.is_empty() {
+        output_ds
+            .set_projection(&projection)
+            .map_err(|e| format!("Failed to set projection: {}", e))?;
+    }
+
+    let mut output_band = output_ds
+        .rasterband(1)
+        .map_err(|e| format!("Failed to get output band: {}", e))?;
+    output_band
+        .set_no_data_value(Some(f64::NAN))
+        .map_err(|e| format!("Failed to set nodata: {}", e))?;
+
+    let ref_count = expression.band_refs().len();
+    for row in (0..height).step_by(BLOCK_ROWS) {
+        let rows = BLOCK_ROWS.min(height - row);
+
+        // One buffer (and nodata value) per band reference
+        let mut inputs_block: Vec<BandBlock> = vec![(Vec::new(), None); ref_count];
+        for (_, source) in &sources {
+            let blocks = if source.aligned {
+                read_block(source, row, width, rows)?
+            } else {
+                warp_block(source, primary, row, width, rows)?
+            };
+            for (block, &index) in blocks.into_iter().zip(&source.refs) {
+                inputs_block[index] = block;
+            }
+        }
+
+        let mut values = vec![0.0; ref_count];
+        let data: Vec<f32> = (0..width * rows)
+            .map(|i| {
+                for (value, (block, nodata)) in values.iter_mut().zip(&inputs_block) {
+                    let v = block[i];
+                    if !v.is_finite() || nodata.is_some_and(|nd| (v - nd).abs() < 1e-10) {
+                        return f32::NAN;
+                    }
+                    *value = v;
+                }
+                let result = expression.eval(&values);
+                if result.is_finite() {
+                    result as f32
+                } else {
+                    f32::NAN
+                }
+            })
+            .collect();
+
+        let mut buffer = Buffer::new((width, rows), data);
+        output_band
+            .write((0, row as isize), (width, rows), &mut buffer)
+            .map_err(|e| format!("Failed to write output block: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Read a block of rows from a dataset on the primary grid, with pixels its
+/// mask or alpha band marks invalid set to NaN
+fn read_block(
+    source: &Source,
+    row: usize,
+    width: usize,
+    rows: usize,
+) -> Result<Vec<BandBlock>, String> {
+    source
+        .bands
+        .iter()
+        .map(|&band_index| {
+            let band = source
+                .dataset
+                .rasterband(band_index)
+                .map_err(|e| format!("Failed to get band {}: {}", band_index, e))?;
+            let data = read_masked(&band, (0, row as isize), (width, rows), (width, rows), None)?;
+            Ok((data, band.no_data_value()))
+        })
+        .collect()
+}
+
+/// Warp a block of rows from a dataset on another grid onto the primary grid.
+///
+/// The block is pre-filled with NaN so pixels outside the source, or masked
+/// by its nodata, come out as nodata.
+fn warp_block(
+    source: &Source,
+    primary: &Dataset,
+    row: usize,
+    width: usize,
+    rows: usize,
+) -> Result<Vec<BandBlock>, String> {
+    let gt = primary
+        .geo_transform()
+        .map_err(|e| format!("Failed to get geotransform: {}", e))?;
+
+    let mem_driver = DriverManager::get_driver_by_name("MEM")
+        .map_err(|e| format!("Failed to get MEM driver: {}", e))?;
+    let mut block_ds = mem_driver
+        .create_with_band_type::<f64, _>("", width, rows, source.bands.len())
+        .map_err(|e| format!("Failed to create block dataset: {}", e))?;
+
+    // Primary geotransform shifted down to the block's first row
+    block_ds
+        .set_geo_transform(&[
+            gt[0] + row as f64 * gt[2],
+            gt[1],
+            gt[2],
+            gt[3] + row as f64 * gt[5],
+            gt[4],
+            gt[5],
+        ])
+        .map_err(|e| format!("Failed to set geotransform: {}", e))?;
+    block_ds
+        .set_projection(&primary.projection())
+        .map_err(|e| format!("Failed to set projection: {}", e))?;
+
+    for i in 1..=source.bands.len() {
+        block_ds
+            .rasterband(i)
+            .and_then(|mut band| band.fill(f64::NAN, None))
+            .map_err(|e| format!("Failed to initialise block: {}", e))?;
+    }
+
+    warp_bands(
+        source.dataset,
+        &block_ds,
+        &source.bands,
+        GDALResampleAlg::GRA_Bilinear,
+    )?;
+
+    (1..=source.bands.len())
+        .map(|i| {
+            let band = block_ds
+                .rasterband(i)
+                .map_err(|e| format!("Failed to get block band {}: {}", i, e))?;
+            let buffer = band
+                .read_as::<f64>((0, 0), (width, rows), (width, rows), None)
+                .map_err(|e| format!("Failed to read block: {}", e))?;
+            Ok((buffer.data().to_vec(), None))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_str(source: &str, values: &[f64]) -> f64 {
+        Expression::parse(source).unwrap().eval(values)
+    }
+
+    #[test]
+    fn test_normalized_difference() {
+        let expr = Expression::parse("(b8 - b4) / (b8 + b4)").unwrap();
+        assert_eq!(
+            expr.band_refs(),
+            &[
+                BandRef {
+                    layer: None,
+                    band: 8
+                },
+                BandRef {
+                    layer: None,
+                    band: 4
+                },
+            ]
+        );
+        assert!((expr.eval(&[0.5, 0.1]) - 0.4 / 0.6).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval_str("b1 + 2 * 3", &[1.0]), 7.0);
+        assert_eq!(eval_str("(b1 + 2) * 3", &[1.0]), 9.0);
+        assert_eq!(eval_str("-b1 ^ 2", &[3.0]), -9.0);
+        assert_eq!(eval_str("2 ^ 3 ^ 2 + b1 * 0", &[1.0]), 512.0);
+        assert_eq!(eval_str("b1 - 1 - 1", &[5.0]), 3.0);
+        assert_eq!(eval_str("b1 / 2 / 2", &[8.0]), 2.0);
+    }
+
+    #[test]
+    fn test_comparisons_and_functions() {
+        assert_eq!(eval_str("b1 > 0.3", &[0.5]), 1.0);
+        assert_eq!(eval_str("b1 <= 0.3", &[0.5]), 0.0);
+        assert_eq!(eval_str("max(b1, 2) + abs(-1)", &[1.0]), 3.0);
+        assert_eq!(eval_str("sqrt(b1)", &[16.0]), 4.0);
+        assert!((eval_str("log10(b1)", &[1000.0]) - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_cross_layer_refs() {
+        let expr = Expression::parse("s2.b8 / b1 + s2.b8").unwrap();
+        assert_eq!(
+            expr.band_refs(),
+            &[
+                BandRef {
+                    layer: Some("s2".to_string()),
+                    band: 8
+                },
+                BandRef {
+                    layer: None,
+                    band: 1
+                },
+            ]
+        );
+        assert_eq!(expr.eval(&[4.0, 2.0]), 6.0);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Expression::parse("").is_err());
+        assert!(Expression::parse("1 + 2").is_err(), "no band referenced");
+        assert!(Expression::parse("b0 + 1").is_err());
+        assert!(Expression::parse("red + 1").is_err());
+        assert!(Expression::parse("(b1 + 1").is_err());
+        assert!(Expression::parse("b1 + ").is_err());
+        assert!(Expression::parse("foo(b1)").is_err());
+        assert!(Expression::parse("min(b1)").is_err());
+        assert!(Expression::parse("b1 $ 2").is_err());
+        assert!(Expression::parse("b1 b2").is_err());
+    }
+
+    #[test]
+    fn test_division_by_zero_is_not_finite() {
+        assert!(!eval_str("b1 / b2", &[1.0, 0.0]).is_finite());
+    }
+}
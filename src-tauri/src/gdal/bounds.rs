@@ -0,0 +1,441 @@
+use crate::gdal::geotransform::{geo_to_pixel, pixel_to_geo};
+use gdal::spatial_ref::{AxisMappingStrategy, CoordTransform, SpatialRef};
+use gdal::Dataset;
+use lru::LruCache;
+use std::cell::RefCell;
+use std::num::NonZeroUsize;
+use std::rc::Rc;
+
+/// Points sampled along each edge of a footprint when reprojecting it, as
+/// recommended for GDAL's `OCTTransformBounds`
+pub const DENSIFY_POINTS: usize = 21;
+
+/// Coordinate transforms kept per thread
+const TRANSFORM_CACHE_SIZE: usize = 32;
+
+/// Source and target CRS (WKT plus axis mapping strategy) of a cached
+/// transform
+type TransformKey = (String, u32, String, u32);
+
+thread_local! {
+    /// Coordinate transforms for the current thread, keyed by CRS pair.
+    ///
+    /// Creating a transform makes PROJ search for an operation between the
+    /// two CRSs, which costs far more than transforming a few points.
+    static TRANSFORMS: RefCell<LruCache<TransformKey, Rc<CoordTransform>>> = RefCell::new(
+        LruCache::new(NonZeroUsize::new(TRANSFORM_CACHE_SIZE).unwrap()),
+    );
+}
+
+/// Area covered by a dataset in its native CRS
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Footprint {
+    /// Axis-aligned extent `[minx, miny, maxx, maxy]`
+    Extent([f64; 4]),
+    /// Pixel grid of a raster; the geotransform's rotation terms (`gt[2]`,
+    /// `gt[4]`) may turn it into any parallelogram
+    Grid {
+        gt: [f64; 6],
+        width: usize,
+        height: usize,
+    },
+}
+
+impl Footprint {
+    /// Footprint of a raster's pixel grid
+    pub fn grid(gt: [f64; 6], width: usize, height: usize) -> Self {
+        Footprint::Grid { gt, width, height }
+    }
+
+    /// Native coordinates at fractions `u` across and `v` down the footprint
+    fn point(&self, u: f64, v: f64) -> (f64, f64) {
+        match *self {
+            Footprint::Extent([min_x, min_y, max_x, max_y]) => {
+                (min_x + u * (max_x - min_x), max_y - v * (max_y - min_y))
+            }
+            Footprint::Grid { gt, width, height } => {
+                pixel_to_geo(&gt, u * width as f64, v * height as f64)
+            }
+        }
+    }
+
+    /// Axis-aligned native bounds `[minx, miny, maxx, maxy]`
+    pub fn bounds(&self) -> [f64; 4] {
+        let corners =
+            [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)].map(|(u, v)| self.point(u, v));
+        let xs = corners.iter().map(|c| c.0);
+        let ys = corners.iter().map(|c| c.1);
+
+        [
+            xs.clone().fold(f64::INFINITY, f64::min),
+            ys.clone().fold(f64::INFINITY, f64::min),
+            xs.fold(f64::NEG_INFINITY, f64::max),
+            ys.fold(f64::NEG_INFINITY, f64::max),
+        ]
+    }
+
+    /// Points along the edges, `points_per_edge` per edge including the
+    /// corners, followed by the centre (see `lon_range`)
+    pub fn outline(&self, points_per_edge: usize) -> (Vec<f64>, Vec<f64>) {
+        let steps = points_per_edge.max(2) - 1;
+        let mut xs = Vec::with_capacity(steps * 4 + 1);
+        let mut ys = Vec::with_capacity(steps * 4 + 1);
+
+        for i in 0..steps {
+            let t = i as f64 / steps as f64;
+            // Top, right, bottom and left edges, clockwise
+            for (u, v) in [(t, 0.0), (1.0, t), (1.0 - t, 1.0), (0.0, 1.0 - t)] {
+                let (x, y) = self.point(u, v);
+                xs.push(x);
+                ys.push(y);
+            }
+        }
+        let (x, y) = self.point(0.5, 0.5);
+        xs.push(x);
+        ys.push(y);
+
+        (xs, ys)
+    }
+
+    /// Whether a native point lies inside the footprint
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let (u, v) = match *self {
+            Footprint::Extent([min_x, min_y, max_x, max_y]) => {
+                return x >= min_x && x <= max_x && y >= min_y && y <= max_y;
+            }
+            Footprint::Grid { gt, width, height } => match geo_to_pixel(&gt, x, y) {
+                Some((col, row)) => (col / width as f64, row / height as f64),
+                None => return false,
+            },
+        };
+
+        (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)
+    }
+}
+
+/// Longitude range `(west, east)` covering the given longitudes, in degrees.
+///
+/// The range is the shortest one around the globe: the complement of the
+/// largest gap between neighbouring longitudes. `west` is in [-180, 180) and
+/// `east` is above 180 when the range crosses the antimeridian, so
+/// `[170, 190]` is a 20° range over Fiji rather than a world-wide one.
+///
+/// Pass a point inside the extent (its centre) along with the outline; with
+/// only a few outline points, an extent wider than 180° is otherwise
+/// indistinguishable from the narrower range on the other side of the globe.
+/// Non-finite values are ignored.
+pub fn lon_range(lons: &[f64]) -> (f64, f64) {
+    let lons: Vec<f64> = lons.iter().copied().filter(|v| v.is_finite()).collect();
+    if lons.is_empty() {
+        return (-180.0, 180.0);
+    }
+
+    let min = lons.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = lons.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max - min >= 360.0 - 1e-9 {
+        return (-180.0, 180.0);
+    }
+
+    let mut sorted: Vec<f64> = lons.iter().map(|&lon| normalize_lon(lon)).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    // Start with the gap across the antimeridian, which gives the plain range
+    let mut gap = sorted[0] + 360.0 - sorted[sorted.len() - 1];
+    let mut west = sorted[0];
+    for pair in sorted.windows(2) {
+        if pair[1] - pair[0] > gap {
+            gap = pair[1] - pair[0];
+            west = pair[1];
+        }
+    }
+
+    (west, west + 360.0 - gap)
+}
+
+/// Wrap a longitude into [-180, 180)
+fn normalize_lon(lon: f64) -> f64 {
+    (lon + 180.0).rem_euclid(360.0) - 180.0
+}
+
+/// EPSG:4326 bounds `[west, south, east, north]` of sampled points; `east` is
+/// above 180 when the points cross the antimeridian (see `lon_range`)
+pub fn geo_bounds(lons: &[f64], lats: &[f64]) -> [f64; 4] {
+    let (west, east) = lon_range(lons);
+    let lats = lats.iter().copied().filter(|v| v.is_finite());
+    let south = lats.clone().fold(f64::INFINITY, f64::min);
+    let north = lats.fold(f64::NEG_INFINITY, f64::max);
+
+    [west, south, east, north]
+}
+
+/// Bounds of a dataset whose CRS is already geographic; grids using 0-360
+/// longitudes are brought into [-180, 180), crossing the antimeridian when
+/// they straddle it
+pub fn geographic_bounds(native: [f64; 4]) -> [f64; 4] {
+    let center = (native[0] + native[2]) / 2.0;
+    geo_bounds(&[native[0], center, native[2]], &[native[1], native[3]])
+}
+
+/// Split bounds that cross the antimeridian into the parts either side of
+/// it, each within [-180, 180]
+pub fn split_antimeridian(bounds: [f64; 4]) -> Vec<[f64; 4]> {
+    let [west, south, east, north] = bounds;
+    if east <= 180.0 {
+        return vec![bounds];
+    }
+
+    vec![
+        [west, south, 180.0, north],
+        [-180.0, south, east - 360.0, north],
+    ]
+}
+
+/// EPSG:4326 with longitude first, the order MapLibre and the frontend use
+pub fn wgs84() -> Result<SpatialRef, String> {
+    let mut srs =
+        SpatialRef::from_epsg(4326).map_err(|e| format!("Failed to create EPSG:4326: {}", e))?;
+    srs.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    Ok(srs)
+}
+
+/// Transform from `source` to `target`, created once per CRS pair on each
+/// thread
+pub fn cached_transform(
+    source: &SpatialRef,
+    target: &SpatialRef,
+) -> Result<Rc<CoordTransform>, String> {
+    let wkt = |srs: &SpatialRef| {
+        srs.to_wkt()
+            .map_err(|e| format!("Failed to export SRS: {}", e))
+    };
+    let key = (
+        wkt(source)?,
+        source.axis_mapping_strategy() as u32,
+        wkt(target)?,
+        target.axis_mapping_strategy() as u32,
+    );
+
+    TRANSFORMS.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some(transform) = cache.get(&key) {
+            return Ok(transform.clone());
+        }
+        let transform = Rc::new(
+            CoordTransform::new(source, target)
+                .map_err(|e| format!("Failed to create transform: {}", e))?,
+        );
+        cache.put(key, transform.clone());
+        Ok(transform)
+    })
+}
+
+/// Transform points in place, keeping only those that transform; points
+/// outside a projection's valid area fail individually
+fn transform_points(
+    transform: &CoordTransform,
+    xs: &mut Vec<f64>,
+    ys: &mut Vec<f64>,
+) -> Result<(), String> {
+    let mut bx = xs.clone();
+    let mut by = ys.clone();
+    if transform
+        .transform_coords(&mut bx, &mut by, &mut [])
+        .is_ok()
+    {
+        *xs = bx;
+        *ys = by;
+        return Ok(());
+    }
+
+    let mut tx = Vec::with_capacity(xs.len());
+    let mut ty = Vec::with_capacity(ys.len());
+    for (&x, &y) in xs.iter().zip(ys.iter()) {
+        let (mut px, mut py) = ([x], [y]);
+        if transform
+            .transform_coords(&mut px, &mut py, &mut [])
+            .is_ok()
+        {
+            tx.push(px[0]);
+            ty.push(py[0]);
+        }
+    }
+    if tx.is_empty() {
+        return Err("Failed to transform coordinates: no point could be transformed".to_string());
+    }
+
+    *xs = tx;
+    *ys = ty;
+    Ok(())
+}
+
+/// EPSG:4326 bounds `[west, south, east, north]` of a footprint in `srs`.
+///
+/// Like GDAL's `OCTTransformBounds`, the edges are densified so curved
+/// outlines (polar stereographic, wide UTM scenes) are not cut short, and a
+/// pole inside the footprint extends the bounds to it. `east` is above 180
+/// when the footprint crosses the antimeridian.
+pub fn footprint_to_4326(srs: &SpatialRef, footprint: &Footprint) -> Result<[f64; 4], String> {
+    if srs.is_geographic() {
+        return Ok(geographic_bounds(footprint.bounds()));
+    }
+
+    // Geotransforms and OGR geometries are in easting/northing order
+    let mut source = srs.clone();
+    source.set_axis_mapping_strategy(AxisMappingStrategy::TraditionalGisOrder);
+    let target = wgs84()?;
+
+    let (mut xs, mut ys) = footprint.outline(DENSIFY_POINTS);
+    let forward = cached_transform(&source, &target)?;
+    transform_points(&forward, &mut xs, &mut ys)?;
+    let mut bounds = geo_bounds(&xs, &ys);
+
+    // Poles map to points (or nothing) in most projections, so check them
+    // in the native CRS
+    let inverse = cached_transform(&target, &source)?;
+    for pole in [90.0, -90.0] {
+        let (mut x, mut y) = ([0.0], [pole]);
+        if inverse.transform_coords(&mut x, &mut y, &mut []).is_ok()
+            && footprint.contains(x[0], y[0])
+        {
+            bounds[0] = -180.0;
+            bounds[2] = 180.0;
+            if pole > 0.0 {
+                bounds[3] = 90.0;
+            } else {
+                bounds[1] = -90.0;
+            }
+        }
+    }
+
+    Ok(bounds)
+}
+
+/// Footprint of a raster's pixel grid, from its geotransform
+pub fn dataset_footprint(dataset: &Dataset) -> Result<Footprint, String> {
+    let gt = dataset
+        .geo_transform()
+        .map_err(|e| format!("Failed to get geotransform: {}", e))?;
+    let (width, height) = dataset.raster_size();
+
+    Ok(Footprint::grid(gt, width, height))
+}
+
+/// EPSG:4326 bounds of a raster; native bounds when it has no projection
+pub fn dataset_geo_bounds(dataset: &Dataset) -> Result<[f64; 4], String> {
+    let footprint = dataset_footprint(dataset)?;
+    let projection = dataset.projection();
+    if projection.is_empty() {
+        return Ok(footprint.bounds());
+    }
+
+    let srs = SpatialRef::from_wkt(&projection)
+        .map_err(|e| format!("Failed to parse source SRS: {}", e))?;
+    footprint_to_4326(&srs, &footprint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 10x20 grid with rotation terms: rows also step east, columns north
+    const ROTATED: Footprint = Footprint::Grid {
+        gt: [100.0, 1.0, 0.5, 200.0, 0.25, -1.0],
+        width: 10,
+        height: 20,
+    };
+
+    #[test]
+    fn test_footprint_bounds_with_rotation() {
+        assert_eq!(ROTATED.bounds(), [100.0, 180.0, 120.0, 202.5]);
+        assert_eq!(
+            Footprint::Extent([0.0, 0.0, 10.0, 20.0]).bounds(),
+            [0.0, 0.0, 10.0, 20.0]
+        );
+    }
+
+    #[test]
+    fn test_footprint_outline() {
+        let (xs, ys) = Footprint::Extent([0.0, 0.0, 10.0, 20.0]).outline(3);
+        // Two steps per edge, then the centre
+        assert_eq!(xs.len(), 9);
+        assert_eq!((xs[8], ys[8]), (5.0, 10.0));
+        // Edge midpoints are included
+        assert!(xs.iter().zip(&ys).any(|(&x, &y)| (x, y) == (10.0, 10.0)));
+        assert!(xs.iter().zip(&ys).any(|(&x, &y)| (x, y) == (5.0, 0.0)));
+
+        let (xs, ys) = ROTATED.outline(DENSIFY_POINTS);
+        assert_eq!(xs.len(), (DENSIFY_POINTS - 1) * 4 + 1);
+        assert!(xs.iter().zip(&ys).all(|(&x, &y)| ROTATED.contains(x, y)));
+    }
+
+    #[test]
+    fn test_footprint_contains() {
+        assert!(ROTATED.contains(110.0, 191.25));
+        assert!(ROTATED.contains(118.0, 183.0));
+        // Inside the axis-aligned bounds, outside the rotated grid
+        assert!(!ROTATED.contains(100.5, 181.0));
+        assert!(!ROTATED.contains(119.0, 202.0));
+
+        let extent = Footprint::Extent([0.0, 0.0, 10.0, 20.0]);
+        assert!(extent.contains(10.0, 0.0));
+        assert!(!extent.contains(-1.0, 5.0));
+    }
+
+    #[test]
+    fn test_lon_range_plain() {
+        assert_eq!(lon_range(&[10.0, 20.0, 15.0]), (10.0, 20.0));
+        assert_eq!(lon_range(&[-180.0, 180.0, 0.0]), (-180.0, 180.0));
+        assert_eq!(lon_range(&[f64::NAN, 5.0]), (5.0, 5.0));
+    }
+
+    #[test]
+    fn test_lon_range_across_antimeridian() {
+        // UTM zone 60 scene extending past 180°
+        let (west, east) = lon_range(&[177.5, -179.0, 178.0, -178.5, 179.4]);
+        assert_eq!(west, 177.5);
+        assert!((east - 181.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lon_range_wide_extent_needs_centre() {
+        // The corners alone read as the 160° range across the antimeridian;
+        // the centre shows the extent is the 200° one
+        assert_eq!(lon_range(&[-100.0, 100.0]), (100.0, 260.0));
+        assert_eq!(lon_range(&[-100.0, 100.0, 0.0]), (-100.0, 100.0));
+    }
+
+    #[test]
+    fn test_geographic_bounds() {
+        // 0-360 grid straddling the antimeridian
+        assert_eq!(
+            geographic_bounds([170.0, -20.0, 190.0, -10.0]),
+            [170.0, -20.0, 190.0, -10.0]
+        );
+        // Same area with negative longitudes
+        assert_eq!(
+            geographic_bounds([-190.0, -20.0, -170.0, -10.0]),
+            [170.0, -20.0, 190.0, -10.0]
+        );
+        // Global grids stay global in either convention
+        assert_eq!(
+            geographic_bounds([0.0, -90.0, 360.0, 90.0]),
+            [-180.0, -90.0, 180.0, 90.0]
+        );
+        assert_eq!(
+            geographic_bounds([10.0, 40.0, 20.0, 50.0]),
+            [10.0, 40.0, 20.0, 50.0]
+        );
+    }
+
+    #[test]
+    fn test_split_antimeridian() {
+        assert_eq!(
+            split_antimeridian([170.0, -20.0, 190.0, -10.0]),
+            vec![[170.0, -20.0, 180.0, -10.0], [-180.0, -20.0, -170.0, -10.0]]
+        );
+        assert_eq!(
+            split_antimeridian([10.0, 40.0, 20.0, 50.0]),
+            vec![[10.0, 40.0, 20.0, 50.0]]
+        );
+    }
+}
@@ -0,0 +1,390 @@
+use gdal::raster::{ColorInterpretation, RasterBand};
+use serde::{Deserialize, Serialize};
+
+/// Named colour ramps, as evenly spaced stops from low to high
+const NAMED_RAMPS: &[(&str, &[u32])] = &[
+    (
+        "viridis",
+        &[
+            0x440154, 0x482878, 0x3e4989, 0x31688e, 0x26828e, 0x1f9e89, 0x35b779, 0x6ece58,
+            0xb5de2b, 0xfde725,
+        ],
+    ),
+    (
+        "magma",
+        &[
+            0x000004, 0x180f3d, 0x440f76, 0x721f81, 0x9e2f7f, 0xcd4071, 0xf1605d, 0xfd9668,
+            0xfeca8d, 0xfcfdbf,
+        ],
+    ),
+    (
+        "inferno",
+        &[
+            0x000004, 0x1b0c41, 0x4a0c6b, 0x781c6d, 0xa52c60, 0xcf4446, 0xed6925, 0xfb9b06,
+            0xf7d13d, 0xfcffa4,
+        ],
+    ),
+    (
+        "plasma",
+        &[
+            0x0d0887, 0x41049d, 0x6a00a8, 0x8f0da4, 0xb12a90, 0xcc4778, 0xe16462, 0xf2844b,
+            0xfca636, 0xf0f921,
+        ],
+    ),
+    (
+        "cividis",
+        &[
+            0x00224e, 0x123570, 0x3b496c, 0x575d6d, 0x707173, 0x8a8678, 0xa59c74, 0xc3b369,
+            0xe1cc55, 0xfee838,
+        ],
+    ),
+    (
+        "terrain",
+        &[
+            0x333399, 0x0888ee, 0x00cc66, 0x80e680, 0xffff99, 0xbfad76, 0x805c54, 0xbfada9,
+            0xffffff,
+        ],
+    ),
+    (
+        "rdylgn",
+        &[
+            0xa50026, 0xd73027, 0xf46d43, 0xfdae61, 0xfee08b, 0xffffbf, 0xd9ef8b, 0xa6d96a,
+            0x66bd63, 0x1a9850, 0x006837,
+        ],
+    ),
+    (
+        "rdbu",
+        &[
+            0x67001f, 0xb2182b, 0xd6604d, 0xf4a582, 0xfddbc7, 0xf7f7f7, 0xd1e5f0, 0x92c5de,
+            0x4393c3, 0x2166ac, 0x053061,
+        ],
+    ),
+    (
+        "spectral",
+        &[
+            0x9e0142, 0xd53e4f, 0xf46d43, 0xfdae61, 0xfee08b, 0xffffbf, 0xe6f598, 0xabdda4,
+            0x66c2a5, 0x3288bd, 0x5e4fa2,
+        ],
+    ),
+    // Cyclic: both ends match, so phase wraps from π to -π without a seam
+    (
+        "twilight",
+        &[
+            0xe2d9e2, 0xb1bfd3, 0x7b9cc5, 0x6276ba, 0x5a4aa3, 0x2f1436, 0x6d2150, 0xa5405a,
+            0xc47a63, 0xd6b3a3, 0xe2d9e2,
+        ],
+    ),
+    ("greys", &[0x000000, 0xffffff]),
+];
+
+/// A user-defined ramp stop. `position` is relative to the stretch range
+/// (0.0 = stretch min, 1.0 = stretch max).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ColorStop {
+    pub position: f64,
+    pub color: String,
+}
+
+/// A discrete class: pixels whose raw value equals `value` get `color`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ColorClass {
+    pub value: f64,
+    pub color: String,
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Colour mapping for single-band rendering, as sent by the frontend.
+///
+/// Colours are CSS-style hex strings (`#rrggbb` or `#rrggbbaa`).
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Colormap {
+    /// One of the built-in ramps (viridis, magma, terrain, rdylgn, ...)
+    Named {
+        name: String,
+        #[serde(default)]
+        invert: bool,
+    },
+    /// A ramp interpolated between user-defined stops
+    Stops {
+        stops: Vec<ColorStop>,
+        #[serde(default)]
+        invert: bool,
+    },
+    /// Exact value-to-colour classes; other values are transparent
+    Classes { classes: Vec<ColorClass> },
+}
+
+/// A colormap resolved into something cheap to apply per pixel
+pub enum ColorRamp {
+    /// Colour for every stretched value 0-255
+    Lut(Box<[[u8; 4]; 256]>),
+    /// Raw value to colour
+    Classes(Vec<(f64, [u8; 4])>),
+    /// Embedded colour table, indexed by raw value
+    Palette(Vec<[u8; 4]>),
+}
+
+impl Colormap {
+    /// Resolve the colormap into a lookup table or class list
+    pub fn build(&self) -> Result<ColorRamp, String> {
+        match self {
+            Colormap::Named { name, invert } => {
+                let colors = NAMED_RAMPS
+                    .iter()
+                    .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                    .map(|(_, colors)| *colors)
+                    .ok_or_else(|| format!("Unknown colormap: {}", name))?;
+
+                let last = (colors.len() - 1) as f64;
+                let stops: Vec<(f64, [u8; 4])> = colors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &rgb)| {
+                        let [_, r, g, b] = rgb.to_be_bytes();
+                        (i as f64 / last, [r, g, b, 255])
+                    })
+                    .collect();
+
+                Ok(ColorRamp::Lut(build_lut(&stops, *invert)))
+            }
+            Colormap::Stops { stops, invert } => {
+                if stops.is_empty() {
+                    return Err("Colormap needs at least one stop".to_string());
+                }
+
+                let mut parsed = stops
+                    .iter()
+                    .map(|stop| Ok((stop.position.clamp(0.0, 1.0), parse_color(&stop.color)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                parsed.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                Ok(ColorRamp::Lut(build_lut(&parsed, *invert)))
+            }
+            Colormap::Classes { classes } => {
+                let parsed = classes
+                    .iter()
+                    .map(|class| Ok((class.value, parse_color(&class.color)?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+
+                Ok(ColorRamp::Classes(parsed))
+            }
+        }
+    }
+}
+
+impl ColorRamp {
+    /// Colour for a pixel, given its raw value and its stretched 0-255 value.
+    /// Returns `None` for values that should be transparent.
+    pub fn color(&self, value: f64, stretched: u8) -> Option<[u8; 4]> {
+        match self {
+            ColorRamp::Lut(lut) => Some(lut[stretched as usize]),
+            ColorRamp::Classes(classes) => classes
+                .iter()
+                .find(|(class_value, _)| (value - class_value).abs() < 1e-9)
+                .map(|(_, color)| *color),
+            ColorRamp::Palette(palette) => palette_color(palette, value),
+        }
+    }
+
+    /// Whether the ramp maps raw values to classes rather than stretched
+    /// values. Discrete ramps skip the stretch and must be read with nearest
+    /// resampling, since interpolated values are not valid classes.
+    pub fn is_discrete(&self) -> bool {
+        !matches!(self, ColorRamp::Lut(_))
+    }
+}
+
+/// Read a band's colour table as RGBA entries, if the band is paletted
+pub fn read_palette(band: &RasterBand) -> Option<Vec<[u8; 4]>> {
+    if band.color_interpretation() != ColorInterpretation::PaletteIndex {
+        return None;
+    }
+
+    let table = band.color_table()?;
+    let palette = (0..table.entry_count())
+        .map(|i| {
+            table
+                .entry_as_rgb(i)
+                .map(|e| [e.r, e.g, e.b, e.a].map(|c| c.clamp(0, 255) as u8))
+                .unwrap_or([0, 0, 0, 0])
+        })
+        .collect();
+
+    Some(palette)
+}
+
+/// Colour of a palette index; `None` when the value is not a valid index
+pub fn palette_color(palette: &[[u8; 4]], value: f64) -> Option<[u8; 4]> {
+    if value < 0.0 || value.fract() != 0.0 {
+        return None;
+    }
+    palette.get(value as usize).copied()
+}
+
+/// Interpolate sorted stops into a 256-entry lookup table
+fn build_lut(stops: &[(f64, [u8; 4])], invert: bool) -> Box<[[u8; 4]; 256]> {
+    let mut lut = Box::new([[0u8; 4]; 256]);
+
+    for (i, entry) in lut.iter_mut().enumerate() {
+        let t = i as f64 / 255.0;
+        let t = if invert { 1.0 - t } else { t };
+        *entry = interpolate(stops, t);
+    }
+
+    lut
+}
+
+fn interpolate(stops: &[(f64, [u8; 4])], t: f64) -> [u8; 4] {
+    let first = stops[0];
+    let last = stops[stops.len() - 1];
+    if t <= first.0 {
+        return first.1;
+    }
+    if t >= last.0 {
+        return last.1;
+    }
+
+    for pair in stops.windows(2) {
+        let (p0, c0) = pair[0];
+        let (p1, c1) = pair[1];
+        if t <= p1 {
+            let span = p1 - p0;
+            let f = if span > 0.0 { (t - p0) / span } else { 1.0 };
+            return std::array::from_fn(|k| {
+                (c0[k] as f64 + (c1[k] as f64 - c0[k] as f64) * f).round() as u8
+            });
+        }
+    }
+
+    last.1
+}
+
+/// Parse `#rrggbb` or `#rrggbbaa`
+fn parse_color(color: &str) -> Result<[u8; 4], String> {
+    let hex = color.trim().trim_start_matches('#');
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.is_ascii() {
+        return Err(format!("Invalid colour: {}", color));
+    }
+
+    let channel = |i: usize| {
+        u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| format!("Invalid colour: {}", color))
+    };
+
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Ok([channel(0)?, channel(2)?, channel(4)?, alpha])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lut(colormap: &Colormap) -> Box<[[u8; 4]; 256]> {
+        match colormap.build().unwrap() {
+            ColorRamp::Lut(lut) => lut,
+            _ => panic!("expected a lookup table"),
+        }
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#ff8000").unwrap(), [255, 128, 0, 255]);
+        assert_eq!(parse_color("00ff0080").unwrap(), [0, 255, 0, 128]);
+        assert!(parse_color("#fff").is_err());
+        assert!(parse_color("#gg0000").is_err());
+    }
+
+    #[test]
+    fn test_named_ramp_endpoints() {
+        let viridis = lut(&Colormap::Named {
+            name: "Viridis".to_string(),
+            invert: false,
+        });
+        assert_eq!(viridis[0], [0x44, 0x01, 0x54, 255]);
+        assert_eq!(viridis[255], [0xfd, 0xe7, 0x25, 255]);
+    }
+
+    #[test]
+    fn test_named_ramp_invert() {
+        let inverted = lut(&Colormap::Named {
+            name: "greys".to_string(),
+            invert: true,
+        });
+        assert_eq!(inverted[0], [255, 255, 255, 255]);
+        assert_eq!(inverted[255], [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_cyclic_ramp_wraps() {
+        let twilight = lut(&Colormap::Named {
+            name: "twilight".to_string(),
+            invert: false,
+        });
+        assert_eq!(twilight[0], twilight[255]);
+        assert_ne!(twilight[0], twilight[128]);
+    }
+
+    #[test]
+    fn test_unknown_named_ramp() {
+        let colormap = Colormap::Named {
+            name: "nope".to_string(),
+            invert: false,
+        };
+        assert!(colormap.build().is_err());
+    }
+
+    #[test]
+    fn test_custom_stops_interpolate() {
+        let ramp = lut(&Colormap::Stops {
+            stops: vec![
+                ColorStop {
+                    position: 1.0,
+                    color: "#ffffff".to_string(),
+                },
+                ColorStop {
+                    position: 0.0,
+                    color: "#000000".to_string(),
+                },
+            ],
+            invert: false,
+        });
+        assert_eq!(ramp[0], [0, 0, 0, 255]);
+        assert_eq!(ramp[255], [255, 255, 255, 255]);
+        assert!((ramp[128][0] as i32 - 128).abs() <= 1);
+    }
+
+    #[test]
+    fn test_class_map() {
+        let ramp = Colormap::Classes {
+            classes: vec![
+                ColorClass {
+                    value: 1.0,
+                    color: "#00ff00".to_string(),
+                    label: Some("Forest".to_string()),
+                },
+                ColorClass {
+                    value: 2.0,
+                    color: "#0000ff".to_string(),
+                    label: None,
+                },
+            ],
+        }
+        .build()
+        .unwrap();
+
+        assert_eq!(ramp.color(2.0, 0), Some([0, 0, 255, 255]));
+        assert_eq!(ramp.color(3.0, 0), None);
+        assert!(ramp.is_discrete());
+    }
+
+    #[test]
+    fn test_palette_color() {
+        let palette = vec![[0, 0, 0, 0], [34, 139, 34, 255]];
+        assert_eq!(palette_color(&palette, 1.0), Some([34, 139, 34, 255]));
+        assert_eq!(palette_color(&palette, 0.0), Some([0, 0, 0, 0]));
+        assert_eq!(palette_color(&palette, 2.0), None);
+        assert_eq!(palette_color(&palette, 0.5), None);
+        assert_eq!(palette_color(&palette, -1.0), None);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::gdal::warp::last_gdal_error;
+use gdal::raster::{RasterBand, ResampleAlg};
+use gdal::{Dataset, DriverManager};
+use gdal_sys::{CPLErr, GDALDataType, GDALRWFlag};
+use serde::{Deserialize, Serialize};
+use std::ffi::c_void;
+use std::os::raw::c_int;
+use std::ptr;
+
+/// How a complex band (SAR single-look complex, CInt16/CFloat32) is drawn.
+///
+/// Tiles, stretch samples and histograms all read the same part, so a dB
+/// layer is stretched over dB values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComplexPart {
+    /// |z|
+    #[default]
+    Amplitude,
+    /// |z|²
+    Intensity,
+    /// 10·log10(|z|²); zero amplitude has no value
+    #[serde(rename = "db")]
+    Decibels,
+    /// arg(z) in radians, -π to π
+    Phase,
+    Real,
+    Imaginary,
+}
+
+impl ComplexPart {
+    pub fn apply(self, re: f64, im: f64) -> f64 {
+        match self {
+            ComplexPart::Amplitude => re.hypot(im),
+            ComplexPart::Intensity => re * re + im * im,
+            ComplexPart::Decibels => 10.0 * (re * re + im * im).log10(),
+            ComplexPart::Phase => im.atan2(re),
+            ComplexPart::Real => re,
+            ComplexPart::Imaginary => im,
+        }
+    }
+}
+
+/// Value of a complex pixel, as returned by pixel queries
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComplexValue {
+    pub real: f64,
+    pub imaginary: f64,
+    pub amplitude: f64,
+    pub phase: f64, // radians, -π to π
+}
+
+impl ComplexValue {
+    pub fn new(real: f64, imaginary: f64) -> Self {
+        Self {
+            real,
+            imaginary,
+            amplitude: ComplexPart::Amplitude.apply(real, imaginary),
+            phase: ComplexPart::Phase.apply(real, imaginary),
+        }
+    }
+}
+
+/// Whether a band holds complex values. The gdal crate reports complex
+/// types as `Unknown`, so this asks GDAL directly.
+pub fn is_complex(band: &RasterBand) -> bool {
+    // SAFETY: the band handle is valid for the lifetime of `band`; both calls
+    // only read its data type
+    unsafe {
+        gdal_sys::GDALDataTypeIsComplex(gdal_sys::GDALGetRasterDataType(band.c_rasterband())) != 0
+    }
+}
+
+/// Read a window of a band as (real, imaginary) pairs
+pub fn read_complex(
+    band: &RasterBand,
+    window: (isize, isize),
+    window_size: (usize, usize),
+    size: (usize, usize),
+    resample_alg: Option<ResampleAlg>,
+) -> Result<Vec<[f64; 2]>, String> {
+    let mut data = vec![[0.0; 2]; size.0 * size.1];
+
+    // SAFETY: an all-zero GDALRasterIOExtraArg is valid (integers, floats and
+    // a null progress callback); the buffer holds exactly size.0 * size.1
+    // CFloat64 values, which is what GDAL writes
+    let err = unsafe {
+        let mut extra: gdal_sys::GDALRasterIOExtraArg = std::mem::zeroed();
+        extra.nVersion = 1;
+        extra.eResampleAlg = resample_alg
+            .unwrap_or(ResampleAlg::NearestNeighbour)
+            .to_gdal();
+        gdal_sys::GDALRasterIOEx(
+            band.c_rasterband(),
+            GDALRWFlag::GF_Read,
+            window.0 as c_int,
+            window.1 as c_int,
+            window_size.0 as c_int,
+            window_size.1 as c_int,
+            data.as_mut_ptr() as *mut c_void,
+            size.0 as c_int,
+            size.1 as c_int,
+            GDALDataType::GDT_CFloat64,
+            0,
+            0,
+            &mut extra,
+        )
+    };
+    if err != CPLErr::CE_None {
+        return Err(format!(
+            "Failed to read complex values: {}",
+            last_gdal_error()
+        ));
+    }
+
+    Ok(data)
+}
+
+/// Read a window as f64, drawing complex bands as `part`; other bands are
+/// read as they are
+pub fn read_values(
+    band: &RasterBand,
+    window: (isize, isize),
+    window_size: (usize, usize),
+    size: (usize, usize),
+    resample_alg: Option<ResampleAlg>,
+    part: ComplexPart,
+) -> Result<Vec<f64>, String> {
+    if is_complex(band) {
+        let values = read_complex(band, window, window_size, size, resample_alg)?;
+        return Ok(values
+            .into_iter()
+            .map(|[re, im]| part.apply(re, im))
+            .collect());
+    }
+
+    let buffer = band
+        .read_as::<f64>(window, window_size, size, resample_alg)
+        .map_err(|e| format!("Failed to read: {}", e))?;
+    Ok(buffer.data().to_vec())
+}
+
+/// In-memory dataset of CFloat64 bands, used as the warp target for complex
+/// sources so the imaginary part survives resampling
+pub fn create_complex_mem(size: (usize, usize), bands: usize) -> Result<Dataset, String> {
+    let driver = DriverManager::get_driver_by_name("MEM")
+        .map_err(|e| format!("Failed to get MEM driver: {}", e))?;
+
+    // SAFETY: the driver handle is valid, the name is a NUL-terminated empty
+    // string and no creation options are passed; the new handle is owned by
+    // the returned Dataset
+    unsafe {
+        let handle = gdal_sys::GDALCreate(
+            driver.c_driver(),
+            c"".as_ptr(),
+            size.0 as c_int,
+            size.1 as c_int,
+            bands as c_int,
+            GDALDataType::GDT_CFloat64,
+            ptr::null_mut(),
+        );
+        if handle.is_null() {
+            return Err(format!(
+                "Failed to create output dataset: {}",
+                last_gdal_error()
+            ));
+        }
+        Ok(Dataset::from_c_dataset(handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn test_complex_parts() {
+        let (re, im) = (3.0, 4.0);
+        assert_eq!(ComplexPart::Amplitude.apply(re, im), 5.0);
+        assert_eq!(ComplexPart::Intensity.apply(re, im), 25.0);
+        assert!((ComplexPart::Decibels.apply(re, im) - 13.979).abs() < 1e-3);
+        assert!((ComplexPart::Phase.apply(re, im) - 0.9273).abs() < 1e-4);
+        assert_eq!(ComplexPart::Real.apply(re, im), 3.0);
+        assert_eq!(ComplexPart::Imaginary.apply(re, im), 4.0);
+
+        // Phase wraps at ±π; zero amplitude has no dB value
+        assert_eq!(ComplexPart::Phase.apply(-1.0, 0.0), PI);
+        assert!(!ComplexPart::Decibels.apply(0.0, 0.0).is_finite());
+    }
+
+    #[test]
+    fn test_complex_value() {
+        let value = ComplexValue::new(0.0, -2.0);
+        assert_eq!(value.amplitude, 2.0);
+        assert!((value.phase + PI / 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_complex_part_serde() {
+        let part: ComplexPart = serde_json::from_str("\"db\"").unwrap();
+        assert_eq!(part, ComplexPart::Decibels);
+        assert_eq!(
+            serde_json::to_string(&ComplexPart::Imaginary).unwrap(),
+            "\"imaginary\""
+        );
+    }
+}
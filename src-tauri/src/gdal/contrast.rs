@@ -0,0 +1,317 @@
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+/// Band samples kept for building lookup tables; each holds up to a million
+/// values (8 MB)
+const MAX_SAMPLES: usize = 8;
+
+/// Lookup tables kept, across all layers
+const MAX_LUTS: usize = 64;
+
+/// How values between a stretch's min and max map to display levels.
+///
+/// The equalizing modes flatten the histogram so low-contrast imagery (haze,
+/// SAR, night lights) uses the whole display range. Their lookup tables come
+/// from a histogram of the whole dataset, never of a single tile, so
+/// neighbouring tiles match at their borders.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StretchMode {
+    /// Linear between min and max, then gamma
+    #[default]
+    Linear,
+    /// Histogram equalization
+    Equalize,
+    /// Equalization with a clipped histogram: bins are clipped at
+    /// `clip_limit` times the mean bin count and the excess is spread evenly,
+    /// which keeps large uniform areas from being blown out. Unlike CLAHE the
+    /// limit applies to one dataset-wide histogram rather than per region, so
+    /// tiles need no neighbours to render.
+    ClippedEqualize {
+        #[serde(default = "default_clip_limit")]
+        clip_limit: f64,
+    },
+}
+
+fn default_clip_limit() -> f64 {
+    3.0
+}
+
+impl StretchMode {
+    pub fn is_linear(&self) -> bool {
+        *self == StretchMode::Linear
+    }
+}
+
+/// Display level (0-1) for each histogram bin of a stretch's range
+#[derive(Debug)]
+pub struct ContrastLut {
+    levels: Vec<f64>,
+}
+
+impl ContrastLut {
+    /// Build the lookup table from histogram counts over the stretch range,
+    /// as produced by `compute_histogram_bins`.
+    ///
+    /// Returns `None` for linear mode, or when every value falls in a single
+    /// bin and there is nothing to equalize.
+    pub fn from_histogram(counts: &[u64], mode: StretchMode) -> Option<Self> {
+        let mut counts: Vec<f64> = counts.iter().map(|&c| c as f64).collect();
+
+        match mode {
+            StretchMode::Linear => return None,
+            StretchMode::Equalize => {}
+            StretchMode::ClippedEqualize { clip_limit } => {
+                let total: f64 = counts.iter().sum();
+                let clip = clip_limit.max(1.0) * total / counts.len() as f64;
+                let excess: f64 = counts.iter().map(|&c| (c - clip).max(0.0)).sum();
+                let share = excess / counts.len() as f64;
+                for c in counts.iter_mut() {
+                    *c = c.min(clip) + share;
+                }
+            }
+        }
+
+        // Cumulative distribution, rescaled so the first occupied bin is 0
+        let mut cdf = Vec::with_capacity(counts.len());
+        let mut sum = 0.0;
+        for &c in &counts {
+            sum += c;
+            cdf.push(sum);
+        }
+        let first = cdf.iter().copied().find(|&c| c > 0.0)?;
+        let total = sum;
+        if total <= first {
+            return None;
+        }
+
+        let levels = cdf
+            .iter()
+            .map(|&c| ((c - first) / (total - first)).max(0.0))
+            .collect();
+        Some(Self { levels })
+    }
+
+    /// Display level for a value already normalized to 0-1 within the
+    /// stretch range, binned the same way as `compute_histogram_bins`
+    pub fn level(&self, normalized: f64) -> f64 {
+        let last = self.levels.len() - 1;
+        let bin = (normalized.clamp(0.0, 1.0) * last as f64).floor() as usize;
+        self.levels[bin.min(last)]
+    }
+}
+
+/// Layer id and the serialized band and complex part of a sample
+type SampleKey = (String, String);
+
+/// Sample key plus the serialized range, mode and nodata rules of a table
+type LutKey = (String, String, String);
+
+/// Lookup tables for equalizing stretches, and the band samples they are
+/// binned from.
+///
+/// Building a table needs a sample of the whole band, which is read once per
+/// layer, band and complex part and kept, so changing the stretch range or
+/// mode only rebins it. Both are shared by every tile and held in LRU caches
+/// of bounded size; entries for a layer are dropped when it is closed.
+pub struct LutCache {
+    samples: Mutex<LruCache<SampleKey, Arc<Vec<f64>>>>,
+    luts: Mutex<LruCache<LutKey, Option<Arc<ContrastLut>>>>,
+}
+
+impl Default for LutCache {
+    fn default() -> Self {
+        Self::new(MAX_SAMPLES, MAX_LUTS)
+    }
+}
+
+impl LutCache {
+    pub fn new(max_samples: usize, max_luts: usize) -> Self {
+        let cap = |n: usize| NonZeroUsize::new(n).unwrap_or(NonZeroUsize::MIN);
+        Self {
+            samples: Mutex::new(LruCache::new(cap(max_samples))),
+            luts: Mutex::new(LruCache::new(cap(max_luts))),
+        }
+    }
+
+    /// Look up a table, building and caching it on a miss.
+    ///
+    /// `read_sample` is only called when the sample for `sample_key` is not
+    /// cached either; `build` bins it into the table for `lut_key`.
+    pub fn get_or_build(
+        &self,
+        layer_id: &str,
+        sample_key: String,
+        lut_key: String,
+        read_sample: impl FnOnce() -> Result<Vec<f64>, String>,
+        build: impl FnOnce(&[f64]) -> Option<ContrastLut>,
+    ) -> Result<Option<Arc<ContrastLut>>, String> {
+        let sample_key = (layer_id.to_string(), sample_key);
+        let lut_key = (sample_key.0.clone(), sample_key.1.clone(), lut_key);
+        if let Some(lut) = self.luts.lock().unwrap().get(&lut_key) {
+            return Ok(lut.clone());
+        }
+
+        // Read and build outside the locks; a concurrent build of the same
+        // table is harmless
+        let cached = self.samples.lock().unwrap().get(&sample_key).cloned();
+        let sample = match cached {
+            Some(sample) => sample,
+            None => {
+                let sample = Arc::new(read_sample()?);
+                self.samples
+                    .lock()
+                    .unwrap()
+                    .put(sample_key, Arc::clone(&sample));
+                sample
+            }
+        };
+
+        let lut = build(&sample).map(Arc::new);
+        self.luts.lock().unwrap().put(lut_key, lut.clone());
+        Ok(lut)
+    }
+
+    /// Drop every table and sample built for the given layer
+    pub fn invalidate_layer(&self, layer_id: &str) {
+        remove_where(&mut self.samples.lock().unwrap(), |(id, _)| id == layer_id);
+        remove_where(&mut self.luts.lock().unwrap(), |(id, _, _)| id == layer_id);
+    }
+}
+
+/// Pop every entry whose key matches
+fn remove_where<K: Hash + Eq + Clone, V>(cache: &mut LruCache<K, V>, matches: impl Fn(&K) -> bool) {
+    let stale: Vec<K> = cache
+        .iter()
+        .filter(|(key, _)| matches(key))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in stale {
+        cache.pop(&key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equalize_spreads_crowded_bins() {
+        // Most values in the low bins: they get most of the display range
+        let lut = ContrastLut::from_histogram(&[60, 30, 0, 10], StretchMode::Equalize).unwrap();
+        assert_eq!(lut.level(0.0), 0.0);
+        assert!((lut.level(0.4) - 0.75).abs() < 1e-9);
+        assert!((lut.level(0.7) - 0.75).abs() < 1e-9);
+        assert_eq!(lut.level(1.0), 1.0);
+    }
+
+    #[test]
+    fn test_clipped_equalize_limits_contrast() {
+        let counts = [90, 5, 0, 5];
+        let equalized = ContrastLut::from_histogram(&counts, StretchMode::Equalize).unwrap();
+        let limited =
+            ContrastLut::from_histogram(&counts, StretchMode::ClippedEqualize { clip_limit: 1.0 })
+                .unwrap();
+
+        // Clipped to 25 per bin, with the excess 65 shared out: 41.25, 21.25, 16.25, 21.25
+        assert!((limited.level(0.4) - 21.25 / 58.75).abs() < 1e-9);
+        assert!((limited.level(0.7) - 37.5 / 58.75).abs() < 1e-9);
+        assert_eq!(limited.level(1.0), 1.0);
+
+        // Equalizing jumps by half the range at a bin holding 5% of the
+        // pixels; the clipped version never jumps more than 0.4
+        let step = |lut: &ContrastLut| {
+            [0.0, 0.4, 0.7, 1.0]
+                .windows(2)
+                .map(|w| lut.level(w[1]) - lut.level(w[0]))
+                .fold(0.0, f64::max)
+        };
+        assert_eq!(step(&equalized), 0.5);
+        assert!(step(&limited) < 0.4);
+    }
+
+    #[test]
+    fn test_degenerate_histograms() {
+        assert!(ContrastLut::from_histogram(&[0, 10, 0], StretchMode::Equalize).is_none());
+        assert!(ContrastLut::from_histogram(&[0, 0, 0], StretchMode::Equalize).is_none());
+        assert!(ContrastLut::from_histogram(&[5, 5], StretchMode::Linear).is_none());
+    }
+
+    #[test]
+    fn test_mode_serde() {
+        let mode: StretchMode = serde_json::from_str("{\"type\": \"clipped_equalize\"}").unwrap();
+        assert_eq!(mode, StretchMode::ClippedEqualize { clip_limit: 3.0 });
+        // Not adaptive, so not accepted as CLAHE
+        assert!(serde_json::from_str::<StretchMode>("{\"type\": \"clahe\"}").is_err());
+        let mode: StretchMode = serde_json::from_str("{\"type\": \"equalize\"}").unwrap();
+        assert_eq!(mode, StretchMode::Equalize);
+        assert!(StretchMode::default().is_linear());
+    }
+
+    fn reread() -> Result<Vec<f64>, String> {
+        Err("sample read again".to_string())
+    }
+
+    fn equalize(sample: &[f64]) -> Option<ContrastLut> {
+        let counts: Vec<u64> = sample.iter().map(|&v| v as u64).collect();
+        ContrastLut::from_histogram(&counts, StretchMode::Equalize)
+    }
+
+    #[test]
+    fn test_lut_cache_invalidate() {
+        let cache = LutCache::default();
+        let sample = || Ok(vec![1.0, 1.0]);
+        let lut = cache.get_or_build("a", "1".into(), "k".into(), sample, equalize);
+        assert!(lut.unwrap().is_some());
+
+        // Cached: neither the sample nor the table is built again
+        let lut = cache.get_or_build("a", "1".into(), "k".into(), reread, |_| panic!("rebuilt"));
+        assert!(lut.unwrap().is_some());
+
+        cache.invalidate_layer("a");
+        assert!(cache
+            .get_or_build("a", "1".into(), "k".into(), reread, equalize)
+            .is_err());
+    }
+
+    #[test]
+    fn test_lut_cache_reuses_sample() {
+        let cache = LutCache::default();
+        let sample = || Ok(vec![1.0, 1.0]);
+        cache
+            .get_or_build("a", "1".into(), "0-10".into(), sample, equalize)
+            .unwrap();
+
+        // A new range rebins the sample already read
+        let lut = cache.get_or_build("a", "1".into(), "0-20".into(), reread, equalize);
+        assert!(lut.unwrap().is_some());
+
+        // Another band reads its own
+        let lut = cache.get_or_build("a", "2".into(), "0-20".into(), reread, equalize);
+        assert!(lut.is_err());
+    }
+
+    #[test]
+    fn test_lut_cache_bounded() {
+        let cache = LutCache::new(1, 2);
+        for range in ["0-10", "0-20", "0-30"] {
+            let sample = || Ok(vec![1.0, 1.0]);
+            cache
+                .get_or_build("a", "1".into(), range.into(), sample, equalize)
+                .unwrap();
+        }
+        assert_eq!(cache.luts.lock().unwrap().len(), 2);
+
+        // Reading band 2 evicts band 1's sample
+        let sample = || Ok(vec![1.0, 1.0]);
+        cache
+            .get_or_build("a", "2".into(), "0-10".into(), sample, equalize)
+            .unwrap();
+        assert_eq!(cache.samples.lock().unwrap().len(), 1);
+        let lut = cache.get_or_build("a", "1".into(), "0-40".into(), reread, equalize);
+        assert!(lut.is_err());
+    }
+}
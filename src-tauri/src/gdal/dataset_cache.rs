@@ -1,30 +1,126 @@
+use crate::gdal::band_math::remove_output;
+use crate::gdal::dimensions::{slice_band, slice_bands, DimensionSlice, RasterDimension};
+use gdal::{Dataset, DatasetOptions};
 use lru::LruCache;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Mutex;
 
-/// Stores file paths for datasets (not the datasets themselves, since GDAL Dataset is not thread-safe).
-/// Each command will open the dataset fresh as needed.
+/// Bumped whenever a dataset id is removed or evicted, so each worker thread
+/// knows its pool may hold handles that are no longer registered.
+static POOL_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// Open GDAL datasets for the current thread, keyed by layer id.
+    ///
+    /// GDAL dataset handles must not be used from several threads at once, so
+    /// every worker thread keeps its own handles instead of sharing them.
+    static DATASET_POOL: RefCell<DatasetPool> = RefCell::new(DatasetPool::default());
+}
+
+#[derive(Default)]
+struct DatasetPool {
+    generation: u64,
+    datasets: HashMap<String, PooledDataset>,
+}
+
+struct PooledDataset {
+    path: String,
+    generation: u64,
+    dataset: Rc<Dataset>,
+    /// Overview levels opened as datasets of their own, for warping
+    overviews: HashMap<usize, Rc<Dataset>>,
+}
+
+/// Open a dataset, configuring GDAL for remote access when needed
+pub fn open_dataset(path: &str) -> Result<Dataset, String> {
+    open_dataset_with(path, &[])
+}
+
+/// Like [`open_dataset`], with GDAL open options such as `OVERVIEW_LEVEL=2`
+fn open_dataset_with(path: &str, open_options: &[&str]) -> Result<Dataset, String> {
+    let options = DatasetOptions {
+        open_options: (!open_options.is_empty()).then_some(open_options),
+        ..Default::default()
+    };
+
+    // For remote COGs (vsicurl), set GDAL config for proper access
+    let is_remote = path.starts_with("/vsicurl/");
+
+    if is_remote {
+        // Ensure GDAL config is set for remote access in this thread
+        // GDAL config options are thread-local, so we need to set them here
+        gdal::config::set_config_option("GDAL_DISABLE_READDIR_ON_OPEN", "EMPTY_DIR").ok();
+
+        // Longer timeout and retries for slow connections (e.g., Wyvern data)
+        gdal::config::set_config_option("GDAL_HTTP_TIMEOUT", "300").ok();
+        gdal::config::set_config_option("GDAL_HTTP_MAX_RETRY", "5").ok();
+        gdal::config::set_config_option("GDAL_HTTP_RETRY_DELAY", "2").ok();
+
+        // Enable caching with larger buffer for pixel-interleaved COGs
+        gdal::config::set_config_option("VSI_CACHE", "TRUE").ok();
+        gdal::config::set_config_option("VSI_CACHE_SIZE", "100000000").ok(); // 100MB cache
+        gdal::config::set_config_option("CPL_VSIL_CURL_CACHE_SIZE", "100000000").ok();
+
+        // For COGs, GDAL automatically uses appropriate overviews during read operations
+        // based on the requested window size. No need to explicitly select overview level.
+        Dataset::open_ex(path, options).map_err(|e| format!("Failed to open remote: {}", e))
+    } else {
+        // Local files - just open normally
+        Dataset::open_ex(path, options).map_err(|e| format!("Failed to open: {}", e))
+    }
+}
+
+/// Stores file paths for datasets, keyed by layer id, along with the
+/// non-spatial dimensions of multidimensional rasters.
+///
+/// Only the most recently used layers are kept registered; an evicted layer
+/// keeps its path and is registered again on its next use, so its handles are
+/// reopened rather than lost. Files are only deleted (band-math results) when
+/// a layer is closed.
+///
+/// The dataset handles themselves live in a per-thread pool (see
+/// [`DatasetCache::get_dataset`]): a handle is opened the first time a worker
+/// thread needs it and reused for every later tile, query and histogram on that
+/// thread. This keeps GDAL's header and `/vsicurl/` caches warm without sharing
+/// a handle between threads.
 ///
 /// # Thread Safety
 ///
 /// This struct is safe to share across threads because:
-/// - The only field is `Mutex<LruCache<String, String>>`
+/// - The fields are `Mutex<LruCache<String, String>>`, `Mutex<HashMap<String, String>>`,
+///   `Mutex<HashMap<String, Vec<RasterDimension>>>`, `Mutex<HashMap<String, u64>>`
+///   and `Mutex<Vec<String>>`
 /// - `Mutex<T>` is `Send + Sync` when `T: Send`
-/// - `LruCache<String, String>` contains only `String` which is `Send + Sync`
-/// - All access to the inner cache goes through the Mutex
+/// - The maps contain only `String`, `f64`, `u64` and `Vec`s of them, which are `Send + Sync`
+/// - All access to the inner maps goes through the Mutexes
 ///
 /// The manual `Send` and `Sync` implementations are required because the compiler
 /// cannot automatically derive them due to the LruCache type's internal structure,
 /// but the invariants above guarantee safety.
 pub struct DatasetCache {
     paths: Mutex<LruCache<String, String>>,
+    /// Paths of layers evicted from `paths` but not closed
+    evicted: Mutex<HashMap<String, String>>,
+    dimensions: Mutex<HashMap<String, Vec<RasterDimension>>>,
+    /// Pool generation at which each layer's file last changed on disk;
+    /// handles opened before it are reopened
+    reopened: Mutex<HashMap<String, u64>>,
+    /// Band-math results of closed layers that could not be deleted yet
+    /// because a thread still had them open
+    pending_removals: Mutex<Vec<String>>,
 }
 
-// SAFETY: DatasetCache only contains Mutex<LruCache<String, String>>.
+// SAFETY: DatasetCache only contains Mutex<LruCache<String, String>> and
+// Mutexes around HashMaps of plain data.
 // - Mutex<T> is Send when T: Send (LruCache<String, String> is Send)
 // - Mutex<T> is Sync when T: Send (same reasoning)
 // - All operations acquire the mutex lock before accessing the cache
 // - String is both Send and Sync
+// Open datasets are kept in thread-local storage and never cross threads.
 unsafe impl Send for DatasetCache {}
 unsafe impl Sync for DatasetCache {}
 
@@ -33,22 +129,97 @@ impl DatasetCache {
         let cap = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(10).unwrap());
         Self {
             paths: Mutex::new(LruCache::new(cap)),
+            evicted: Mutex::new(HashMap::new()),
+            dimensions: Mutex::new(HashMap::new()),
+            reopened: Mutex::new(HashMap::new()),
+            pending_removals: Mutex::new(Vec::new()),
         }
     }
 
+    /// Path of a layer's file, registering an evicted layer again
     pub fn get_path(&self, id: &str) -> Option<String> {
-        let mut cache = self.paths.lock().unwrap();
-        cache.get(id).cloned()
+        if let Some(path) = self.paths.lock().unwrap().get(id) {
+            return Some(path.clone());
+        }
+        let path = self.evicted.lock().unwrap().remove(id)?;
+        self.add(id.to_string(), path.clone());
+        Some(path)
     }
 
+    /// Register a layer's file. The least recently used layer may be evicted
+    /// to make room; it keeps its path and dimensions, and only its pooled
+    /// handles are dropped.
     pub fn add(&self, id: String, path: String) {
         let mut cache = self.paths.lock().unwrap();
-        cache.put(id, path);
+        self.evicted.lock().unwrap().remove(&id);
+        if let Some((old_id, old_path)) = cache.push(id.clone(), path) {
+            // Either an older layer was evicted or an id was re-registered
+            if old_id != id {
+                self.evicted.lock().unwrap().insert(old_id, old_path);
+            }
+            POOL_GENERATION.fetch_add(1, Ordering::Relaxed);
+        }
     }
 
+    /// Unregister a layer, deleting its file if it is a band-math result.
+    ///
+    /// Other threads close their handles for it lazily (see
+    /// [`DatasetCache::get_dataset`]); a file one of them still holds open
+    /// where open files cannot be deleted (Windows) is deleted once the
+    /// handles are dropped.
     pub fn remove(&self, id: &str) {
-        let mut cache = self.paths.lock().unwrap();
-        cache.pop(id);
+        let path = self.paths.lock().unwrap().pop(id);
+        let path = path.or_else(|| self.evicted.lock().unwrap().remove(id));
+        self.dimensions.lock().unwrap().remove(id);
+        self.reopened.lock().unwrap().remove(id);
+        POOL_GENERATION.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(path) = path.filter(|path| !remove_output(path)) {
+            self.pending_removals.lock().unwrap().push(path);
+        }
+    }
+
+    /// Retry deleting closed layers' band-math results, after this thread
+    /// dropped its handles
+    fn retry_removals(&self) {
+        self.pending_removals
+            .lock()
+            .unwrap()
+            .retain(|path| !remove_output(path));
+    }
+
+    /// Make every thread reopen a layer's file on its next access, after it
+    /// changed on disk (e.g. overviews were added)
+    pub fn reopen(&self, id: &str) {
+        // Record the generation before publishing it: a thread that sees the
+        // bump must also find the entry, or it would keep its stale handle
+        // and never look again
+        let mut reopened = self.reopened.lock().unwrap();
+        let generation = POOL_GENERATION.load(Ordering::Relaxed) + 1;
+        reopened.insert(id.to_string(), generation);
+        POOL_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record the non-spatial dimensions behind a layer's bands
+    pub fn set_dimensions(&self, id: &str, dimensions: Vec<RasterDimension>) {
+        let mut cache = self.dimensions.lock().unwrap();
+        if dimensions.is_empty() {
+            cache.remove(id);
+        } else {
+            cache.insert(id.to_string(), dimensions);
+        }
+    }
+
+    /// Band to draw for a slice of a layer's dimensions
+    pub fn slice_band(&self, id: &str, slice: &DimensionSlice) -> Result<usize, String> {
+        let cache = self.dimensions.lock().unwrap();
+        slice_band(cache.get(id).map(Vec::as_slice).unwrap_or_default(), slice)
+    }
+
+    /// Bands covered by a slice of a layer's dimensions
+    pub fn slice_bands(&self, id: &str, slice: &DimensionSlice) -> Result<Vec<usize>, String> {
+        let cache = self.dimensions.lock().unwrap();
+        slice_bands(cache.get(id).map(Vec::as_slice).unwrap_or_default(), slice)
     }
 
     #[allow(dead_code)]
@@ -56,4 +227,108 @@ impl DatasetCache {
         let cache = self.paths.lock().unwrap();
         cache.len()
     }
+
+    /// Get the open dataset for a layer from the current thread's pool,
+    /// opening it on first use.
+    ///
+    /// The returned handle must stay on the calling thread; it is only valid
+    /// for the duration of a synchronous command body.
+    ///
+    /// Handles of closed or evicted layers are only dropped when a thread next
+    /// calls this, since no other thread can reach its pool. Until then the
+    /// file stays open, which on Windows keeps it from being deleted or
+    /// replaced; a closed band-math result whose deletion failed is retried
+    /// here each time a thread drops handles. The async runtime's workers run
+    /// every command and soon look again; blocking-pool threads exit after
+    /// idling briefly, dropping their pools with them.
+    pub fn get_dataset(&self, id: &str) -> Result<Rc<Dataset>, String> {
+        let path = self.get_path(id).ok_or("Dataset not found")?;
+
+        DATASET_POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+
+            // Drop handles for layers that were closed or evicted since the
+            // last time this thread looked at its pool
+            let generation = POOL_GENERATION.load(Ordering::Relaxed);
+            if pool.generation != generation {
+                let cache = self.paths.lock().unwrap();
+                let reopened = self.reopened.lock().unwrap();
+                pool.datasets.retain(|pooled_id, pooled| {
+                    cache.peek(pooled_id).is_some_and(|p| *p == pooled.path)
+                        && reopened
+                            .get(pooled_id)
+                            .is_none_or(|&changed| pooled.generation >= changed)
+                });
+                pool.generation = generation;
+                self.retry_removals();
+            }
+
+            if let Some(pooled) = pool.datasets.get(id) {
+                if pooled.path == path {
+                    return Ok(Rc::clone(&pooled.dataset));
+                }
+            }
+
+            let dataset = Rc::new(open_dataset(&path)?);
+            let generation = pool.generation;
+            pool.datasets.insert(
+                id.to_string(),
+                PooledDataset {
+                    path,
+                    generation,
+                    dataset: Rc::clone(&dataset),
+                    overviews: HashMap::new(),
+                },
+            );
+            Ok(dataset)
+        })
+    }
+}
+
+/// Overview `level` of a dataset from the current thread's pool, opened as a
+/// dataset of its own (with the `OVERVIEW_LEVEL` open option) and kept with
+/// it, so it is reused by later reads and dropped along with it.
+///
+/// Returns `Ok(None)` for datasets that are not pooled, such as in-memory
+/// ones, which have no path to reopen.
+pub fn pooled_overview(dataset: &Dataset, level: usize) -> Result<Option<Rc<Dataset>>, String> {
+    DATASET_POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+        let Some(pooled) = pool
+            .datasets
+            .values_mut()
+            .find(|pooled| pooled.dataset.c_dataset() == dataset.c_dataset())
+        else {
+            return Ok(None);
+        };
+
+        if let Some(overview) = pooled.overviews.get(&level) {
+            return Ok(Some(Rc::clone(overview)));
+        }
+        let level_option = format!("OVERVIEW_LEVEL={}", level);
+        let overview = Rc::new(open_dataset_with(&pooled.path, &[level_option.as_str()])?);
+        pooled.overviews.insert(level, Rc::clone(&overview));
+        Ok(Some(overview))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicted_layer_keeps_its_path() {
+        let cache = DatasetCache::new(1);
+        cache.add("a".into(), "/data/a.tif".into());
+        cache.add("b".into(), "/data/b.tif".into());
+        assert_eq!(cache.len(), 1);
+
+        // "a" comes back, evicting "b" in turn
+        assert_eq!(cache.get_path("a").as_deref(), Some("/data/a.tif"));
+        assert_eq!(cache.get_path("b").as_deref(), Some("/data/b.tif"));
+
+        cache.remove("a");
+        assert_eq!(cache.get_path("a"), None);
+        assert_eq!(cache.get_path("b").as_deref(), Some("/data/b.tif"));
+    }
 }
@@ -0,0 +1,451 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use gdal::cpl::CslStringList;
+use gdal::raster::Group;
+use gdal::{Dataset, DatasetOptions, GdalOpenFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Non-spatial dimension of a multidimensional raster (time, depth, level),
+/// which the classic raster API flattens into bands
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RasterDimension {
+    pub name: String,
+    /// Coordinate value at each index; the index itself when the file has no
+    /// numeric coordinate variable for the dimension
+    pub values: Vec<f64>,
+    /// Display text at each index: dates for CF time axes, otherwise the value
+    pub labels: Vec<String>,
+    pub units: Option<String>,
+}
+
+/// Position along a dimension, by index or by the nearest coordinate value
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DimensionSelector {
+    Index(usize),
+    Value(f64),
+}
+
+/// Selected position of some dimensions, keyed by dimension name
+pub type DimensionSlice = HashMap<String, DimensionSelector>;
+
+impl RasterDimension {
+    fn index_of(&self, selector: DimensionSelector) -> Result<usize, String> {
+        match selector {
+            DimensionSelector::Index(index) if index < self.values.len() => Ok(index),
+            DimensionSelector::Index(index) => Err(format!(
+                "Index {} is out of range for dimension {} ({} values)",
+                index,
+                self.name,
+                self.values.len()
+            )),
+            DimensionSelector::Value(value) => self
+                .values
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| !v.is_nan())
+                .min_by(|(_, a), (_, b)| (*a - value).abs().total_cmp(&(*b - value).abs()))
+                .map(|(index, _)| index)
+                .ok_or_else(|| format!("Dimension {} has no values", self.name)),
+        }
+    }
+}
+
+/// 1-based bands of a slice: selected dimensions stay fixed and the others
+/// run over all their indices.
+///
+/// GDAL numbers bands row-major over the non-spatial dimensions in
+/// declaration order, the last one varying fastest.
+pub fn slice_bands(dims: &[RasterDimension], slice: &DimensionSlice) -> Result<Vec<usize>, String> {
+    if let Some(name) = slice
+        .keys()
+        .find(|name| !dims.iter().any(|d| &d.name == *name))
+    {
+        return Err(format!("Unknown dimension: {}", name));
+    }
+
+    let mut bands = vec![0];
+    for dim in dims {
+        let size = dim.values.len();
+        let indices = match slice.get(&dim.name) {
+            Some(&selector) => vec![dim.index_of(selector)?],
+            None => (0..size).collect(),
+        };
+        bands = bands
+            .iter()
+            .flat_map(|band| indices.iter().map(move |index| band * size + index))
+            .collect();
+    }
+    Ok(bands.into_iter().map(|band| band + 1).collect())
+}
+
+/// Band to draw for a slice; unselected dimensions are taken at index 0
+pub fn slice_band(dims: &[RasterDimension], slice: &DimensionSlice) -> Result<usize, String> {
+    slice_bands(dims, slice)?
+        .first()
+        .copied()
+        .ok_or_else(|| "Slice selects no bands".to_string())
+}
+
+/// Dimensions behind the bands of the raster at `path`, read through GDAL's
+/// multidimensional API. Empty for ordinary rasters, or when no array in the
+/// file lines up with the raster's size and band count.
+pub fn read_dimensions(
+    path: &str,
+    width: usize,
+    height: usize,
+    bands: usize,
+) -> Vec<RasterDimension> {
+    if bands < 2 {
+        return Vec::new();
+    }
+    try_read_dimensions(path, width, height, bands).unwrap_or_default()
+}
+
+fn try_read_dimensions(
+    path: &str,
+    width: usize,
+    height: usize,
+    bands: usize,
+) -> Result<Vec<RasterDimension>, String> {
+    let (file, array_path) = split_connection_string(path);
+    let dataset = Dataset::open_ex(
+        file,
+        DatasetOptions {
+            open_flags: GdalOpenFlags::GDAL_OF_MULTIDIM_RASTER,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| format!("Failed to open multidimensional dataset: {}", e))?;
+    let root = dataset
+        .root_group()
+        .map_err(|e| format!("Failed to get root group: {}", e))?;
+
+    // Subdataset names point at one array; a plain path is the file's only
+    // raster variable, found among the root arrays by shape
+    let (group, candidates) = match array_path {
+        Some(array_path) => {
+            let mut segments: Vec<&str> = array_path.split('/').filter(|s| !s.is_empty()).collect();
+            let name = segments.pop().ok_or("Empty array path")?;
+            let mut group = None;
+            for segment in segments {
+                let parent = group.as_ref().unwrap_or(&root);
+                let child = parent
+                    .open_group(segment, CslStringList::new())
+                    .map_err(|e| format!("Failed to open group {}: {}", segment, e))?;
+                group = Some(child);
+            }
+            (group, vec![name.to_string()])
+        }
+        None => (None, root.array_names(CslStringList::new())),
+    };
+    let group = group.as_ref().unwrap_or(&root);
+
+    for name in candidates {
+        let Ok(array) = group.open_md_array(&name, CslStringList::new()) else {
+            continue;
+        };
+        let dimensions = array
+            .dimensions()
+            .map_err(|e| format!("Failed to get dimensions of {}: {}", name, e))?;
+        let sizes: Vec<usize> = dimensions.iter().map(|d| d.size()).collect();
+        if !is_band_stack(&sizes, width, height, bands) {
+            continue;
+        }
+
+        return Ok(dimensions[..dimensions.len() - 2]
+            .iter()
+            .map(|dim| read_dimension(&[group, &root], &dim.name(), dim.size()))
+            .collect());
+    }
+
+    Ok(Vec::new())
+}
+
+/// Whether an array of `sizes` (slowest varying first) is what the classic
+/// API shows as a `width` × `height` raster with `bands` bands
+fn is_band_stack(sizes: &[usize], width: usize, height: usize, bands: usize) -> bool {
+    match sizes {
+        [extra @ .., rows, cols] if !extra.is_empty() => {
+            *rows == height && *cols == width && extra.iter().product::<usize>() == bands
+        }
+        _ => false,
+    }
+}
+
+/// Read a dimension's coordinate variable, which netCDF, HDF5 and Zarr store
+/// as a 1-D array named after the dimension
+fn read_dimension(groups: &[&Group], name: &str, size: usize) -> RasterDimension {
+    let indices: Vec<f64> = (0..size).map(|i| i as f64).collect();
+    let mut dimension = RasterDimension {
+        name: name.to_string(),
+        labels: indices.iter().map(|i| i.to_string()).collect(),
+        values: indices,
+        units: None,
+    };
+
+    let Some(coordinate) = groups
+        .iter()
+        .find_map(|group| group.open_md_array(name, CslStringList::new()).ok())
+        .filter(|array| array.num_dimensions() == 1 && array.num_elements() as usize == size)
+    else {
+        return dimension;
+    };
+
+    if coordinate.datatype().class().is_string() {
+        if let Ok(labels) = coordinate.read_as_string_array() {
+            if labels.len() == size {
+                dimension.labels = labels;
+            }
+        }
+        return dimension;
+    }
+
+    let Ok(values) = coordinate.read_as::<f64>(vec![0], vec![size]) else {
+        return dimension;
+    };
+    let units = Some(coordinate.unit())
+        .filter(|u| !u.is_empty())
+        .or_else(|| {
+            coordinate
+                .attribute("units")
+                .ok()
+                .map(|a| a.read_as_string())
+        })
+        .filter(|u| !u.is_empty());
+    let calendar = coordinate
+        .attribute("calendar")
+        .ok()
+        .map(|a| a.read_as_string());
+
+    dimension.labels = units
+        .as_deref()
+        .and_then(|units| cf_time_labels(&values, units, calendar.as_deref()))
+        .unwrap_or_else(|| values.iter().map(|v| v.to_string()).collect());
+    dimension.values = values;
+    dimension.units = units;
+    dimension
+}
+
+/// File and array path of a `DRIVER:"file":array` subdataset name; other
+/// paths are returned whole
+fn split_connection_string(path: &str) -> (&str, Option<&str>) {
+    let Some(start) = path.find(":\"") else {
+        return (path, None);
+    };
+    if !path[..start]
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    {
+        return (path, None);
+    }
+    match path[start + 2..].rsplit_once("\":") {
+        Some((file, array)) if !array.is_empty() => (file, Some(array)),
+        _ => (path, None),
+    }
+}
+
+/// Dates of a CF time axis (`days since 1950-01-01` and the like): `YYYY-MM-DD`
+/// when every step falls on midnight, otherwise with the time of day.
+///
+/// None for other units, and for calendars other than the standard Gregorian
+/// one, whose day counts chrono cannot follow.
+fn cf_time_labels(values: &[f64], units: &str, calendar: Option<&str>) -> Option<Vec<String>> {
+    if let Some(calendar) = calendar {
+        let calendar = calendar.trim().to_ascii_lowercase();
+        if !matches!(
+            calendar.as_str(),
+            "" | "standard" | "gregorian" | "proleptic_gregorian"
+        ) {
+            return None;
+        }
+    }
+
+    let (unit, reference) = units.split_once(" since ")?;
+    let seconds_per_unit = match unit.trim().to_ascii_lowercase().as_str() {
+        "days" | "day" | "d" => 86400.0,
+        "hours" | "hour" | "hrs" | "hr" | "h" => 3600.0,
+        "minutes" | "minute" | "mins" | "min" => 60.0,
+        "seconds" | "second" | "secs" | "sec" | "s" => 1.0,
+        _ => return None,
+    };
+    let reference = parse_reference_time(reference)?;
+
+    let times = values
+        .iter()
+        .map(|v| {
+            let seconds = v * seconds_per_unit;
+            if !seconds.is_finite() {
+                return None;
+            }
+            let offset = TimeDelta::try_milliseconds((seconds * 1000.0).round() as i64)?;
+            reference.checked_add_signed(offset)
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    let format = if times.iter().all(|t| t.time() == NaiveTime::MIN) {
+        "%Y-%m-%d"
+    } else {
+        "%Y-%m-%d %H:%M:%S"
+    };
+    Some(times.iter().map(|t| t.format(format).to_string()).collect())
+}
+
+/// Reference time of CF units: `1950-01-01`, `1950-1-1 0:0:0`,
+/// `2000-01-01T12:00:00Z` and similar; any zone suffix is ignored
+fn parse_reference_time(text: &str) -> Option<NaiveDateTime> {
+    let mut parts = text.trim().splitn(2, [' ', 'T']);
+    let mut date = parts.next()?.split('-').map(|p| p.parse::<i32>().ok());
+    let date = NaiveDate::from_ymd_opt(date.next()??, date.next()?? as u32, date.next()?? as u32)?;
+
+    let time = match parts.next().map(str::trim) {
+        Some(time) if !time.is_empty() => {
+            let clock = time
+                .split(|c: char| c.is_whitespace() || c == 'Z' || c == '+')
+                .next()?;
+            let mut fields = clock.split(':').map(|f| f.parse::<f64>().ok());
+            let hour = fields.next().flatten()?;
+            let minute = fields.next().flatten().unwrap_or(0.0);
+            let second = fields.next().flatten().unwrap_or(0.0);
+            NaiveTime::from_hms_milli_opt(
+                hour as u32,
+                minute as u32,
+                second.trunc() as u32,
+                (second.fract() * 1000.0).round() as u32,
+            )?
+        }
+        _ => NaiveTime::MIN,
+    };
+    Some(date.and_time(time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dimension(name: &str, values: &[f64]) -> RasterDimension {
+        RasterDimension {
+            name: name.to_string(),
+            values: values.to_vec(),
+            labels: values.iter().map(|v| v.to_string()).collect(),
+            units: None,
+        }
+    }
+
+    fn slice(selectors: &[(&str, DimensionSelector)]) -> DimensionSlice {
+        selectors
+            .iter()
+            .map(|(name, selector)| (name.to_string(), *selector))
+            .collect()
+    }
+
+    #[test]
+    fn test_slice_bands_row_major() {
+        // time × depth, depth varying fastest
+        let dims = [
+            dimension("time", &[0.0, 1.0, 2.0]),
+            dimension("depth", &[5.0, 10.0]),
+        ];
+
+        let band = slice_band(
+            &dims,
+            &slice(&[
+                ("time", DimensionSelector::Index(2)),
+                ("depth", DimensionSelector::Index(1)),
+            ]),
+        );
+        assert_eq!(band, Ok(6));
+
+        // Leaving a dimension out runs over all of it
+        let bands = slice_bands(&dims, &slice(&[("depth", DimensionSelector::Index(1))]));
+        assert_eq!(bands, Ok(vec![2, 4, 6]));
+        let bands = slice_bands(&dims, &slice(&[("time", DimensionSelector::Index(1))]));
+        assert_eq!(bands, Ok(vec![3, 4]));
+        // ...but tiles take its first index
+        let band = slice_band(&dims, &slice(&[("time", DimensionSelector::Index(1))]));
+        assert_eq!(band, Ok(3));
+    }
+
+    #[test]
+    fn test_slice_by_nearest_value() {
+        let dims = [dimension("depth", &[0.0, 10.0, 50.0, 100.0])];
+
+        let band = slice_band(&dims, &slice(&[("depth", DimensionSelector::Value(42.0))]));
+        assert_eq!(band, Ok(3));
+        let band = slice_band(&dims, &slice(&[("depth", DimensionSelector::Value(-5.0))]));
+        assert_eq!(band, Ok(1));
+    }
+
+    #[test]
+    fn test_slice_errors() {
+        let dims = [dimension("time", &[0.0, 1.0])];
+
+        assert!(slice_band(&dims, &slice(&[("time", DimensionSelector::Index(2))])).is_err());
+        assert!(slice_band(&dims, &slice(&[("level", DimensionSelector::Index(0))])).is_err());
+    }
+
+    #[test]
+    fn test_is_band_stack() {
+        assert!(is_band_stack(&[12, 4, 720, 1440], 1440, 720, 48));
+        assert!(!is_band_stack(&[12, 720, 1440], 720, 1440, 12));
+        assert!(!is_band_stack(&[720, 1440], 1440, 720, 1));
+    }
+
+    #[test]
+    fn test_split_connection_string() {
+        assert_eq!(
+            split_connection_string("NETCDF:\"/data/ocean.nc\":sst"),
+            ("/data/ocean.nc", Some("sst"))
+        );
+        assert_eq!(
+            split_connection_string("HDF5:\"/data/l2.h5\"://Grid/b2"),
+            ("/data/l2.h5", Some("//Grid/b2"))
+        );
+        assert_eq!(
+            split_connection_string("/data/ocean.nc"),
+            ("/data/ocean.nc", None)
+        );
+        assert_eq!(
+            split_connection_string("GTIFF_DIR:2:/data/frames.tif"),
+            ("GTIFF_DIR:2:/data/frames.tif", None)
+        );
+    }
+
+    #[test]
+    fn test_cf_time_labels() {
+        let labels = cf_time_labels(&[0.0, 31.0, 366.0], "days since 2000-01-01", None);
+        assert_eq!(
+            labels,
+            Some(vec![
+                "2000-01-01".to_string(),
+                "2000-02-01".to_string(),
+                "2001-01-01".to_string(),
+            ])
+        );
+
+        let labels = cf_time_labels(&[0.0, 6.0], "hours since 1900-1-1 0:0:0", Some("gregorian"));
+        assert_eq!(
+            labels,
+            Some(vec![
+                "1900-01-01 00:00:00".to_string(),
+                "1900-01-01 06:00:00".to_string(),
+            ])
+        );
+
+        let labels = cf_time_labels(&[1.5], "days since 2020-03-01T00:00:00Z", None);
+        assert_eq!(labels, Some(vec!["2020-03-02 12:00:00".to_string()]));
+    }
+
+    #[test]
+    fn test_cf_time_labels_unsupported() {
+        assert_eq!(cf_time_labels(&[0.0], "m", None), None);
+        assert_eq!(
+            cf_time_labels(&[0.0], "months since 2000-01-01", None),
+            None
+        );
+        assert_eq!(
+            cf_time_labels(&[0.0], "days since 2000-01-01", Some("360_day")),
+            None
+        );
+    }
+}
@@ -0,0 +1,85 @@
+/// Native coordinates of a fractional pixel position (column, row), using all
+/// six geotransform terms so rotated and sheared grids map correctly
+pub fn pixel_to_geo(gt: &[f64; 6], col: f64, row: f64) -> (f64, f64) {
+    (
+        gt[0] + col * gt[1] + row * gt[2],
+        gt[3] + col * gt[4] + row * gt[5],
+    )
+}
+
+/// Fractional pixel position (column, row) of native coordinates; the
+/// inverse of `pixel_to_geo`. None when the geotransform is not invertible
+pub fn geo_to_pixel(gt: &[f64; 6], x: f64, y: f64) -> Option<(f64, f64)> {
+    let det = gt[1] * gt[5] - gt[2] * gt[4];
+    if det == 0.0 || !det.is_finite() {
+        return None;
+    }
+
+    let dx = x - gt[0];
+    let dy = y - gt[3];
+    Some((
+        (gt[5] * dx - gt[2] * dy) / det,
+        (gt[1] * dy - gt[4] * dx) / det,
+    ))
+}
+
+/// Ground distance between neighbouring pixels along a row and down a
+/// column; `[gt[1], |gt[5]|]` for north-up rasters
+pub fn pixel_size(gt: &[f64; 6]) -> [f64; 2] {
+    [gt[1].hypot(gt[4]), gt[2].hypot(gt[5])]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 30° rotation with 2 m pixels
+    fn rotated() -> [f64; 6] {
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        [
+            500000.0,
+            2.0 * cos,
+            2.0 * sin,
+            4000000.0,
+            2.0 * sin,
+            -2.0 * cos,
+        ]
+    }
+
+    #[test]
+    fn test_north_up() {
+        let gt = [100.0, 10.0, 0.0, 500.0, 0.0, -10.0];
+        assert_eq!(pixel_to_geo(&gt, 2.0, 3.0), (120.0, 470.0));
+        assert_eq!(geo_to_pixel(&gt, 125.0, 465.0), Some((2.5, 3.5)));
+        assert_eq!(pixel_size(&gt), [10.0, 10.0]);
+    }
+
+    #[test]
+    fn test_rotated_round_trip() {
+        let gt = rotated();
+        for (col, row) in [(0.0, 0.0), (10.5, 3.25), (1024.0, 768.0)] {
+            let (x, y) = pixel_to_geo(&gt, col, row);
+            let (c, r) = geo_to_pixel(&gt, x, y).unwrap();
+            assert!((c - col).abs() < 1e-6 && (r - row).abs() < 1e-6);
+        }
+
+        // Ignoring the rotation terms lands on the wrong pixel
+        let (x, y) = pixel_to_geo(&gt, 100.0, 100.0);
+        assert!(((x - gt[0]) / gt[1] - 100.0).abs() > 10.0);
+        assert!(((y - gt[3]) / gt[5] - 100.0).abs() > 10.0);
+    }
+
+    #[test]
+    fn test_pixel_size_rotated() {
+        let [w, h] = pixel_size(&rotated());
+        assert!((w - 2.0).abs() < 1e-12 && (h - 2.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_degenerate() {
+        assert_eq!(
+            geo_to_pixel(&[0.0, 1.0, 1.0, 0.0, 1.0, 1.0], 1.0, 1.0),
+            None
+        );
+    }
+}
@@ -1,2 +1,18 @@
+pub mod band_math;
+pub mod bounds;
+pub mod colormap;
+pub mod complex;
+pub mod contrast;
 pub mod dataset_cache;
+pub mod dimensions;
+pub mod geotransform;
+pub mod nodata;
+pub mod overviews;
+pub mod region;
+pub mod statistics;
+pub mod stretch;
+pub mod subdatasets;
+pub mod terrain;
+pub mod tile_cache;
 pub mod tile_extractor;
+pub mod warp;
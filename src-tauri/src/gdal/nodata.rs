@@ -0,0 +1,151 @@
+use crate::gdal::complex::{read_values, ComplexPart};
+use gdal::raster::{RasterBand, ResampleAlg};
+use serde::{Deserialize, Serialize};
+
+/// Per-layer values to treat as nodata, on top of the dataset's own nodata
+/// value, mask and alpha band.
+///
+/// The default (no values, no range) hides nothing extra, so a band's real
+/// zeros stay visible.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct NodataRules {
+    /// Exact values to hide, e.g. a fill value the file does not declare
+    #[serde(default)]
+    pub values: Vec<f64>,
+    /// Inclusive `[min, max]` range to hide
+    #[serde(default)]
+    pub range: Option<[f64; 2]>,
+}
+
+impl NodataRules {
+    /// Whether a value read from a valid (unmasked) pixel is data
+    pub fn is_valid(&self, val: f64) -> bool {
+        val.is_finite()
+            && !self.values.iter().any(|v| (val - v).abs() < 1e-10)
+            && !self
+                .range
+                .is_some_and(|[min, max]| val >= min && val <= max)
+    }
+}
+
+/// Read which pixels of a window hold data, from the band's mask band.
+///
+/// The mask band covers the nodata value, per-dataset masks and alpha bands
+/// alike. Returns `None` when GDAL reports every pixel as valid, which skips
+/// the extra read for the common case.
+pub fn read_mask(
+    band: &RasterBand,
+    window: (isize, isize),
+    window_size: (usize, usize),
+    size: (usize, usize),
+) -> Result<Option<Vec<bool>>, String> {
+    let flags = band
+        .mask_flags()
+        .map_err(|e| format!("Failed to get mask flags: {}", e))?;
+    if flags.is_all_valid() {
+        return Ok(None);
+    }
+
+    let mask = band
+        .open_mask_band()
+        .map_err(|e| format!("Failed to open mask band: {}", e))?;
+    let buffer = mask
+        .read_as::<u8>(window, window_size, size, None)
+        .map_err(|e| format!("Failed to read mask: {}", e))?;
+
+    Ok(Some(buffer.data().iter().map(|&m| m > 0).collect()))
+}
+
+/// Read a window as f64, with masked pixels set to NaN. Complex bands are
+/// read as their amplitude
+pub fn read_masked(
+    band: &RasterBand,
+    window: (isize, isize),
+    window_size: (usize, usize),
+    size: (usize, usize),
+    resample_alg: Option<ResampleAlg>,
+) -> Result<Vec<f64>, String> {
+    read_masked_part(
+        band,
+        window,
+        window_size,
+        size,
+        resample_alg,
+        ComplexPart::default(),
+    )
+}
+
+/// Like `read_masked`, drawing complex bands as `part`
+pub fn read_masked_part(
+    band: &RasterBand,
+    window: (isize, isize),
+    window_size: (usize, usize),
+    size: (usize, usize),
+    resample_alg: Option<ResampleAlg>,
+    part: ComplexPart,
+) -> Result<Vec<f64>, String> {
+    let mut data = read_values(band, window, window_size, size, resample_alg, part)?;
+
+    if let Some(mask) = read_mask(band, window, window_size, size)? {
+        apply_mask(&mut data, &mask);
+    }
+
+    Ok(data)
+}
+
+/// Replace the values of masked-out pixels with NaN
+pub fn apply_mask(data: &mut [f64], mask: &[bool]) {
+    for (val, &valid) in data.iter_mut().zip(mask) {
+        if !valid {
+            *val = f64::NAN;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_rules_keep_zero() {
+        let rules = NodataRules::default();
+        assert!(rules.is_valid(0.0));
+        assert!(rules.is_valid(-9999.0));
+        assert!(!rules.is_valid(f64::NAN));
+        assert!(!rules.is_valid(f64::INFINITY));
+    }
+
+    #[test]
+    fn test_rules_values_and_range() {
+        let rules = NodataRules {
+            values: vec![0.0, 255.0],
+            range: Some([-10000.0, -1000.0]),
+        };
+        assert!(!rules.is_valid(0.0));
+        assert!(!rules.is_valid(255.0));
+        assert!(!rules.is_valid(-9999.0));
+        assert!(!rules.is_valid(-1000.0), "range is inclusive");
+        assert!(rules.is_valid(-999.0));
+        assert!(rules.is_valid(1.0));
+    }
+
+    #[test]
+    fn test_rules_serde_defaults() {
+        let rules: NodataRules = serde_json::from_str("{\"values\": [0]}").unwrap();
+        assert_eq!(rules.values, vec![0.0]);
+        assert_eq!(rules.range, None);
+        assert_eq!(
+            serde_json::from_str::<NodataRules>("{}").unwrap(),
+            NodataRules::default()
+        );
+    }
+
+    #[test]
+    fn test_apply_mask() {
+        let mut data = [1.0, 0.0, 3.0];
+        apply_mask(&mut data, &[true, false, true]);
+        assert_eq!(data[0], 1.0);
+        assert!(data[1].is_nan());
+        assert_eq!(data[2], 3.0);
+    }
+}
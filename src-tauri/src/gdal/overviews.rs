@@ -0,0 +1,342 @@
+use crate::gdal::warp::last_gdal_error;
+use gdal::cpl::CslStringList;
+use gdal::{Dataset, DatasetOptions, DriverManager, GdalOpenFlags};
+use gdal_sys::CPLErr;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::{c_void, CStr};
+use std::os::raw::{c_char, c_int};
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Longest side, in pixels, up to which a raster without overviews still
+/// draws quickly when zoomed out
+const MIN_OVERVIEW_SIZE: usize = 2048;
+
+/// The smallest overview fits in one tile of this many pixels
+const OVERVIEW_TILE_SIZE: usize = 256;
+
+/// Where overviews are written
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverviewLocation {
+    /// Inside the raster itself; GeoTIFF and a few other formats only
+    Internal,
+    /// A `.ovr` file next to the raster, which is left untouched
+    #[default]
+    External,
+}
+
+/// How overview pixels are computed from the full-resolution ones
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverviewResampling {
+    Nearest,
+    #[default]
+    Average,
+    Bilinear,
+    Cubic,
+    Lanczos,
+    /// Most common value, for classified rasters
+    Mode,
+    Gauss,
+}
+
+impl OverviewResampling {
+    fn to_gdal(self) -> &'static CStr {
+        match self {
+            OverviewResampling::Nearest => c"NEAREST",
+            OverviewResampling::Average => c"AVERAGE",
+            OverviewResampling::Bilinear => c"BILINEAR",
+            OverviewResampling::Cubic => c"CUBIC",
+            OverviewResampling::Lanczos => c"LANCZOS",
+            OverviewResampling::Mode => c"MODE",
+            OverviewResampling::Gauss => c"GAUSS",
+        }
+    }
+}
+
+/// Progress of an overview build, emitted as `overview-progress` events
+#[derive(Clone, Debug, Serialize)]
+pub struct OverviewProgress {
+    pub id: String,
+    pub progress: f64, // 0.0 to 1.0
+}
+
+/// Decimation factors (2, 4, 8, ...) down to the first level that fits in
+/// one tile; empty when the raster already does
+pub fn overview_factors(width: usize, height: usize) -> Vec<i32> {
+    let mut factors = Vec::new();
+    let mut factor = 1;
+    while width.max(height).div_ceil(factor) > OVERVIEW_TILE_SIZE {
+        factor *= 2;
+        factors.push(factor as i32);
+    }
+    factors
+}
+
+/// Whether a raster is big enough that, without overviews, zoomed-out tiles
+/// are slow to read at full resolution
+pub fn needs_overviews(width: usize, height: usize, overview_count: usize) -> bool {
+    overview_count == 0 && width.max(height) > MIN_OVERVIEW_SIZE
+}
+
+/// Whether overviews can be built for a path: plain local files only, not
+/// `/vsicurl/` URLs or subdataset connection strings
+pub fn is_local_file(path: &str) -> bool {
+    Path::new(path).is_file()
+}
+
+/// Overview builds in progress, by layer id, with the flag that cancels each
+#[derive(Default)]
+pub struct OverviewJobs {
+    jobs: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl OverviewJobs {
+    /// Register a build, returning its cancel flag; fails if the layer
+    /// already has one running
+    pub fn start(&self, id: &str) -> Result<Arc<AtomicBool>, String> {
+        let mut jobs = self.jobs.lock().unwrap();
+        if jobs.contains_key(id) {
+            return Err("Overviews are already being built for this layer".to_string());
+        }
+        let cancel = Arc::new(AtomicBool::new(false));
+        jobs.insert(id.to_string(), Arc::clone(&cancel));
+        Ok(cancel)
+    }
+
+    /// Ask a running build to stop; returns whether one was running
+    pub fn cancel(&self, id: &str) -> bool {
+        let jobs = self.jobs.lock().unwrap();
+        jobs.get(id)
+            .map(|cancel| cancel.store(true, Ordering::Relaxed))
+            .is_some()
+    }
+
+    pub fn finish(&self, id: &str) {
+        self.jobs.lock().unwrap().remove(id);
+    }
+}
+
+/// Forward GDAL progress to a Rust closure; returning 0 stops the build
+unsafe extern "C" fn progress_trampoline<F: FnMut(f64) -> bool>(
+    complete: f64,
+    _message: *const c_char,
+    arg: *mut c_void,
+) -> c_int {
+    let callback = &mut *(arg as *mut F);
+    callback(complete) as c_int
+}
+
+/// The trampoline for a closure's (unnameable) type
+fn trampoline_for<F: FnMut(f64) -> bool>(_: &F) -> gdal_sys::GDALProgressFunc {
+    Some(progress_trampoline::<F>)
+}
+
+/// Build overviews at `factors` for every band of the raster at `path`.
+///
+/// `progress` is called with the completed fraction and returns `false` to
+/// cancel. A cancelled or failed build leaves the raster as it was, so GDAL
+/// never picks up half-computed overviews:
+/// - internal overviews are only built into a raster that has none yet, and
+///   removed again if the build stops
+/// - external overviews are built into a staging `.ovr` that replaces the
+///   raster's own only once complete
+pub fn build_overviews(
+    path: &str,
+    location: OverviewLocation,
+    resampling: OverviewResampling,
+    factors: &[i32],
+    progress: impl FnMut(f64) -> bool,
+) -> Result<(), String> {
+    match location {
+        OverviewLocation::Internal => build_internal(path, resampling, factors, progress),
+        OverviewLocation::External => build_external(path, resampling, factors, progress),
+    }
+}
+
+fn build_internal(
+    path: &str,
+    resampling: OverviewResampling,
+    factors: &[i32],
+    progress: impl FnMut(f64) -> bool,
+) -> Result<(), String> {
+    let dataset = Dataset::open_ex(
+        path,
+        DatasetOptions {
+            open_flags: GdalOpenFlags::GDAL_OF_UPDATE | GdalOpenFlags::GDAL_OF_RASTER,
+            ..Default::default()
+        },
+    )
+    .map_err(|e| {
+        format!(
+            "Failed to open raster for writing: {}; try external overviews",
+            e
+        )
+    })?;
+
+    // Cleaning up after a failed build removes every overview, so existing
+    // ones (internal or in a .ovr) must not be at stake
+    let existing = dataset
+        .rasterband(1)
+        .and_then(|band| band.overview_count())
+        .unwrap_or(0);
+    if existing > 0 {
+        return Err("Raster already has overviews; build external ones instead".to_string());
+    }
+
+    let result = run_build(&dataset, resampling, factors, progress);
+    if result.is_err() {
+        // SAFETY: the dataset handle is valid until `dataset` is dropped; an
+        // empty factor list removes the overviews
+        unsafe {
+            gdal_sys::GDALBuildOverviews(
+                dataset.c_dataset(),
+                c"NONE".as_ptr(),
+                0,
+                ptr::null_mut(),
+                0,
+                ptr::null_mut(),
+                None,
+                ptr::null_mut(),
+            );
+        }
+    }
+    result
+}
+
+/// Staging VRT for an external build of `path`, and the `.ovr` GDAL writes
+/// for it
+fn staging_paths(path: &str) -> (String, String) {
+    let vrt = format!("{}.building.vrt", path);
+    let ovr = format!("{}.ovr", vrt);
+    (vrt, ovr)
+}
+
+fn build_external(
+    path: &str,
+    resampling: OverviewResampling,
+    factors: &[i32],
+    progress: impl FnMut(f64) -> bool,
+) -> Result<(), String> {
+    let source = Dataset::open(path).map_err(|e| format!("Failed to open raster: {}", e))?;
+
+    // GDAL writes external overviews to `<dataset>.ovr`. Building them for a
+    // VRT copy of the raster leaves its own .ovr untouched until the staged
+    // one is complete and renamed over it
+    let (staging_vrt, staging_ovr) = staging_paths(path);
+    let driver = DriverManager::get_driver_by_name("VRT")
+        .map_err(|e| format!("Failed to get VRT driver: {}", e))?;
+    source
+        .create_copy(&driver, &staging_vrt, &CslStringList::new())
+        .map_err(|e| format!("Failed to stage overviews: {}", e))?;
+    drop(source);
+
+    let result = Dataset::open(&staging_vrt)
+        .map_err(|e| format!("Failed to stage overviews: {}", e))
+        .and_then(|staging| run_build(&staging, resampling, factors, progress))
+        .and_then(|()| {
+            std::fs::rename(&staging_ovr, format!("{}.ovr", path))
+                .map_err(|e| format!("Failed to write overviews: {}", e))
+        });
+
+    std::fs::remove_file(&staging_ovr).ok();
+    std::fs::remove_file(&staging_vrt).ok();
+    result
+}
+
+/// Run `GDALBuildOverviews` on every band of an open dataset
+fn run_build(
+    dataset: &Dataset,
+    resampling: OverviewResampling,
+    factors: &[i32],
+    mut progress: impl FnMut(f64) -> bool,
+) -> Result<(), String> {
+    let mut cancelled = false;
+    let mut callback = |complete: f64| {
+        let keep_going = progress(complete);
+        cancelled |= !keep_going;
+        keep_going
+    };
+
+    let bands: Vec<c_int> = (1..=dataset.raster_count() as c_int).collect();
+
+    // SAFETY: the dataset handle is valid for the call; the factor and band
+    // lists outlive it, and the progress argument points to `callback`, whose
+    // type matches the trampoline
+    let err = unsafe {
+        gdal_sys::GDALBuildOverviews(
+            dataset.c_dataset(),
+            resampling.to_gdal().as_ptr(),
+            factors.len() as c_int,
+            factors.as_ptr() as *mut c_int,
+            bands.len() as c_int,
+            bands.as_ptr() as *mut c_int,
+            trampoline_for(&callback),
+            &mut callback as *mut _ as *mut c_void,
+        )
+    };
+    if cancelled {
+        Err("Overview build cancelled".to_string())
+    } else if err != CPLErr::CE_None {
+        Err(format!("Failed to build overviews: {}", last_gdal_error()))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overview_factors() {
+        assert_eq!(overview_factors(10000, 3000), vec![2, 4, 8, 16, 32, 64]);
+        assert_eq!(overview_factors(512, 300), vec![2]);
+        assert_eq!(overview_factors(513, 100), vec![2, 4]);
+        assert!(overview_factors(256, 256).is_empty());
+    }
+
+    #[test]
+    fn test_needs_overviews() {
+        assert!(needs_overviews(10000, 8000, 0));
+        assert!(!needs_overviews(10000, 8000, 3), "already has overviews");
+        assert!(
+            !needs_overviews(2048, 1024, 0),
+            "small enough to read whole"
+        );
+    }
+
+    #[test]
+    fn test_staging_paths() {
+        let (vrt, ovr) = staging_paths("/data/scene.tif");
+        assert_eq!(vrt, "/data/scene.tif.building.vrt");
+        assert_eq!(ovr, "/data/scene.tif.building.vrt.ovr");
+    }
+
+    #[test]
+    fn test_overview_jobs() {
+        let jobs = OverviewJobs::default();
+        let cancel = jobs.start("a").unwrap();
+        assert!(jobs.start("a").is_err(), "one build per layer");
+
+        assert!(!jobs.cancel("b"));
+        assert!(jobs.cancel("a"));
+        assert!(cancel.load(Ordering::Relaxed));
+
+        jobs.finish("a");
+        assert!(!jobs.cancel("a"));
+        assert!(jobs.start("a").is_ok());
+    }
+
+    #[test]
+    fn test_overview_options_serde() {
+        let location: OverviewLocation = serde_json::from_str("\"internal\"").unwrap();
+        assert_eq!(location, OverviewLocation::Internal);
+        let resampling: OverviewResampling = serde_json::from_str("\"mode\"").unwrap();
+        assert_eq!(resampling.to_gdal(), c"MODE");
+    }
+}
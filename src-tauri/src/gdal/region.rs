@@ -0,0 +1,163 @@
+use serde::{Deserialize, Serialize};
+
+/// Coordinate space a region's bbox and polygon are given in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegionCrs {
+    /// Longitude/latitude, reprojected to the raster's own CRS
+    #[serde(rename = "epsg:4326")]
+    Epsg4326,
+    /// Pixel (column, row) coordinates, used as given
+    #[serde(rename = "pixel")]
+    Pixel,
+}
+
+impl RegionCrs {
+    /// Space of a region for a raster, when the caller does not name one:
+    /// EPSG:4326 if the raster is georeferenced, pixels otherwise (matching
+    /// `RasterMetadata.bounds`)
+    pub fn resolve(crs: Option<RegionCrs>, georeferenced: bool) -> Result<RegionCrs, String> {
+        match crs {
+            Some(RegionCrs::Epsg4326) if !georeferenced => {
+                Err("Raster is not georeferenced; give the region in pixels".to_string())
+            }
+            Some(crs) => Ok(crs),
+            None if georeferenced => Ok(RegionCrs::Epsg4326),
+            None => Ok(RegionCrs::Pixel),
+        }
+    }
+}
+
+/// Polygon in a raster's pixel coordinates, as rings of `(x, y)` vertices.
+///
+/// Rings are combined with the even-odd rule, so rings inside the first are
+/// holes, as in GeoJSON polygons.
+pub struct PixelPolygon {
+    rings: Vec<Vec<(f64, f64)>>,
+}
+
+impl PixelPolygon {
+    pub fn new(rings: Vec<Vec<(f64, f64)>>) -> Self {
+        PixelPolygon { rings }
+    }
+
+    /// Whether a point lies inside the polygon (and outside its holes)
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+        for ring in &self.rings {
+            let Some(&last) = ring.last() else { continue };
+            let mut prev = last;
+            for &(vx, vy) in ring {
+                let (px, py) = prev;
+                if (vy > y) != (py > y) && x < (px - vx) * (y - vy) / (py - vy) + vx {
+                    inside = !inside;
+                }
+                prev = (vx, vy);
+            }
+        }
+        inside
+    }
+
+    /// Vertex coordinates of all rings, from which the read window is taken
+    pub fn vertices(&self) -> (Vec<f64>, Vec<f64>) {
+        self.rings.iter().flatten().copied().unzip()
+    }
+}
+
+/// Set values whose pixel centres fall outside `polygon` to NaN, so stats and
+/// histograms skip them like masked pixels.
+///
+/// `values` is a read of the window at `window` of `window_size` pixels,
+/// resampled to `sample_size`.
+pub fn mask_outside(
+    values: &mut [f64],
+    window: (isize, isize),
+    window_size: (usize, usize),
+    sample_size: (usize, usize),
+    polygon: &PixelPolygon,
+) {
+    let (width, height) = sample_size;
+    let step_x = window_size.0 as f64 / width as f64;
+    let step_y = window_size.1 as f64 / height as f64;
+
+    for (row, line) in values.chunks_mut(width).take(height).enumerate() {
+        let y = window.1 as f64 + (row as f64 + 0.5) * step_y;
+        for (col, value) in line.iter_mut().enumerate() {
+            let x = window.0 as f64 + (col as f64 + 0.5) * step_x;
+            if !polygon.contains(x, y) {
+                *value = f64::NAN;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: f64, max: f64) -> Vec<(f64, f64)> {
+        vec![(min, min), (max, min), (max, max), (min, max), (min, min)]
+    }
+
+    #[test]
+    fn test_region_crs() {
+        assert_eq!(
+            serde_json::from_str::<RegionCrs>("\"epsg:4326\"").unwrap(),
+            RegionCrs::Epsg4326
+        );
+        assert_eq!(
+            serde_json::from_str::<RegionCrs>("\"pixel\"").unwrap(),
+            RegionCrs::Pixel
+        );
+
+        assert_eq!(RegionCrs::resolve(None, true), Ok(RegionCrs::Epsg4326));
+        assert_eq!(RegionCrs::resolve(None, false), Ok(RegionCrs::Pixel));
+        assert_eq!(
+            RegionCrs::resolve(Some(RegionCrs::Pixel), true),
+            Ok(RegionCrs::Pixel)
+        );
+        assert!(RegionCrs::resolve(Some(RegionCrs::Epsg4326), false).is_err());
+    }
+
+    #[test]
+    fn test_polygon_contains() {
+        let triangle = PixelPolygon::new(vec![vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]]);
+        assert!(triangle.contains(2.0, 2.0));
+        assert!(!triangle.contains(8.0, 8.0));
+        assert!(!triangle.contains(-1.0, 2.0));
+    }
+
+    #[test]
+    fn test_polygon_hole() {
+        let ring = PixelPolygon::new(vec![square(0.0, 10.0), square(4.0, 6.0)]);
+        assert!(ring.contains(2.0, 2.0));
+        assert!(!ring.contains(5.0, 5.0), "inside the hole");
+        assert_eq!(ring.vertices().0.len(), 10);
+    }
+
+    #[test]
+    fn test_mask_outside() {
+        // Left half of a 4x2 window at (10, 20), read at full resolution
+        let polygon = PixelPolygon::new(vec![vec![
+            (0.0, 0.0),
+            (12.0, 0.0),
+            (12.0, 100.0),
+            (0.0, 100.0),
+        ]]);
+        let mut values = vec![1.0; 8];
+        mask_outside(&mut values, (10, 20), (4, 2), (4, 2), &polygon);
+
+        let kept: Vec<bool> = values.iter().map(|v| !v.is_nan()).collect();
+        assert_eq!(kept, [true, true, false, false, true, true, false, false]);
+    }
+
+    #[test]
+    fn test_mask_outside_decimated() {
+        // An 8x8 window read as 2x2: sample centres sit at pixels 2 and 6
+        let polygon = PixelPolygon::new(vec![square(0.0, 4.0)]);
+        let mut values = vec![1.0; 4];
+        mask_outside(&mut values, (0, 0), (8, 8), (2, 2), &polygon);
+
+        assert!(!values[0].is_nan());
+        assert!(values[1..].iter().all(|v| v.is_nan()));
+    }
+}
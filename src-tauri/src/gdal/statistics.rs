@@ -0,0 +1,183 @@
+use gdal::raster::RasterBand;
+use gdal::Metadata;
+use serde::{Deserialize, Serialize};
+
+/// How thoroughly `compute_statistics` reads a band
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsAccuracy {
+    /// From overviews or a subsample of blocks, like `gdalinfo -approx_stats`
+    #[default]
+    Approximate,
+    /// Every pixel, like `gdalinfo -stats`
+    Exact,
+}
+
+/// Band statistics as GDAL computes them and stores them in the band's
+/// `STATISTICS_*` metadata (persisted to a PAM `.aux.xml` file)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GdalStatistics {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// Share of pixels that are not nodata, 0-100; written by GDAL 3.2+
+    pub valid_percent: Option<f64>,
+    pub approximate: bool,
+}
+
+impl GdalStatistics {
+    /// Valid pixels in a band of `width` x `height`
+    pub fn valid_count(&self, width: usize, height: usize) -> Option<u64> {
+        self.valid_percent
+            .map(|percent| (percent / 100.0 * width as f64 * height as f64).round() as u64)
+    }
+
+    pub fn nodata_percent(&self) -> Option<f64> {
+        self.valid_percent.map(|percent| 100.0 - percent)
+    }
+}
+
+/// Parse `STATISTICS_*` metadata items; all four moments must be present
+fn parse_statistics(item: impl Fn(&str) -> Option<String>) -> Option<GdalStatistics> {
+    let number = |key: &str| item(key).and_then(|s| s.trim().parse::<f64>().ok());
+
+    Some(GdalStatistics {
+        min: number("STATISTICS_MINIMUM")?,
+        max: number("STATISTICS_MAXIMUM")?,
+        mean: number("STATISTICS_MEAN")?,
+        std_dev: number("STATISTICS_STDDEV")?,
+        valid_percent: number("STATISTICS_VALID_PERCENT"),
+        approximate: item("STATISTICS_APPROXIMATE").is_some_and(|s| s.eq_ignore_ascii_case("YES")),
+    })
+}
+
+/// Parse `STATISTICS_*` metadata items that may hold only the range, as some
+/// COGs do; a missing mean or standard deviation is estimated from it and the
+/// result marked approximate
+fn parse_range_statistics(item: impl Fn(&str) -> Option<String>) -> Option<GdalStatistics> {
+    if let Some(stats) = parse_statistics(&item) {
+        return Some(stats);
+    }
+
+    let number = |key: &str| item(key).and_then(|s| s.trim().parse::<f64>().ok());
+    let min = number("STATISTICS_MINIMUM")?;
+    let max = number("STATISTICS_MAXIMUM")?;
+    Some(GdalStatistics {
+        min,
+        max,
+        mean: number("STATISTICS_MEAN").unwrap_or((min + max) / 2.0),
+        std_dev: number("STATISTICS_STDDEV").unwrap_or((max - min) / 4.0),
+        valid_percent: number("STATISTICS_VALID_PERCENT"),
+        approximate: true,
+    })
+}
+
+/// Statistics stored with the band (in the file or its `.aux.xml`), without
+/// reading any pixels
+pub fn stored_statistics(band: &RasterBand) -> Option<GdalStatistics> {
+    parse_statistics(|key| band.metadata_item(key, ""))
+}
+
+/// Like [`stored_statistics`], but also accepting a stored minimum and
+/// maximum alone, with the other moments estimated
+pub fn stored_range_statistics(band: &RasterBand) -> Option<GdalStatistics> {
+    parse_range_statistics(|key| band.metadata_item(key, ""))
+}
+
+/// Compute band statistics with GDAL, skipping nodata pixels.
+///
+/// Stored statistics are reused when accurate enough; otherwise GDAL computes
+/// and stores them, and they are written to the dataset's `.aux.xml` when it
+/// is closed, if its directory is writable.
+pub fn compute_statistics(
+    band: &RasterBand,
+    accuracy: StatsAccuracy,
+) -> Result<GdalStatistics, String> {
+    let approx_ok = accuracy == StatsAccuracy::Approximate;
+    let stats = band
+        .get_statistics(true, approx_ok)
+        .map_err(|e| format!("Failed to compute statistics: {}", e))?
+        .ok_or("Failed to compute statistics: band has no valid pixels")?;
+
+    // The moments come back directly; the valid share and accuracy only
+    // through the metadata GDAL stored alongside them
+    let stored = stored_statistics(band);
+    Ok(GdalStatistics {
+        min: stats.min,
+        max: stats.max,
+        mean: stats.mean,
+        std_dev: stats.std_dev,
+        valid_percent: stored.and_then(|s| s.valid_percent),
+        approximate: stored.map_or(approx_ok, |s| s.approximate),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn items(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_statistics() {
+        let metadata = items(&[
+            ("STATISTICS_MINIMUM", "1"),
+            ("STATISTICS_MAXIMUM", "250"),
+            ("STATISTICS_MEAN", "97.5"),
+            ("STATISTICS_STDDEV", "12.25"),
+            ("STATISTICS_VALID_PERCENT", "75"),
+            ("STATISTICS_APPROXIMATE", "YES"),
+        ]);
+        let stats = parse_statistics(|key| metadata.get(key).cloned()).unwrap();
+
+        assert_eq!((stats.min, stats.max), (1.0, 250.0));
+        assert_eq!((stats.mean, stats.std_dev), (97.5, 12.25));
+        assert!(stats.approximate);
+        assert_eq!(stats.valid_count(100, 40), Some(3000));
+        assert_eq!(stats.nodata_percent(), Some(25.0));
+    }
+
+    #[test]
+    fn test_parse_statistics_incomplete() {
+        let metadata = items(&[("STATISTICS_MINIMUM", "0"), ("STATISTICS_MAXIMUM", "1")]);
+        assert!(
+            parse_statistics(|key| metadata.get(key).cloned()).is_none(),
+            "mean and standard deviation are not made up"
+        );
+
+        // ...unless a range is all there is to go on
+        let stats = parse_range_statistics(|key| metadata.get(key).cloned()).unwrap();
+        assert_eq!((stats.min, stats.max), (0.0, 1.0));
+        assert_eq!((stats.mean, stats.std_dev), (0.5, 0.25));
+        assert!(stats.approximate);
+
+        let metadata = items(&[
+            ("STATISTICS_MINIMUM", "0"),
+            ("STATISTICS_MAXIMUM", "100"),
+            ("STATISTICS_MEAN", "20"),
+        ]);
+        let stats = parse_range_statistics(|key| metadata.get(key).cloned()).unwrap();
+        assert_eq!((stats.mean, stats.std_dev), (20.0, 25.0));
+
+        let metadata = items(&[("STATISTICS_MINIMUM", "0")]);
+        assert!(parse_range_statistics(|key| metadata.get(key).cloned()).is_none());
+
+        // Statistics written before GDAL 3.2 have no valid share
+        let metadata = items(&[
+            ("STATISTICS_MINIMUM", "0"),
+            ("STATISTICS_MAXIMUM", "1"),
+            ("STATISTICS_MEAN", "0.5"),
+            ("STATISTICS_STDDEV", "0.1"),
+        ]);
+        let stats = parse_statistics(|key| metadata.get(key).cloned()).unwrap();
+        assert!(!stats.approximate);
+        assert_eq!(stats.valid_count(10, 10), None);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::gdal::complex::ComplexPart;
+use crate::gdal::nodata::{read_masked_part, NodataRules};
+use crate::gdal::tile_extractor::StretchParams;
+use gdal::raster::{RasterBand, ResampleAlg};
+use serde::{Deserialize, Serialize};
+
+/// How to pick a stretch range from a band's sampled values.
+///
+/// Percentile cuts ignore the few outliers (hot pixels, fill values the file
+/// does not declare) that would otherwise wash out a min/max stretch.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AutoStretch {
+    /// Full range of the valid values
+    MinMax,
+    /// Cut at the `low` and `high` percentiles (0-100)
+    Percentile { low: f64, high: f64 },
+    /// `mean ± n * std_dev`, clamped to the data range
+    StdDev { n: f64 },
+}
+
+impl Default for AutoStretch {
+    fn default() -> Self {
+        AutoStretch::Percentile {
+            low: 2.0,
+            high: 98.0,
+        }
+    }
+}
+
+impl AutoStretch {
+    /// Compute a stretch from sampled values, skipping invalid ones.
+    ///
+    /// Returns `None` when the sample holds no valid values.
+    pub fn compute(&self, values: &[f64], rules: &NodataRules) -> Option<StretchParams> {
+        let mut valid: Vec<f64> = values
+            .iter()
+            .copied()
+            .filter(|&v| rules.is_valid(v))
+            .collect();
+        if valid.is_empty() {
+            return None;
+        }
+        valid.sort_by(|a, b| a.total_cmp(b));
+
+        let data_min = valid[0];
+        let data_max = valid[valid.len() - 1];
+
+        let (min, max) = match *self {
+            AutoStretch::MinMax => (data_min, data_max),
+            AutoStretch::Percentile { low, high } => {
+                (percentile(&valid, low), percentile(&valid, high))
+            }
+            AutoStretch::StdDev { n } => {
+                let count = valid.len() as f64;
+                let mean = valid.iter().sum::<f64>() / count;
+                let variance = valid.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+                let spread = n * variance.sqrt();
+                ((mean - spread).max(data_min), (mean + spread).min(data_max))
+            }
+        };
+
+        Some(StretchParams {
+            min,
+            max,
+            ..Default::default()
+        })
+    }
+}
+
+/// Linearly interpolated percentile (0-100) of sorted values
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let pos = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    let frac = pos - lower as f64;
+
+    sorted[lower] + (sorted[upper] - sorted[lower]) * frac
+}
+
+/// Size `read_sample` reads a window at: the longer side at most `max_size`
+/// pixels, keeping the aspect ratio
+pub fn sample_size(window_size: (usize, usize), max_size: usize) -> (usize, usize) {
+    let (width, height) = window_size;
+    let scale = (max_size as f64 / width.max(height) as f64).min(1.0);
+    (
+        ((width as f64 * scale) as usize).max(1),
+        ((height as f64 * scale) as usize).max(1),
+    )
+}
+
+/// Read a decimated sample of a band window for statistics, with masked
+/// pixels set to NaN. Complex bands are sampled as `part`.
+///
+/// The longer side of the sample is at most `max_size` pixels; GDAL serves
+/// decimated reads from overviews when the file has them.
+pub fn read_sample(
+    band: &RasterBand,
+    window: (isize, isize),
+    window_size: (usize, usize),
+    max_size: usize,
+    part: ComplexPart,
+) -> Result<Vec<f64>, String> {
+    read_masked_part(
+        band,
+        window,
+        window_size,
+        sample_size(window_size, max_size),
+        Some(ResampleAlg::NearestNeighbour),
+        part,
+    )
+    .map_err(|e| format!("Failed to read band sample: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0..=100 with one extreme outlier
+    fn sample_with_outlier() -> Vec<f64> {
+        let mut values: Vec<f64> = (0..=100).map(|v| v as f64).collect();
+        values.push(100000.0);
+        values
+    }
+
+    #[test]
+    fn test_sample_size() {
+        assert_eq!(sample_size((4096, 1024), 1024), (1024, 256));
+        assert_eq!(sample_size((300, 200), 1024), (300, 200), "never upsampled");
+        assert_eq!(sample_size((100000, 10), 1024), (1024, 1));
+    }
+
+    #[test]
+    fn test_min_max_includes_outlier() {
+        let stretch = AutoStretch::MinMax
+            .compute(&sample_with_outlier(), &NodataRules::default())
+            .unwrap();
+        assert_eq!(stretch.min, 0.0);
+        assert_eq!(stretch.max, 100000.0);
+    }
+
+    #[test]
+    fn test_percentile_ignores_outlier() {
+        let stretch = AutoStretch::default()
+            .compute(&sample_with_outlier(), &NodataRules::default())
+            .unwrap();
+        assert!((stretch.min - 2.02).abs() < 1e-9);
+        assert!((stretch.max - 98.98).abs() < 1e-9);
+        assert_eq!(stretch.gamma, 1.0);
+    }
+
+    #[test]
+    fn test_std_dev_clamped_to_data() {
+        let values = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0];
+        let stretch = AutoStretch::StdDev { n: 1.0 }
+            .compute(&values, &NodataRules::default())
+            .unwrap();
+        assert_eq!((stretch.min, stretch.max), (3.0, 7.0));
+
+        let stretch = AutoStretch::StdDev { n: 3.0 }
+            .compute(&values, &NodataRules::default())
+            .unwrap();
+        assert_eq!((stretch.min, stretch.max), (2.0, 9.0));
+    }
+
+    #[test]
+    fn test_skips_invalid_values() {
+        let rules = NodataRules {
+            values: vec![-9999.0],
+            range: None,
+        };
+        let values = [-9999.0, f64::NAN, 10.0, 20.0];
+        let stretch = AutoStretch::MinMax.compute(&values, &rules).unwrap();
+        assert_eq!((stretch.min, stretch.max), (10.0, 20.0));
+        assert!(AutoStretch::MinMax.compute(&[f64::NAN], &rules).is_none());
+    }
+
+    #[test]
+    fn test_serde_tagged() {
+        let method: AutoStretch =
+            serde_json::from_str("{\"type\": \"percentile\", \"low\": 1, \"high\": 99}").unwrap();
+        assert_eq!(
+            method,
+            AutoStretch::Percentile {
+                low: 1.0,
+                high: 99.0
+            }
+        );
+        let method: AutoStretch =
+            serde_json::from_str("{\"type\": \"std_dev\", \"n\": 2}").unwrap();
+        assert_eq!(method, AutoStretch::StdDev { n: 2.0 });
+    }
+}
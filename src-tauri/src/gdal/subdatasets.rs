@@ -0,0 +1,189 @@
+use gdal::{Dataset, Metadata};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// One entry of a container's `SUBDATASETS` metadata domain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SubdatasetInfo {
+    /// GDAL connection string, e.g. `NETCDF:"/data/ocean.nc":sst`; pass it to
+    /// `open_raster` as the path
+    pub name: String,
+    /// Driver description, e.g. `[12x720x1440] sst (32-bit floating-point)`
+    pub description: String,
+    /// Short name for layer lists: the variable or array path, or the
+    /// description when the name has none
+    pub label: String,
+    /// Array dimensions from the description, slowest varying first
+    pub dimensions: Vec<usize>,
+    /// Data type from the description, e.g. `32-bit floating-point`
+    pub data_type: Option<String>,
+}
+
+/// Subdatasets of a dataset, in the order the driver lists them
+pub fn read_subdatasets(dataset: &Dataset) -> Vec<SubdatasetInfo> {
+    dataset
+        .metadata_domain("SUBDATASETS")
+        .map(|items| parse_subdatasets(&items))
+        .unwrap_or_default()
+}
+
+/// Parse `SUBDATASET_<n>_NAME=...` / `SUBDATASET_<n>_DESC=...` items
+pub fn parse_subdatasets(items: &[String]) -> Vec<SubdatasetInfo> {
+    let mut entries: BTreeMap<usize, (Option<String>, Option<String>)> = BTreeMap::new();
+    for item in items {
+        let Some((key, value)) = item.split_once('=') else {
+            continue;
+        };
+        let Some(rest) = key.strip_prefix("SUBDATASET_") else {
+            continue;
+        };
+        let Some((index, field)) = rest.split_once('_') else {
+            continue;
+        };
+        let Ok(index) = index.parse::<usize>() else {
+            continue;
+        };
+
+        let entry = entries.entry(index).or_default();
+        match field {
+            "NAME" => entry.0 = Some(value.to_string()),
+            "DESC" => entry.1 = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    entries
+        .into_values()
+        .filter_map(|(name, description)| {
+            let name = name?;
+            let description = description.unwrap_or_else(|| name.clone());
+            let (dimensions, data_type) = parse_description(&description);
+            Some(SubdatasetInfo {
+                label: subdataset_label(&name).unwrap_or_else(|| description.clone()),
+                name,
+                description,
+                dimensions,
+                data_type,
+            })
+        })
+        .collect()
+}
+
+/// Dimensions and data type of a subdataset description.
+///
+/// netCDF and HDF write `[12x720x1440] sst (32-bit floating-point)`; GeoTIFF
+/// and NITF pages write `Page 2 (1024P x 768L x 3B)`, read here as bands,
+/// lines, pixels to match the slowest-first order of the array drivers.
+fn parse_description(description: &str) -> (Vec<usize>, Option<String>) {
+    let trailing_group = description
+        .trim_end()
+        .strip_suffix(')')
+        .and_then(|rest| rest.rfind('(').map(|start| &rest[start + 1..]));
+
+    if let Some(dims) = description
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(dims, _)| dims)
+    {
+        let dimensions = dims
+            .split('x')
+            .map(|d| d.trim().parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_default();
+        return (dimensions, trailing_group.map(str::to_string));
+    }
+
+    // Image pages: "<pixels>P x <lines>L x <bands>B"
+    if let Some(group) = trailing_group {
+        let mut size = [None; 3];
+        for part in group.split('x') {
+            let part = part.trim();
+            let slot = match part.chars().last() {
+                Some('P') => 2,
+                Some('L') => 1,
+                Some('B') => 0,
+                _ => continue,
+            };
+            size[slot] = part[..part.len() - 1].trim().parse::<usize>().ok();
+        }
+        if let [Some(bands), Some(lines), Some(pixels)] = size {
+            return (vec![bands, lines, pixels], None);
+        }
+    }
+
+    (Vec::new(), None)
+}
+
+/// Variable or array path at the end of a `DRIVER:"file":variable`
+/// connection string
+fn subdataset_label(name: &str) -> Option<String> {
+    let (_, after_file) = name.rsplit_once('"')?;
+    let label = after_file.strip_prefix(':')?;
+    (!label.is_empty()).then(|| label.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn items(pairs: &[(&str, &str)]) -> Vec<String> {
+        pairs.iter().map(|(k, v)| format!("{}={}", k, v)).collect()
+    }
+
+    #[test]
+    fn test_parse_netcdf_subdatasets() {
+        let subdatasets = parse_subdatasets(&items(&[
+            ("SUBDATASET_1_NAME", "NETCDF:\"/data/ocean.nc\":sst"),
+            (
+                "SUBDATASET_1_DESC",
+                "[12x720x1440] sea_surface_temperature (32-bit floating-point)",
+            ),
+            ("SUBDATASET_2_NAME", "NETCDF:\"/data/ocean.nc\":mask"),
+            (
+                "SUBDATASET_2_DESC",
+                "[720x1440] mask (8-bit unsigned integer)",
+            ),
+        ]));
+
+        assert_eq!(subdatasets.len(), 2);
+        assert_eq!(subdatasets[0].name, "NETCDF:\"/data/ocean.nc\":sst");
+        assert_eq!(subdatasets[0].label, "sst");
+        assert_eq!(subdatasets[0].dimensions, vec![12, 720, 1440]);
+        assert_eq!(
+            subdatasets[0].data_type.as_deref(),
+            Some("32-bit floating-point")
+        );
+        assert_eq!(subdatasets[1].dimensions, vec![720, 1440]);
+    }
+
+    #[test]
+    fn test_parse_subdatasets_in_index_order() {
+        // Indices compare numerically, not as strings
+        let subdatasets = parse_subdatasets(&items(&[
+            ("SUBDATASET_10_NAME", "HDF5:\"/data/l2.h5\"://Grid/b10"),
+            ("SUBDATASET_2_NAME", "HDF5:\"/data/l2.h5\"://Grid/b2"),
+            (
+                "SUBDATASET_2_DESC",
+                "[1800x3600] //Grid/b2 (16-bit integer)",
+            ),
+        ]));
+
+        let labels: Vec<&str> = subdatasets.iter().map(|s| s.label.as_str()).collect();
+        assert_eq!(labels, vec!["//Grid/b2", "//Grid/b10"]);
+        // Missing descriptions fall back to the name
+        assert_eq!(subdatasets[1].description, subdatasets[1].name);
+        assert!(subdatasets[1].dimensions.is_empty());
+    }
+
+    #[test]
+    fn test_parse_image_pages() {
+        let subdatasets = parse_subdatasets(&items(&[
+            ("SUBDATASET_1_NAME", "GTIFF_DIR:2:/data/frames.tif"),
+            ("SUBDATASET_1_DESC", "Page 2 (1024P x 768L x 3B)"),
+        ]));
+
+        assert_eq!(subdatasets[0].label, "Page 2 (1024P x 768L x 3B)");
+        assert_eq!(subdatasets[0].dimensions, vec![3, 768, 1024]);
+        assert_eq!(subdatasets[0].data_type, None);
+    }
+}
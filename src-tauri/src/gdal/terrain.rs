@@ -0,0 +1,322 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// Hillshade illumination model
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HillshadeMode {
+    /// Single light source at `azimuth`/`altitude`
+    #[default]
+    Standard,
+    /// Four light sources (225°, 270°, 315°, 360°) weighted by aspect
+    Multidirectional,
+    /// Oblique shading combined with slope shading
+    Combined,
+}
+
+/// Hillshade parameters, following `gdaldem hillshade`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HillshadeParams {
+    /// Sun azimuth in degrees clockwise from north
+    #[serde(default = "default_azimuth")]
+    pub azimuth: f64,
+    /// Sun altitude in degrees above the horizon
+    #[serde(default = "default_altitude")]
+    pub altitude: f64,
+    /// Vertical exaggeration
+    #[serde(default = "default_z_factor")]
+    pub z_factor: f64,
+    #[serde(default)]
+    pub mode: HillshadeMode,
+}
+
+fn default_azimuth() -> f64 {
+    315.0
+}
+
+fn default_altitude() -> f64 {
+    45.0
+}
+
+fn default_z_factor() -> f64 {
+    1.0
+}
+
+impl Default for HillshadeParams {
+    fn default() -> Self {
+        Self {
+            azimuth: default_azimuth(),
+            altitude: default_altitude(),
+            z_factor: default_z_factor(),
+            mode: HillshadeMode::Standard,
+        }
+    }
+}
+
+/// Value derived from a DEM and rendered in place of the elevation
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TerrainDerivative {
+    /// Slope angle, 0-90°
+    SlopeDegrees,
+    /// Slope as rise over run, in percent (45° = 100%)
+    SlopePercent,
+    /// Direction the slope faces, 0-360° clockwise from north
+    Aspect,
+}
+
+impl TerrainDerivative {
+    /// Derive the value from a gradient; `None` for the aspect of flat cells,
+    /// which face no direction
+    pub fn compute(self, gradient: Gradient) -> Option<f64> {
+        match self {
+            TerrainDerivative::SlopeDegrees => Some(gradient.slope_degrees()),
+            TerrainDerivative::SlopePercent => Some(gradient.slope_percent()),
+            TerrainDerivative::Aspect => gradient.aspect(),
+        }
+    }
+}
+
+/// Surface gradient from Horn's method.
+///
+/// `x` is the elevation drop from west to east and `y` the elevation rise from
+/// north to south, both per unit of horizontal distance (the `gdaldem` sign
+/// convention).
+#[derive(Clone, Copy, Debug)]
+pub struct Gradient {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Gradient {
+    /// Compute the gradient of a 3x3 window (row-major, north row first)
+    pub fn horn(window: &[f64; 9], cell_x: f64, cell_y: f64) -> Self {
+        let [a, b, c, d, _, f, g, h, i] = *window;
+        Self {
+            x: ((a + 2.0 * d + g) - (c + 2.0 * f + i)) / (8.0 * cell_x),
+            y: ((g + 2.0 * h + i) - (a + 2.0 * b + c)) / (8.0 * cell_y),
+        }
+    }
+
+    fn magnitude(&self) -> f64 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    /// Slope angle in degrees
+    pub fn slope_degrees(&self) -> f64 {
+        self.magnitude().atan().to_degrees()
+    }
+
+    /// Slope in percent
+    pub fn slope_percent(&self) -> f64 {
+        self.magnitude() * 100.0
+    }
+
+    /// Downslope direction in degrees clockwise from north, `None` when flat
+    pub fn aspect(&self) -> Option<f64> {
+        if self.x == 0.0 && self.y == 0.0 {
+            return None;
+        }
+        let angle = self.y.atan2(self.x).to_degrees();
+        let aspect = if angle > 90.0 {
+            450.0 - angle
+        } else {
+            90.0 - angle
+        };
+        Some(aspect % 360.0)
+    }
+}
+
+/// Approximate ground size in metres of a geographic (lon/lat degrees) cell.
+///
+/// Slopes need horizontal distances in the same unit as elevation, so degree
+/// cell sizes are converted at the cell's latitude.
+pub fn geographic_cell_size(cell_x: f64, cell_y: f64, lat: f64) -> (f64, f64) {
+    const METRES_PER_DEGREE: f64 = 111_320.0;
+    (
+        cell_x * METRES_PER_DEGREE * lat.to_radians().cos(),
+        cell_y * METRES_PER_DEGREE,
+    )
+}
+
+/// Shade a single cell, returning a brightness between 0 and 1
+pub fn hillshade(gradient: Gradient, params: &HillshadeParams) -> f64 {
+    let z = params.z_factor;
+    let alt = params.altitude.to_radians();
+
+    // Brightness for a light source at the given azimuth (radians)
+    let oblique = |azimuth: f64| {
+        let slope = FRAC_PI_2 - (gradient.magnitude() * z).atan();
+        let aspect = gradient.y.atan2(gradient.x);
+        alt.sin() * slope.sin() + alt.cos() * slope.cos() * (azimuth - FRAC_PI_2 - aspect).cos()
+    };
+
+    let shade = match params.mode {
+        HillshadeMode::Standard => oblique(params.azimuth.to_radians()),
+        HillshadeMode::Multidirectional => {
+            let (x, y) = (gradient.x, gradient.y);
+            let xx_plus_yy = x * x + y * y;
+            if xx_plus_yy == 0.0 {
+                // Flat: every light source gives the same result
+                oblique(PI)
+            } else {
+                let w225 = 0.5 * xx_plus_yy - x * y;
+                let w270 = x * x;
+                let w315 = xx_plus_yy - w225;
+                let w360 = y * y;
+                (w225 * oblique(225f64.to_radians())
+                    + w270 * oblique(270f64.to_radians())
+                    + w315 * oblique(315f64.to_radians())
+                    + w360 * oblique(360f64.to_radians()))
+                    / (2.0 * xx_plus_yy)
+            }
+        }
+        HillshadeMode::Combined => {
+            let angle = oblique(params.azimuth.to_radians()).clamp(-1.0, 1.0).acos();
+            let slope = (gradient.magnitude() * z).atan();
+            1.0 - angle * slope / (FRAC_PI_2 * FRAC_PI_2)
+        }
+    };
+
+    shade.clamp(0.0, 1.0)
+}
+
+/// Run a 3x3 kernel over the interior of a grid that carries a 1-pixel buffer.
+///
+/// `grid` is `(size + 2) x (size + 2)` so every output pixel has its full
+/// neighbourhood, which keeps tile edges seamless. The kernel gets the output
+/// row and the window. Cells whose centre is invalid yield `None`; invalid
+/// neighbours are replaced by the centre value so data edges still shade.
+pub fn map_neighbourhood<T>(
+    grid: &[f64],
+    size: usize,
+    is_valid: impl Fn(f64) -> bool,
+    mut kernel: impl FnMut(usize, &[f64; 9]) -> T,
+) -> Vec<Option<T>> {
+    let stride = size + 2;
+    let mut out = Vec::with_capacity(size * size);
+
+    for row in 0..size {
+        for col in 0..size {
+            let centre = grid[(row + 1) * stride + col + 1];
+            if !is_valid(centre) {
+                out.push(None);
+                continue;
+            }
+
+            let mut window = [0.0; 9];
+            for (k, cell) in window.iter_mut().enumerate() {
+                let value = grid[(row + k / 3) * stride + col + k % 3];
+                *cell = if is_valid(value) { value } else { centre };
+            }
+            out.push(Some(kernel(row, &window)));
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plane(dx: f64, dy: f64) -> [f64; 9] {
+        // Elevation rises by `dx` per cell eastwards and `dy` per cell southwards
+        std::array::from_fn(|k| (k % 3) as f64 * dx + (k / 3) as f64 * dy)
+    }
+
+    #[test]
+    fn test_horn_gradient_of_plane() {
+        let g = Gradient::horn(&plane(2.0, 0.0), 1.0, 1.0);
+        assert!((g.x + 2.0).abs() < 1e-12, "rising east gives negative x");
+        assert!(g.y.abs() < 1e-12);
+
+        let g = Gradient::horn(&plane(0.0, 3.0), 1.0, 1.0);
+        assert!((g.y - 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_flat_hillshade() {
+        let g = Gradient::horn(&[5.0; 9], 10.0, 10.0);
+        let params = HillshadeParams::default();
+        let expected = 45f64.to_radians().sin();
+        assert!((hillshade(g, &params) - expected).abs() < 1e-9);
+
+        let multi = HillshadeParams {
+            mode: HillshadeMode::Multidirectional,
+            ..HillshadeParams::default()
+        };
+        assert!((hillshade(g, &multi) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_slope_facing_sun_is_brighter() {
+        // Default sun is in the north-west, so terrain rising towards the
+        // south-east faces it
+        let params = HillshadeParams::default();
+        let facing = hillshade(Gradient::horn(&plane(1.0, 1.0), 1.0, 1.0), &params);
+        let away = hillshade(Gradient::horn(&plane(-1.0, -1.0), 1.0, 1.0), &params);
+        assert!(facing > away);
+    }
+
+    #[test]
+    fn test_slope() {
+        // Rises one unit per unit eastwards: 45°, 100%
+        let g = Gradient::horn(&plane(1.0, 0.0), 1.0, 1.0);
+        assert!((g.slope_degrees() - 45.0).abs() < 1e-9);
+        assert!((g.slope_percent() - 100.0).abs() < 1e-9);
+        assert_eq!(Gradient::horn(&[3.0; 9], 1.0, 1.0).slope_degrees(), 0.0);
+    }
+
+    #[test]
+    fn test_aspect_faces_downhill() {
+        let aspect = |dx, dy| Gradient::horn(&plane(dx, dy), 1.0, 1.0).aspect().unwrap();
+        // Rising southwards faces north, rising eastwards faces west, etc.
+        assert!(aspect(0.0, 1.0).abs() < 1e-9);
+        assert!((aspect(-1.0, 0.0) - 90.0).abs() < 1e-9);
+        assert!((aspect(0.0, -1.0) - 180.0).abs() < 1e-9);
+        assert!((aspect(1.0, 0.0) - 270.0).abs() < 1e-9);
+        assert!((aspect(1.0, 1.0) - 315.0).abs() < 1e-9);
+        assert_eq!(Gradient::horn(&[3.0; 9], 1.0, 1.0).aspect(), None);
+    }
+
+    #[test]
+    fn test_geographic_cell_size() {
+        let (x, y) = geographic_cell_size(1.0, 1.0, 0.0);
+        assert!((x - 111_320.0).abs() < 1e-6);
+        assert!((y - 111_320.0).abs() < 1e-6);
+
+        let (x, _) = geographic_cell_size(1.0, 1.0, 60.0);
+        assert!((x - 55_660.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_derivative_serde_names() {
+        let parsed: TerrainDerivative = serde_json::from_str("\"slope_percent\"").unwrap();
+        assert_eq!(parsed, TerrainDerivative::SlopePercent);
+    }
+
+    #[test]
+    fn test_map_neighbourhood_uses_buffer() {
+        // 1x1 output with a buffer ring rising eastwards
+        let grid = [0.0, 1.0, 2.0, 0.0, 1.0, 2.0, 0.0, 1.0, 2.0];
+        let out = map_neighbourhood(
+            &grid,
+            1,
+            |v| v.is_finite(),
+            |_, w| Gradient::horn(w, 1.0, 1.0).x,
+        );
+        assert_eq!(out.len(), 1);
+        assert!((out[0].unwrap() + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_map_neighbourhood_invalid_cells() {
+        let mut grid = [1.0; 16];
+        grid[5] = f64::NAN; // centre of output (0, 0)
+        let out = map_neighbourhood(&grid, 2, |v| v.is_finite(), |_, w| w.iter().sum());
+        assert_eq!(out[0], None);
+        // Neighbour of the NaN falls back to its own value
+        assert_eq!(out[1], Some(9.0));
+    }
+}
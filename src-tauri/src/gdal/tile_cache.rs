@@ -0,0 +1,219 @@
+use lru::LruCache;
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+/// Default memory budget for rendered tiles (256 MB)
+pub const DEFAULT_TILE_CACHE_BYTES: usize = 256 * 1024 * 1024;
+
+/// Identifies a rendered tile: the layers it was drawn from, its position and
+/// a hash of everything that affects the output (stretch, bands, ...).
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct TileCacheKey {
+    layer_ids: Vec<String>,
+    z: u8,
+    x: i32,
+    y: i32,
+    params_hash: u64,
+}
+
+impl TileCacheKey {
+    /// Build a key from the source layer ids, tile coordinates and render parameters.
+    ///
+    /// The parameters are hashed through their serialized form, so any type that
+    /// derives `Serialize` (including tuples of them) can be part of the key.
+    pub fn new<P: Serialize>(layer_ids: &[&str], z: u8, x: i32, y: i32, params: &P) -> Self {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_vec(params)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+
+        Self {
+            layer_ids: layer_ids.iter().map(|id| id.to_string()).collect(),
+            z,
+            x,
+            y,
+            params_hash: hasher.finish(),
+        }
+    }
+
+    fn references(&self, layer_id: &str) -> bool {
+        self.layer_ids.iter().any(|id| id == layer_id)
+    }
+}
+
+/// Cache usage counters reported to the frontend
+#[derive(Clone, Serialize)]
+pub struct TileCacheStats {
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget_bytes: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+struct TileCacheInner {
+    tiles: LruCache<TileCacheKey, Vec<u8>>,
+    bytes: usize,
+    hits: u64,
+    misses: u64,
+}
+
+/// Byte-budgeted LRU cache of encoded tiles.
+///
+/// Panning back over tiles that were already rendered with the same parameters
+/// skips the warp and the encode entirely. Entries for a layer are dropped when
+/// the layer is closed.
+pub struct TileCache {
+    inner: Mutex<TileCacheInner>,
+    budget_bytes: usize,
+}
+
+impl TileCache {
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            inner: Mutex::new(TileCacheInner {
+                tiles: LruCache::unbounded(),
+                bytes: 0,
+                hits: 0,
+                misses: 0,
+            }),
+            budget_bytes,
+        }
+    }
+
+    /// Look up a tile, counting the access as a hit or a miss
+    pub fn get(&self, key: &TileCacheKey) -> Option<Vec<u8>> {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.tiles.get(key).cloned() {
+            Some(tile) => {
+                inner.hits += 1;
+                Some(tile)
+            }
+            None => {
+                inner.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Store a tile, evicting least recently used tiles to stay within budget
+    pub fn insert(&self, key: TileCacheKey, tile: Vec<u8>) {
+        if tile.len() > self.budget_bytes {
+            return;
+        }
+
+        let mut inner = self.inner.lock().unwrap();
+        let size = tile.len();
+        if let Some(old) = inner.tiles.put(key, tile) {
+            inner.bytes -= old.len();
+        }
+        inner.bytes += size;
+
+        while inner.bytes > self.budget_bytes {
+            match inner.tiles.pop_lru() {
+                Some((_, evicted)) => inner.bytes -= evicted.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Drop every tile rendered from the given layer
+    pub fn invalidate_layer(&self, layer_id: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        let stale: Vec<TileCacheKey> = inner
+            .tiles
+            .iter()
+            .filter(|(key, _)| key.references(layer_id))
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in stale {
+            if let Some(tile) = inner.tiles.pop(&key) {
+                inner.bytes -= tile.len();
+            }
+        }
+    }
+
+    pub fn stats(&self) -> TileCacheStats {
+        let inner = self.inner.lock().unwrap();
+        TileCacheStats {
+            entries: inner.tiles.len(),
+            bytes: inner.bytes,
+            budget_bytes: self.budget_bytes,
+            hits: inner.hits,
+            misses: inner.misses,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(layer: &str, x: i32, params: &(i32, f64)) -> TileCacheKey {
+        TileCacheKey::new(&[layer], 3, x, 2, params)
+    }
+
+    #[test]
+    fn test_tile_cache_hit_and_miss() {
+        let cache = TileCache::new(1024);
+        let k = key("a", 1, &(1, 1.0));
+
+        assert!(cache.get(&k).is_none());
+        cache.insert(k.clone(), vec![1, 2, 3]);
+        assert_eq!(cache.get(&k), Some(vec![1, 2, 3]));
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.bytes, 3);
+    }
+
+    #[test]
+    fn test_tile_cache_key_includes_params() {
+        let a = key("a", 1, &(1, 1.0));
+        let b = key("a", 1, &(1, 0.5));
+        assert!(a != b, "different render params should give different keys");
+        assert!(a == key("a", 1, &(1, 1.0)));
+    }
+
+    #[test]
+    fn test_tile_cache_evicts_to_budget() {
+        let cache = TileCache::new(10);
+        cache.insert(key("a", 1, &(1, 1.0)), vec![0; 6]);
+        cache.insert(key("a", 2, &(1, 1.0)), vec![0; 6]);
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, 6);
+        assert!(cache.get(&key("a", 1, &(1, 1.0))).is_none());
+        assert!(cache.get(&key("a", 2, &(1, 1.0))).is_some());
+    }
+
+    #[test]
+    fn test_tile_cache_skips_oversized_tiles() {
+        let cache = TileCache::new(4);
+        cache.insert(key("a", 1, &(1, 1.0)), vec![0; 5]);
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_tile_cache_invalidate_layer() {
+        let cache = TileCache::new(1024);
+        cache.insert(key("a", 1, &(1, 1.0)), vec![0; 4]);
+        cache.insert(key("b", 1, &(1, 1.0)), vec![0; 4]);
+        cache.insert(
+            TileCacheKey::new(&["b", "a", "c"], 3, 5, 2, &(1, 1.0)),
+            vec![0; 4],
+        );
+
+        cache.invalidate_layer("a");
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 1);
+        assert_eq!(stats.bytes, 4);
+        assert!(cache.get(&key("b", 1, &(1, 1.0))).is_some());
+    }
+}
@@ -1,38 +1,142 @@
 #![allow(clippy::too_many_arguments)]
 
-use gdal::raster::reproject;
+use crate::gdal::bounds::{dataset_geo_bounds, split_antimeridian};
+use crate::gdal::colormap::{read_palette, ColorRamp};
+use crate::gdal::complex::{create_complex_mem, is_complex, read_values, ComplexPart};
+use crate::gdal::contrast::{ContrastLut, StretchMode};
+use crate::gdal::nodata::{apply_mask, read_masked, read_masked_part, NodataRules};
+use crate::gdal::stretch::{read_sample, AutoStretch};
+use crate::gdal::terrain::{self, map_neighbourhood, Gradient, HillshadeParams, TerrainDerivative};
+use crate::gdal::warp::warp_bands;
+use gdal::raster::ResampleAlg;
 use gdal::spatial_ref::SpatialRef;
 use gdal::{Dataset, DriverManager};
-use image::ImageBuffer;
+use gdal_sys::GDALResampleAlg;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ExtendedColorType, ImageBuffer};
+use serde::{Deserialize, Serialize};
 use std::f64::consts::PI;
 use std::io::Cursor;
+use std::sync::Arc;
+
+/// Resampling algorithm used when warping or reading tiles.
+///
+/// Nearest keeps categorical rasters (land cover, masks) crisp; the smoothing
+/// kernels suit imagery and DEMs when zoomed out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resampling {
+    #[default]
+    Nearest,
+    Bilinear,
+    Cubic,
+    Lanczos,
+    Average,
+    Mode,
+}
+
+impl Resampling {
+    /// Algorithm for the GDAL warper (georeferenced tiles)
+    pub fn to_warp_alg(self) -> GDALResampleAlg::Type {
+        match self {
+            Resampling::Nearest => GDALResampleAlg::GRA_NearestNeighbour,
+            Resampling::Bilinear => GDALResampleAlg::GRA_Bilinear,
+            Resampling::Cubic => GDALResampleAlg::GRA_Cubic,
+            Resampling::Lanczos => GDALResampleAlg::GRA_Lanczos,
+            Resampling::Average => GDALResampleAlg::GRA_Average,
+            Resampling::Mode => GDALResampleAlg::GRA_Mode,
+        }
+    }
+
+    /// Algorithm for `RasterIO` reads (non-georeferenced tiles)
+    pub fn to_read_alg(self) -> ResampleAlg {
+        match self {
+            Resampling::Nearest => ResampleAlg::NearestNeighbour,
+            Resampling::Bilinear => ResampleAlg::Bilinear,
+            Resampling::Cubic => ResampleAlg::Cubic,
+            Resampling::Lanczos => ResampleAlg::Lanczos,
+            Resampling::Average => ResampleAlg::Average,
+            Resampling::Mode => ResampleAlg::Mode,
+        }
+    }
+}
+
+/// Image format of rendered tiles.
+///
+/// PNG is lossless but slow to encode and large for imagery. WebP is
+/// lossless and usually much smaller, or lossy (through libwebp) with a
+/// `quality`, which keeps the alpha channel at close to JPEG sizes. JPEG is
+/// smallest and fastest but has no alpha channel, so tiles with any
+/// transparent pixel (dataset edges, nodata) fall back to lossy WebP at the
+/// same quality.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TileFormat {
+    #[default]
+    Png,
+    Webp {
+        /// 1-100 for lossy encoding; lossless without
+        #[serde(default)]
+        quality: Option<u8>,
+    },
+    Jpeg {
+        /// 1-100
+        #[serde(default = "default_jpeg_quality")]
+        quality: u8,
+    },
+}
+
+fn default_jpeg_quality() -> u8 {
+    85
+}
 
 #[derive(Clone, Copy)]
-pub struct TileRequest {
+pub struct TileRequest<'a> {
     pub x: i32,
     pub y: i32,
     pub z: u8,
     pub band: i32,
     pub tile_size: usize,
+    pub resampling: Resampling,
+    pub format: TileFormat,
+    /// Layer's extra nodata values, applied after the dataset's own mask
+    pub nodata: &'a NodataRules,
+    /// How complex bands are drawn; ignored for real bands
+    pub complex: ComplexPart,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 pub struct StretchParams {
     pub min: f64,
     pub max: f64,
     pub gamma: f64,
+    #[serde(skip_serializing_if = "StretchMode::is_linear")]
+    pub mode: StretchMode,
+    /// Dataset-wide lookup table for the equalizing modes, attached by the
+    /// tile commands; without one the stretch is linear
+    #[serde(skip)]
+    pub lut: Option<Arc<ContrastLut>>,
 }
 
-impl Default for StretchParams {
-    fn default() -> Self {
+impl StretchParams {
+    pub fn new(min: f64, max: f64, gamma: f64, mode: StretchMode) -> Self {
         Self {
-            min: 0.0,
-            max: 255.0,
-            gamma: 1.0,
+            min,
+            max,
+            gamma,
+            mode,
+            lut: None,
         }
     }
 }
 
+impl Default for StretchParams {
+    fn default() -> Self {
+        Self::new(0.0, 255.0, 1.0, StretchMode::Linear)
+    }
+}
+
 /// Convert Web Mercator tile coordinates to EPSG:3857 bounds (meters)
 fn tile_to_web_mercator_bounds(x: i32, y: i32, z: u8) -> [f64; 4] {
     let n = 2_f64.powi(z as i32);
@@ -65,86 +169,67 @@ fn tile_to_geo_bounds(x: i32, y: i32, z: u8) -> [f64; 4] {
     [lon_min, lat_min, lon_max, lat_max]
 }
 
-/// Get dataset bounds in EPSG:4326
-fn get_dataset_geo_bounds(dataset: &Dataset) -> Result<[f64; 4], String> {
-    let gt = dataset
-        .geo_transform()
-        .map_err(|e| format!("Failed to get geotransform: {}", e))?;
-
-    let (width, height) = dataset.raster_size();
-    let projection = dataset.projection();
-
-    // Calculate native bounds
-    let native_min_x = gt[0];
-    let native_max_x = gt[0] + (width as f64) * gt[1];
-    let native_max_y = gt[3];
-    let native_min_y = gt[3] + (height as f64) * gt[5];
-
-    if projection.is_empty() {
-        return Ok([native_min_x, native_min_y, native_max_x, native_max_y]);
-    }
-
-    let source_srs = SpatialRef::from_wkt(&projection)
-        .map_err(|e| format!("Failed to parse source SRS: {}", e))?;
-
-    if source_srs.is_geographic() {
-        return Ok([native_min_x, native_min_y, native_max_x, native_max_y]);
-    }
-
-    // Transform to EPSG:4326
-    let mut target_srs = SpatialRef::from_epsg(4326)
-        .map_err(|e| format!("Failed to create EPSG:4326 SRS: {}", e))?;
-    target_srs
-        .set_axis_mapping_strategy(gdal::spatial_ref::AxisMappingStrategy::TraditionalGisOrder);
-
-    let transform = gdal::spatial_ref::CoordTransform::new(&source_srs, &target_srs)
-        .map_err(|e| format!("Failed to create transform: {}", e))?;
-
-    let mut xs = vec![native_min_x, native_max_x, native_min_x, native_max_x];
-    let mut ys = vec![native_min_y, native_min_y, native_max_y, native_max_y];
-
-    transform
-        .transform_coords(&mut xs, &mut ys, &mut [])
-        .map_err(|e| format!("Failed to transform: {}", e))?;
-
-    let min_lon = xs.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_lon = xs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-    let min_lat = ys.iter().cloned().fold(f64::INFINITY, f64::min);
-    let max_lat = ys.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-
-    Ok([min_lon, min_lat, max_lon, max_lat])
-}
-
 /// Check if two bounding boxes intersect
 fn bounds_intersect(a: [f64; 4], b: [f64; 4]) -> bool {
     !(a[2] < b[0] || a[0] > b[2] || a[3] < b[1] || a[1] > b[3])
 }
 
-/// Extract raw tile data (f64 values) for a single band
-fn extract_raw_tile(dataset: &Dataset, request: &TileRequest) -> Result<Vec<f64>, String> {
+/// Check if a tile intersects dataset bounds, testing each side of the
+/// antimeridian separately for datasets that cross it
+fn tile_intersects_dataset(tile_bounds: [f64; 4], ds_bounds: [f64; 4]) -> bool {
+    split_antimeridian(ds_bounds)
+        .into_iter()
+        .any(|part| bounds_intersect(tile_bounds, part))
+}
+
+/// Extract raw tile data (f64 values) for the given bands.
+///
+/// Only the listed bands are warped, all in a single pass; the result holds one
+/// buffer per requested band, in the same order. `buffer` grows the window by
+/// that many pixels on every side, so each buffer is
+/// `(tile_size + 2 * buffer)²`; neighbourhood operations use it to see past the
+/// tile edge.
+///
+/// Pixels with no valid source data (outside the image, nodata, masked or
+/// transparent in the source) come back as NaN.
+fn extract_raw_tile(
+    dataset: &Dataset,
+    request: &TileRequest,
+    bands: &[usize],
+    buffer: usize,
+) -> Result<Vec<Vec<f64>>, String> {
     // Get tile bounds in Web Mercator (EPSG:3857)
     let tile_bounds = tile_to_web_mercator_bounds(request.x, request.y, request.z);
     let tile_size = request.tile_size;
-    let band_count = dataset.raster_count();
-
-    // Create in-memory output dataset in Web Mercator with same number of bands
-    let mem_driver = DriverManager::get_driver_by_name("MEM")
-        .map_err(|e| format!("Failed to get MEM driver: {}", e))?;
-
-    let mut output_ds = mem_driver
-        .create_with_band_type::<f64, _>("", tile_size, tile_size, band_count)
-        .map_err(|e| format!("Failed to create output dataset: {}", e))?;
+    let size = tile_size + 2 * buffer;
+
+    // Create in-memory output dataset in Web Mercator with one band per
+    // requested band, plus an alpha band recording where source data landed.
+    // Complex sources keep both parts through the warp, and the requested
+    // part is taken afterwards
+    let complex = bands
+        .iter()
+        .filter_map(|&b| dataset.rasterband(b).ok())
+        .any(|band| is_complex(&band));
+    let mut output_ds = if complex {
+        create_complex_mem((size, size), bands.len() + 1)?
+    } else {
+        DriverManager::get_driver_by_name("MEM")
+            .map_err(|e| format!("Failed to get MEM driver: {}", e))?
+            .create_with_band_type::<f64, _>("", size, size, bands.len() + 1)
+            .map_err(|e| format!("Failed to create output dataset: {}", e))?
+    };
 
-    // Set output geotransform for Web Mercator tile
+    // Set output geotransform for Web Mercator tile, shifted out by the buffer
     let pixel_size_x = (tile_bounds[2] - tile_bounds[0]) / tile_size as f64;
     let pixel_size_y = (tile_bounds[1] - tile_bounds[3]) / tile_size as f64;
 
     output_ds
         .set_geo_transform(&[
-            tile_bounds[0],
+            tile_bounds[0] - buffer as f64 * pixel_size_x,
             pixel_size_x,
             0.0,
-            tile_bounds[3],
+            tile_bounds[3] - buffer as f64 * pixel_size_y,
             0.0,
             pixel_size_y,
         ])
@@ -157,107 +242,273 @@ fn extract_raw_tile(dataset: &Dataset, request: &TileRequest) -> Result<Vec<f64>
         .set_projection(&web_mercator.to_wkt().unwrap_or_default())
         .map_err(|e| format!("Failed to set projection: {}", e))?;
 
-    // Use GDAL's warp to reproject all bands
-    reproject(dataset, &output_ds).map_err(|e| format!("Failed to reproject: {}", e))?;
-
-    // Read the requested band from the reprojected output
-    let output_band = output_ds
-        .rasterband(request.band as usize)
-        .map_err(|e| format!("Failed to get output band {}: {}", request.band, e))?;
+    // Warp just the requested bands
+    warp_bands(dataset, &output_ds, bands, request.resampling.to_warp_alg())?;
+
+    // Read every band back from the reprojected output
+    let mut data = (1..=bands.len() + 1)
+        .map(|i| {
+            let output_band = output_ds
+                .rasterband(i)
+                .map_err(|e| format!("Failed to get output band {}: {}", i, e))?;
+
+            // The alpha band holds no imaginary part to draw
+            let part = if i > bands.len() {
+                ComplexPart::Real
+            } else {
+                request.complex
+            };
+            read_values(&output_band, (0, 0), (size, size), (size, size), None, part)
+                .map_err(|e| format!("Failed to read output: {}", e))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let valid: Vec<bool> = data
+        .pop()
+        .unwrap_or_default()
+        .iter()
+        .map(|&a| a > 0.0)
+        .collect();
+    for band in &mut data {
+        apply_mask(band, &valid);
+    }
 
-    let buffer = output_band
-        .read_as::<f64>((0, 0), (tile_size, tile_size), (tile_size, tile_size), None)
-        .map_err(|e| format!("Failed to read output: {}", e))?;
+    Ok(data)
+}
 
-    Ok(buffer.data().to_vec())
+/// Ground size in metres of each pixel row of a Web Mercator tile.
+///
+/// Mercator pixels shrink on the ground by `cos(latitude)`, so slopes computed
+/// from the projected pixel size would be exaggerated away from the equator.
+fn mercator_row_cell_sizes(request: &TileRequest) -> Vec<f64> {
+    let tile_bounds = tile_to_web_mercator_bounds(request.x, request.y, request.z);
+    let pixel_size = (tile_bounds[2] - tile_bounds[0]) / request.tile_size as f64;
+
+    (0..request.tile_size)
+        .map(|row| {
+            let y = tile_bounds[3] - (row as f64 + 0.5) * pixel_size;
+            let lat = (y / 6378137.0).sinh().atan();
+            pixel_size * lat.cos()
+        })
+        .collect()
 }
 
 /// Apply stretch and gamma to a value
-fn apply_stretch(val: f64, stretch: &StretchParams, nodata: Option<f64>) -> Option<u8> {
-    // Check for nodata or invalid values
-    // Many rasters use 0 as nodata for areas outside the image extent
-    if val == 0.0 || nodata.is_some_and(|nd| (val - nd).abs() < 1e-10) || !val.is_finite() {
+///
+/// Masked pixels arrive as NaN; `rules` hides the layer's extra nodata values.
+fn apply_stretch(val: f64, stretch: &StretchParams, rules: &NodataRules) -> Option<u8> {
+    if !rules.is_valid(val) {
         return None;
     }
 
+    Some(stretch_value(val, stretch))
+}
+
+/// Stretch a value already known to be valid
+fn stretch_value(val: f64, stretch: &StretchParams) -> u8 {
     let range = if stretch.max > stretch.min {
         stretch.max - stretch.min
     } else {
         1.0
     };
     let normalized = (val - stretch.min) / range;
-    let clamped = normalized.clamp(0.0, 1.0);
+    let clamped = match &stretch.lut {
+        Some(lut) => lut.level(normalized),
+        None => normalized.clamp(0.0, 1.0),
+    };
 
     // Apply gamma correction
     let gamma_corrected = clamped.powf(1.0 / stretch.gamma);
 
-    Some((gamma_corrected * 255.0).clamp(0.0, 255.0) as u8)
+    (gamma_corrected * 255.0).clamp(0.0, 255.0) as u8
+}
+
+/// Stretch a single-band value and map it to RGBA, through the colormap if
+/// one is set and as grayscale otherwise
+fn colorize(
+    val: f64,
+    stretch: &StretchParams,
+    rules: &NodataRules,
+    colormap: Option<&ColorRamp>,
+) -> Option<[u8; 4]> {
+    if !rules.is_valid(val) {
+        return None;
+    }
+
+    colorize_valid(val, stretch, colormap)
+}
+
+/// Colour a value that has already passed the nodata check. Derived terrain
+/// values go straight here, since the layer's nodata rules describe the
+/// elevations, not the slopes computed from them.
+fn colorize_valid(
+    val: f64,
+    stretch: &StretchParams,
+    colormap: Option<&ColorRamp>,
+) -> Option<[u8; 4]> {
+    // Class maps and palettes look up the raw value, without stretching
+    if let Some(ramp) = colormap.filter(|ramp| ramp.is_discrete()) {
+        return ramp.color(val, 0);
+    }
+
+    let stretched = stretch_value(val, stretch);
+    match colormap {
+        Some(ramp) => ramp.color(val, stretched),
+        None => Some([stretched, stretched, stretched, 255]),
+    }
+}
+
+/// Force nearest resampling for class maps and palettes, where an
+/// interpolated value is not a valid class
+fn discrete_request<'a>(
+    request: &TileRequest<'a>,
+    colormap: Option<&ColorRamp>,
+) -> TileRequest<'a> {
+    if colormap.is_some_and(ColorRamp::is_discrete) {
+        TileRequest {
+            resampling: Resampling::Nearest,
+            ..*request
+        }
+    } else {
+        *request
+    }
 }
 
 /// Extract a tile with custom stretch parameters
+///
+/// With `hillshade` the band is treated as elevation and shaded; a colormap on
+/// top gives colour relief. With `derivative` the slope or aspect is stretched
+/// and coloured instead of the elevation.
 pub fn extract_tile_with_stretch(
     dataset: &Dataset,
     request: &TileRequest,
     stretch: &StretchParams,
+    colormap: Option<&ColorRamp>,
+    hillshade: Option<&HillshadeParams>,
+    derivative: Option<TerrainDerivative>,
 ) -> Result<Vec<u8>, String> {
     // Get tile bounds in geographic coordinates for intersection test
     let tile_geo_bounds = tile_to_geo_bounds(request.x, request.y, request.z);
 
     // Get dataset bounds in geographic coordinates
-    let ds_geo_bounds = get_dataset_geo_bounds(dataset)?;
+    let ds_geo_bounds = dataset_geo_bounds(dataset)?;
 
     // Check if tile intersects dataset
-    if !bounds_intersect(tile_geo_bounds, ds_geo_bounds) {
+    if !tile_intersects_dataset(tile_geo_bounds, ds_geo_bounds) {
         return create_empty_tile(request.tile_size);
     }
 
-    // Get nodata value
     let band = dataset
         .rasterband(request.band as usize)
         .map_err(|e| format!("Failed to get band: {}", e))?;
-    let nodata = band.no_data_value();
 
-    // Extract raw tile data
-    let data = extract_raw_tile(dataset, request)?;
+    // Paletted bands are drawn through their colour table unless the caller
+    // picked a colormap
+    let palette = colormap
+        .is_none()
+        .then(|| read_palette(&band).map(ColorRamp::Palette))
+        .flatten();
+    let colormap = colormap.or(palette.as_ref());
+    let request = &discrete_request(request, colormap);
+
     let tile_size = request.tile_size;
 
     // Create RGBA output
     let mut tile_data = vec![0u8; tile_size * tile_size * 4];
 
-    for (i, &val) in data.iter().enumerate() {
-        let idx = i * 4;
+    if hillshade.is_none() && derivative.is_none() {
+        // Extract raw tile data
+        let data = extract_raw_tile(dataset, request, &[request.band as usize], 0)?.swap_remove(0);
 
-        if let Some(stretched) = apply_stretch(val, stretch, nodata) {
-            tile_data[idx] = stretched;
-            tile_data[idx + 1] = stretched;
-            tile_data[idx + 2] = stretched;
-            tile_data[idx + 3] = 255;
+        for (i, &val) in data.iter().enumerate() {
+            if let Some(rgba) = colorize(val, stretch, request.nodata, colormap) {
+                tile_data[i * 4..i * 4 + 4].copy_from_slice(&rgba);
+            }
+            // else: leave as transparent (0, 0, 0, 0)
+        }
+
+        return encode_tile(&tile_data, tile_size, request.format);
+    }
+
+    // Terrain modes need each pixel's neighbours, so read a 1-pixel buffer to
+    // keep adjacent tiles seamless
+    let grid = extract_raw_tile(dataset, request, &[request.band as usize], 1)?.swap_remove(0);
+    let cell_sizes = mercator_row_cell_sizes(request);
+    let gradients = map_neighbourhood(
+        &grid,
+        tile_size,
+        |val| request.nodata.is_valid(val),
+        |row, window| {
+            let cell_size = cell_sizes[row];
+            Gradient::horn(window, cell_size, cell_size)
+        },
+    );
+
+    for (i, gradient) in gradients.into_iter().enumerate() {
+        let Some(gradient) = gradient else { continue };
+
+        // Value to colour: the derived slope/aspect, or the elevation itself.
+        // Flat cells have no aspect and stay transparent.
+        let val = match derivative {
+            Some(derivative) => match derivative.compute(gradient) {
+                Some(val) => val,
+                None => continue,
+            },
+            None => grid[(i / tile_size + 1) * (tile_size + 2) + i % tile_size + 1],
+        };
+
+        let rgba = match hillshade {
+            Some(params) => {
+                shade_pixel(val, terrain::hillshade(gradient, params), stretch, colormap)
+            }
+            None => colorize_valid(val, stretch, colormap),
+        };
+        if let Some(rgba) = rgba {
+            tile_data[i * 4..i * 4 + 4].copy_from_slice(&rgba);
         }
-        // else: leave as transparent (0, 0, 0, 0)
     }
 
-    encode_png(&tile_data, tile_size)
+    encode_tile(&tile_data, tile_size, request.format)
+}
+
+/// Combine a hillshade brightness with the pixel's colour: plain grey shading
+/// without a colormap, colour relief (the colormap darkened by the shade) with
+/// one
+fn shade_pixel(
+    val: f64,
+    shade: f64,
+    stretch: &StretchParams,
+    colormap: Option<&ColorRamp>,
+) -> Option<[u8; 4]> {
+    if colormap.is_none() {
+        let grey = (shade * 255.0).round() as u8;
+        return Some([grey, grey, grey, 255]);
+    }
+
+    let [r, g, b, a] = colorize_valid(val, stretch, colormap)?;
+    let blend = |c: u8| (c as f64 * shade).round() as u8;
+    Some([blend(r), blend(g), blend(b), a])
 }
 
-/// Extract a tile using default auto-calculated stretch
-pub fn extract_tile(dataset: &Dataset, request: &TileRequest) -> Result<Vec<u8>, String> {
-    // Get global statistics for auto stretch
+/// Extract a tile using the default auto stretch (2-98% percentile cut of a
+/// band sample)
+pub fn extract_tile(
+    dataset: &Dataset,
+    request: &TileRequest,
+    colormap: Option<&ColorRamp>,
+    hillshade: Option<&HillshadeParams>,
+    derivative: Option<TerrainDerivative>,
+) -> Result<Vec<u8>, String> {
     let band = dataset
         .rasterband(request.band as usize)
         .map_err(|e| format!("Failed to get band: {}", e))?;
 
-    let (min_val, max_val) = match band.compute_raster_min_max(true) {
-        Ok(stats) => (stats.min, stats.max),
-        Err(_) => (0.0, 255.0),
-    };
-
-    let stretch = StretchParams {
-        min: min_val,
-        max: max_val,
-        gamma: 1.0,
-    };
+    let stretch = read_sample(&band, (0, 0), dataset.raster_size(), 256, request.complex)
+        .ok()
+        .and_then(|values| AutoStretch::default().compute(&values, request.nodata))
+        .unwrap_or_default();
 
-    extract_tile_with_stretch(dataset, request, &stretch)
+    extract_tile_with_stretch(dataset, request, &stretch, colormap, hillshade, derivative)
 }
 
 /// Extract an RGB composite tile from potentially different bands
@@ -273,43 +524,20 @@ pub fn extract_rgb_tile(
 ) -> Result<Vec<u8>, String> {
     // Get tile bounds in geographic coordinates for intersection test
     let tile_geo_bounds = tile_to_geo_bounds(request.x, request.y, request.z);
-    let ds_geo_bounds = get_dataset_geo_bounds(dataset)?;
+    let ds_geo_bounds = dataset_geo_bounds(dataset)?;
 
-    if !bounds_intersect(tile_geo_bounds, ds_geo_bounds) {
+    if !tile_intersects_dataset(tile_geo_bounds, ds_geo_bounds) {
         return create_empty_tile(request.tile_size);
     }
 
-    // Get nodata values for each band
-    let r_nodata = dataset
-        .rasterband(red_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let g_nodata = dataset
-        .rasterband(green_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let b_nodata = dataset
-        .rasterband(blue_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-
-    // Extract raw data for each band
-    let r_request = TileRequest {
-        band: red_band,
-        ..*request
-    };
-    let g_request = TileRequest {
-        band: green_band,
-        ..*request
-    };
-    let b_request = TileRequest {
-        band: blue_band,
-        ..*request
-    };
-
-    let r_data = extract_raw_tile(dataset, &r_request)?;
-    let g_data = extract_raw_tile(dataset, &g_request)?;
-    let b_data = extract_raw_tile(dataset, &b_request)?;
+    // Warp the three bands together
+    let rgb_data = extract_raw_tile(
+        dataset,
+        request,
+        &[red_band as usize, green_band as usize, blue_band as usize],
+        0,
+    )?;
+    let (r_data, g_data, b_data) = (&rgb_data[0], &rgb_data[1], &rgb_data[2]);
 
     let tile_size = request.tile_size;
     let mut tile_data = vec![0u8; tile_size * tile_size * 4];
@@ -317,9 +545,9 @@ pub fn extract_rgb_tile(
     for i in 0..r_data.len() {
         let idx = i * 4;
 
-        let r = apply_stretch(r_data[i], red_stretch, r_nodata);
-        let g = apply_stretch(g_data[i], green_stretch, g_nodata);
-        let b = apply_stretch(b_data[i], blue_stretch, b_nodata);
+        let r = apply_stretch(r_data[i], red_stretch, request.nodata);
+        let g = apply_stretch(g_data[i], green_stretch, request.nodata);
+        let b = apply_stretch(b_data[i], blue_stretch, request.nodata);
 
         // If any band has valid data, show the pixel
         if r.is_some() || g.is_some() || b.is_some() {
@@ -330,7 +558,86 @@ pub fn extract_rgb_tile(
         }
     }
 
-    encode_png(&tile_data, tile_size)
+    encode_tile(&tile_data, tile_size, request.format)
+}
+
+/// Magic bytes opening a data tile (see [`encode_data_tile`])
+const DATA_TILE_MAGIC: &[u8; 4] = b"HDT1";
+
+/// Extract the warped values of one or more bands, before any stretch, as a
+/// data tile for client-side styling
+pub fn extract_data_tile(
+    dataset: &Dataset,
+    request: &TileRequest,
+    bands: &[usize],
+) -> Result<Vec<u8>, String> {
+    let tile_geo_bounds = tile_to_geo_bounds(request.x, request.y, request.z);
+    let ds_geo_bounds = dataset_geo_bounds(dataset)?;
+
+    if !tile_intersects_dataset(tile_geo_bounds, ds_geo_bounds) {
+        return Ok(encode_data_tile(
+            &vec![Vec::new(); bands.len()],
+            0,
+            request.nodata,
+        ));
+    }
+
+    let data = extract_raw_tile(dataset, request, bands, 0)?;
+    Ok(encode_data_tile(&data, request.tile_size, request.nodata))
+}
+
+/// Extract a data tile for non-georeferenced images (see [`extract_data_tile`])
+pub fn extract_pixel_data_tile(
+    dataset: &Dataset,
+    request: &TileRequest,
+    bands: &[usize],
+) -> Result<Vec<u8>, String> {
+    let data = bands
+        .iter()
+        .map(|&band| {
+            let band_request = TileRequest {
+                band: band as i32,
+                ..*request
+            };
+            extract_raw_pixel_tile(dataset, &band_request)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(encode_data_tile(&data, request.tile_size, request.nodata))
+}
+
+/// Pack band values into a data tile, little-endian throughout:
+///
+/// - 12-byte header: magic `HDT1`, then width, height, band count and a
+///   reserved zero as `u16`
+/// - each band in turn as `width * height` `f32` values, NaN where invalid
+/// - a `width * height` byte validity mask, 255 where any band holds data
+///
+/// Tiles outside the dataset have a zero width and height and no data.
+pub fn encode_data_tile(bands: &[Vec<f64>], size: usize, rules: &NodataRules) -> Vec<u8> {
+    let pixels = size * size;
+    let mut out = Vec::with_capacity(12 + bands.len() * pixels * 4 + pixels);
+
+    out.extend_from_slice(DATA_TILE_MAGIC);
+    for field in [size, size, bands.len(), 0] {
+        out.extend_from_slice(&(field as u16).to_le_bytes());
+    }
+
+    let mut mask = vec![0u8; pixels];
+    for band in bands {
+        for (i, &val) in band.iter().take(pixels).enumerate() {
+            let val = if rules.is_valid(val) {
+                mask[i] = 255;
+                val as f32
+            } else {
+                f32::NAN
+            };
+            out.extend_from_slice(&val.to_le_bytes());
+        }
+    }
+    out.extend_from_slice(&mask);
+
+    out
 }
 
 fn create_empty_tile(size: usize) -> Result<Vec<u8>, String> {
@@ -338,6 +645,62 @@ fn create_empty_tile(size: usize) -> Result<Vec<u8>, String> {
     encode_png(&data, size)
 }
 
+/// Encode an RGBA tile in the requested format, falling back from JPEG to
+/// lossy WebP when any pixel is transparent
+pub fn encode_tile(rgba_data: &[u8], size: usize, format: TileFormat) -> Result<Vec<u8>, String> {
+    match format {
+        TileFormat::Png => encode_png(rgba_data, size),
+        TileFormat::Webp { quality: None } => encode_webp(rgba_data, size),
+        TileFormat::Webp {
+            quality: Some(quality),
+        } => encode_lossy_webp(rgba_data, size, quality),
+        TileFormat::Jpeg { quality } => {
+            if rgba_data.chunks_exact(4).all(|px| px[3] == 255) {
+                encode_jpeg(rgba_data, size, quality)
+            } else {
+                encode_lossy_webp(rgba_data, size, quality)
+            }
+        }
+    }
+}
+
+/// Lossy WebP with alpha; the pure-Rust encoder in `image` is lossless only
+fn encode_lossy_webp(rgba_data: &[u8], size: usize, quality: u8) -> Result<Vec<u8>, String> {
+    let encoded = webp::Encoder::from_rgba(rgba_data, size as u32, size as u32)
+        .encode_simple(false, quality.clamp(1, 100) as f32)
+        .map_err(|e| format!("Failed to encode WebP: {:?}", e))?;
+
+    Ok(encoded.to_vec())
+}
+
+fn encode_webp(rgba_data: &[u8], size: usize) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    WebPEncoder::new_lossless(&mut bytes)
+        .encode(
+            rgba_data,
+            size as u32,
+            size as u32,
+            ExtendedColorType::Rgba8,
+        )
+        .map_err(|e| format!("Failed to encode WebP: {}", e))?;
+
+    Ok(bytes)
+}
+
+fn encode_jpeg(rgba_data: &[u8], size: usize, quality: u8) -> Result<Vec<u8>, String> {
+    let rgb: Vec<u8> = rgba_data
+        .chunks_exact(4)
+        .flat_map(|px| [px[0], px[1], px[2]])
+        .collect();
+
+    let mut bytes: Vec<u8> = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, quality.clamp(1, 100))
+        .encode(&rgb, size as u32, size as u32, ExtendedColorType::Rgb8)
+        .map_err(|e| format!("Failed to encode JPEG: {}", e))?;
+
+    Ok(bytes)
+}
+
 fn encode_png(rgba_data: &[u8], size: usize) -> Result<Vec<u8>, String> {
     let img: ImageBuffer<image::Rgba<u8>, Vec<u8>> =
         ImageBuffer::from_raw(size as u32, size as u32, rgba_data.to_vec())
@@ -352,17 +715,32 @@ fn encode_png(rgba_data: &[u8], size: usize) -> Result<Vec<u8>, String> {
     Ok(bytes)
 }
 
-/// Extract raw pixel data for non-georeferenced images (returns f64 values)
-fn extract_raw_pixel_tile(dataset: &Dataset, request: &TileRequest) -> Result<Vec<f64>, String> {
-    let (img_width, img_height) = dataset.raster_size();
+/// Source and destination rectangles of a tile over a non-georeferenced image
+struct PixelTileWindow {
+    src_offset: (isize, isize),
+    src_size: (usize, usize),
+    /// Where the source rectangle lands in the tile; always inside it
+    dst_offset: (usize, usize),
+    dst_size: (usize, usize),
+}
+
+/// Map a tile onto a non-georeferenced image, using the same synthetic
+/// coordinate system as the frontend: images are centered at 0,0 with 0.01°
+/// per pixel, squeezed vertically to stay within ±85°.
+///
+/// Returns `None` when the tile misses the image. Tiles over the image edge
+/// only cover the part of the tile the image fills, at least one pixel even
+/// when the image is a sliver along the tile's far edge.
+fn pixel_tile_window(img_size: (usize, usize), request: &TileRequest) -> Option<PixelTileWindow> {
+    let (img_width, img_height) = img_size;
     let tile_size = request.tile_size;
 
-    // Use the same synthetic coordinate system as the frontend
     let scale = 0.01;
     let half_width = (img_width as f64 * scale) / 2.0;
     let half_height = (img_height as f64 * scale) / 2.0;
     let clamped_half_height = half_height.min(85.0);
 
+    // Get tile bounds in geographic coordinates (from MapLibre)
     let tile_geo_bounds = tile_to_geo_bounds(request.x, request.y, request.z);
     let img_geo_bounds = [
         -half_width,
@@ -372,50 +750,101 @@ fn extract_raw_pixel_tile(dataset: &Dataset, request: &TileRequest) -> Result<Ve
     ];
 
     if !bounds_intersect(tile_geo_bounds, img_geo_bounds) {
-        return Ok(vec![0.0; tile_size * tile_size]);
+        return None;
     }
 
+    // geo_x = -half_width + pixel_x * scale  =>  pixel_x = (geo_x + half_width) / scale
+    // geo_y = clamped_half_height - pixel_y * pixel_scale_y  =>  pixel_y = (clamped_half_height - geo_y) / pixel_scale_y
     let pixel_scale_y = if half_height > 85.0 {
         (clamped_half_height * 2.0) / img_height as f64
     } else {
         scale
     };
 
-    let src_x_f = (tile_geo_bounds[0] + half_width) / scale;
-    let src_y_f = (clamped_half_height - tile_geo_bounds[3]) / pixel_scale_y;
-    let src_x_end_f = (tile_geo_bounds[2] + half_width) / scale;
-    let src_y_end_f = (clamped_half_height - tile_geo_bounds[1]) / pixel_scale_y;
+    // Calculate full tile extent in source pixel coordinates (unclamped)
+    let tile_src_x_start = (tile_geo_bounds[0] + half_width) / scale;
+    let tile_src_y_start = (clamped_half_height - tile_geo_bounds[3]) / pixel_scale_y;
+    let tile_src_x_end = (tile_geo_bounds[2] + half_width) / scale;
+    let tile_src_y_end = (clamped_half_height - tile_geo_bounds[1]) / pixel_scale_y;
 
-    let src_x = src_x_f.max(0.0).floor() as isize;
-    let src_y = src_y_f.max(0.0).floor() as isize;
-    let src_x_end = src_x_end_f.min(img_width as f64).ceil() as isize;
-    let src_y_end = src_y_end_f.min(img_height as f64).ceil() as isize;
+    // Calculate the intersection with image bounds (clamped source coordinates)
+    let src_x_f = tile_src_x_start.max(0.0);
+    let src_y_f = tile_src_y_start.max(0.0);
+    let src_x_end_f = tile_src_x_end.min(img_width as f64);
+    let src_y_end_f = tile_src_y_end.min(img_height as f64);
 
-    let src_width = (src_x_end - src_x).max(1) as usize;
-    let src_height = (src_y_end - src_y).max(1) as usize;
+    // Integer source coordinates
+    let src_x = src_x_f.floor() as isize;
+    let src_y = src_y_f.floor() as isize;
+    let src_x_end = src_x_end_f.ceil() as isize;
+    let src_y_end = src_y_end_f.ceil() as isize;
+
+    let src_width = (src_x_end - src_x).max(0) as usize;
+    let src_height = (src_y_end - src_y).max(0) as usize;
 
     if src_width == 0
         || src_height == 0
         || src_x >= img_width as isize
         || src_y >= img_height as isize
     {
-        return Ok(vec![0.0; tile_size * tile_size]);
+        return None;
     }
 
+    // Destination span in tile coordinates (0 to tile_size), so partial
+    // tiles only fill the portion the image covers
+    let dst_span = |start: f64, end: f64, tile_start: f64, tile_end: f64| {
+        let to_tile =
+            |v: f64| ((v - tile_start) / (tile_end - tile_start) * tile_size as f64).round();
+        let dst_start = (to_tile(start) as usize).min(tile_size - 1);
+        let dst_len = (to_tile(end) as usize)
+            .saturating_sub(dst_start)
+            .max(1)
+            .min(tile_size - dst_start);
+        (dst_start, dst_len)
+    };
+    let (dst_x, dst_width) = dst_span(src_x_f, src_x_end_f, tile_src_x_start, tile_src_x_end);
+    let (dst_y, dst_height) = dst_span(src_y_f, src_y_end_f, tile_src_y_start, tile_src_y_end);
+
+    Some(PixelTileWindow {
+        src_offset: (src_x, src_y),
+        src_size: (src_width, src_height),
+        dst_offset: (dst_x, dst_y),
+        dst_size: (dst_width, dst_height),
+    })
+}
+
+/// Extract raw pixel data for non-georeferenced images (returns f64 values).
+///
+/// Tiles over the image edge hold the image only where it lies within the
+/// tile; the rest is NaN, like pixels outside a georeferenced image.
+fn extract_raw_pixel_tile(dataset: &Dataset, request: &TileRequest) -> Result<Vec<f64>, String> {
+    let tile_size = request.tile_size;
+    let Some(window) = pixel_tile_window(dataset.raster_size(), request) else {
+        return Ok(vec![f64::NAN; tile_size * tile_size]);
+    };
+
     let band = dataset
         .rasterband(request.band as usize)
         .map_err(|e| format!("Failed to get band: {}", e))?;
 
-    let buffer = band
-        .read_as::<f64>(
-            (src_x, src_y),
-            (src_width, src_height),
-            (tile_size, tile_size),
-            None,
-        )
-        .map_err(|e| format!("Failed to read: {}", e))?;
-
-    Ok(buffer.data().to_vec())
+    // Read source data and resample to the destination size (not full tile size)
+    let data = read_masked_part(
+        &band,
+        window.src_offset,
+        window.src_size,
+        window.dst_size,
+        Some(request.resampling.to_read_alg()),
+        request.complex,
+    )?;
+
+    let (dst_x, dst_y) = window.dst_offset;
+    let (dst_width, dst_height) = window.dst_size;
+    let mut tile = vec![f64::NAN; tile_size * tile_size];
+    for (dy, row) in data.chunks(dst_width).take(dst_height).enumerate() {
+        let start = (dst_y + dy) * tile_size + dst_x;
+        tile[start..start + dst_width].copy_from_slice(row);
+    }
+    Ok(tile)
 }
 
 /// Extract a cross-layer RGB tile from multiple datasets (for non-georeferenced images)
@@ -450,27 +879,14 @@ pub fn extract_cross_layer_pixel_rgb_tile(
     let g_data = extract_raw_pixel_tile(green_ds, &g_request)?;
     let b_data = extract_raw_pixel_tile(blue_ds, &b_request)?;
 
-    let r_nodata = red_ds
-        .rasterband(red_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let g_nodata = green_ds
-        .rasterband(green_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let b_nodata = blue_ds
-        .rasterband(blue_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-
     let mut tile_data = vec![0u8; tile_size * tile_size * 4];
 
     for i in 0..r_data.len() {
         let idx = i * 4;
 
-        let r = apply_stretch(r_data[i], red_stretch, r_nodata);
-        let g = apply_stretch(g_data[i], green_stretch, g_nodata);
-        let b = apply_stretch(b_data[i], blue_stretch, b_nodata);
+        let r = apply_stretch(r_data[i], red_stretch, request.nodata);
+        let g = apply_stretch(g_data[i], green_stretch, request.nodata);
+        let b = apply_stretch(b_data[i], blue_stretch, request.nodata);
 
         if r.is_some() || g.is_some() || b.is_some() {
             tile_data[idx] = r.unwrap_or(0);
@@ -480,7 +896,7 @@ pub fn extract_cross_layer_pixel_rgb_tile(
         }
     }
 
-    encode_png(&tile_data, tile_size)
+    encode_tile(&tile_data, tile_size, request.format)
 }
 
 /// Extract a cross-layer RGB tile from multiple datasets
@@ -498,46 +914,19 @@ pub fn extract_cross_layer_rgb_tile(
 ) -> Result<Vec<u8>, String> {
     let tile_size = request.tile_size;
 
-    // Extract raw data from each dataset
-    let r_request = TileRequest {
-        band: red_band,
-        ..*request
-    };
-    let g_request = TileRequest {
-        band: green_band,
-        ..*request
-    };
-    let b_request = TileRequest {
-        band: blue_band,
-        ..*request
-    };
-
-    let r_data = extract_raw_tile(red_ds, &r_request)?;
-    let g_data = extract_raw_tile(green_ds, &g_request)?;
-    let b_data = extract_raw_tile(blue_ds, &b_request)?;
-
-    // Get nodata values
-    let r_nodata = red_ds
-        .rasterband(red_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let g_nodata = green_ds
-        .rasterband(green_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let b_nodata = blue_ds
-        .rasterband(blue_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
+    // Extract raw data from each dataset, warping only the requested band
+    let r_data = extract_raw_tile(red_ds, request, &[red_band as usize], 0)?.swap_remove(0);
+    let g_data = extract_raw_tile(green_ds, request, &[green_band as usize], 0)?.swap_remove(0);
+    let b_data = extract_raw_tile(blue_ds, request, &[blue_band as usize], 0)?.swap_remove(0);
 
     let mut tile_data = vec![0u8; tile_size * tile_size * 4];
 
     for i in 0..r_data.len() {
         let idx = i * 4;
 
-        let r = apply_stretch(r_data[i], red_stretch, r_nodata);
-        let g = apply_stretch(g_data[i], green_stretch, g_nodata);
-        let b = apply_stretch(b_data[i], blue_stretch, b_nodata);
+        let r = apply_stretch(r_data[i], red_stretch, request.nodata);
+        let g = apply_stretch(g_data[i], green_stretch, request.nodata);
+        let b = apply_stretch(b_data[i], blue_stretch, request.nodata);
 
         if r.is_some() || g.is_some() || b.is_some() {
             tile_data[idx] = r.unwrap_or(0);
@@ -547,7 +936,7 @@ pub fn extract_cross_layer_rgb_tile(
         }
     }
 
-    encode_png(&tile_data, tile_size)
+    encode_tile(&tile_data, tile_size, request.format)
 }
 
 /// Extract an RGB tile using pixel coordinates (for non-georeferenced images)
@@ -562,102 +951,11 @@ pub fn extract_pixel_rgb_tile(
     green_stretch: &StretchParams,
     blue_stretch: &StretchParams,
 ) -> Result<Vec<u8>, String> {
-    let (img_width, img_height) = dataset.raster_size();
     let tile_size = request.tile_size;
-
-    // Use the same synthetic coordinate system as the frontend
-    let scale = 0.01;
-    let half_width = (img_width as f64 * scale) / 2.0;
-    let half_height = (img_height as f64 * scale) / 2.0;
-    let clamped_half_height = half_height.min(85.0);
-
-    // Get tile bounds in geographic coordinates (from MapLibre)
-    let tile_geo_bounds = tile_to_geo_bounds(request.x, request.y, request.z);
-
-    // Image bounds in synthetic geographic coordinates
-    let img_geo_bounds = [
-        -half_width,
-        -clamped_half_height,
-        half_width,
-        clamped_half_height,
-    ];
-
-    // Check intersection
-    if !bounds_intersect(tile_geo_bounds, img_geo_bounds) {
+    let Some(window) = pixel_tile_window(dataset.raster_size(), request) else {
         return create_empty_tile(tile_size);
-    }
-
-    // Convert tile geographic bounds to pixel coordinates
-    let pixel_scale_y = if half_height > 85.0 {
-        (clamped_half_height * 2.0) / img_height as f64
-    } else {
-        scale
     };
 
-    // Calculate full tile extent in source pixel coordinates (unclamped)
-    let tile_src_x_start = (tile_geo_bounds[0] + half_width) / scale;
-    let tile_src_y_start = (clamped_half_height - tile_geo_bounds[3]) / pixel_scale_y;
-    let tile_src_x_end = (tile_geo_bounds[2] + half_width) / scale;
-    let tile_src_y_end = (clamped_half_height - tile_geo_bounds[1]) / pixel_scale_y;
-
-    // Calculate the intersection with image bounds (clamped source coordinates)
-    let src_x_f = tile_src_x_start.max(0.0);
-    let src_y_f = tile_src_y_start.max(0.0);
-    let src_x_end_f = tile_src_x_end.min(img_width as f64);
-    let src_y_end_f = tile_src_y_end.min(img_height as f64);
-
-    // Integer source coordinates
-    let src_x = src_x_f.floor() as isize;
-    let src_y = src_y_f.floor() as isize;
-    let src_x_end = src_x_end_f.ceil() as isize;
-    let src_y_end = src_y_end_f.ceil() as isize;
-
-    let src_width = (src_x_end - src_x).max(0) as usize;
-    let src_height = (src_y_end - src_y).max(0) as usize;
-
-    if src_width == 0
-        || src_height == 0
-        || src_x >= img_width as isize
-        || src_y >= img_height as isize
-    {
-        return create_empty_tile(tile_size);
-    }
-
-    // Calculate where in the output tile the image data should be placed
-    let tile_pixel_width = tile_src_x_end - tile_src_x_start;
-    let tile_pixel_height = tile_src_y_end - tile_src_y_start;
-
-    // Destination rectangle in tile coordinates (0 to tile_size)
-    let dst_x_start =
-        ((src_x_f - tile_src_x_start) / tile_pixel_width * tile_size as f64).round() as usize;
-    let dst_y_start =
-        ((src_y_f - tile_src_y_start) / tile_pixel_height * tile_size as f64).round() as usize;
-    let dst_x_end =
-        ((src_x_end_f - tile_src_x_start) / tile_pixel_width * tile_size as f64).round() as usize;
-    let dst_y_end =
-        ((src_y_end_f - tile_src_y_start) / tile_pixel_height * tile_size as f64).round() as usize;
-
-    let dst_width = (dst_x_end - dst_x_start)
-        .max(1)
-        .min(tile_size - dst_x_start);
-    let dst_height = (dst_y_end - dst_y_start)
-        .max(1)
-        .min(tile_size - dst_y_start);
-
-    // Get nodata values for each band
-    let r_nodata = dataset
-        .rasterband(red_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let g_nodata = dataset
-        .rasterband(green_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-    let b_nodata = dataset
-        .rasterband(blue_band as usize)
-        .ok()
-        .and_then(|b| b.no_data_value());
-
     // Read each band
     let r_band = dataset
         .rasterband(red_band as usize)
@@ -669,55 +967,46 @@ pub fn extract_pixel_rgb_tile(
         .rasterband(blue_band as usize)
         .map_err(|e| format!("Failed to get blue band: {}", e))?;
 
-    let r_buffer = r_band
-        .read_as::<f64>(
-            (src_x, src_y),
-            (src_width, src_height),
-            (dst_width, dst_height),
-            None,
-        )
-        .map_err(|e| format!("Failed to read red: {}", e))?;
-
-    let g_buffer = g_band
-        .read_as::<f64>(
-            (src_x, src_y),
-            (src_width, src_height),
-            (dst_width, dst_height),
-            None,
-        )
-        .map_err(|e| format!("Failed to read green: {}", e))?;
-
-    let b_buffer = b_band
-        .read_as::<f64>(
-            (src_x, src_y),
-            (src_width, src_height),
-            (dst_width, dst_height),
-            None,
-        )
-        .map_err(|e| format!("Failed to read blue: {}", e))?;
-
-    let r_data = r_buffer.data();
-    let g_data = g_buffer.data();
-    let b_data = b_buffer.data();
+    let r_data = read_masked(
+        &r_band,
+        window.src_offset,
+        window.src_size,
+        window.dst_size,
+        Some(request.resampling.to_read_alg()),
+    )
+    .map_err(|e| format!("Failed to read red: {}", e))?;
+
+    let g_data = read_masked(
+        &g_band,
+        window.src_offset,
+        window.src_size,
+        window.dst_size,
+        Some(request.resampling.to_read_alg()),
+    )
+    .map_err(|e| format!("Failed to read green: {}", e))?;
+
+    let b_data = read_masked(
+        &b_band,
+        window.src_offset,
+        window.src_size,
+        window.dst_size,
+        Some(request.resampling.to_read_alg()),
+    )
+    .map_err(|e| format!("Failed to read blue: {}", e))?;
 
     let mut tile_data = vec![0u8; tile_size * tile_size * 4];
 
     // Place the resampled data at the correct position in the output tile
+    let (dst_x, dst_y) = window.dst_offset;
+    let (dst_width, dst_height) = window.dst_size;
     for dy in 0..dst_height {
         for dx in 0..dst_width {
             let src_idx = dy * dst_width + dx;
-            let dst_tile_x = dst_x_start + dx;
-            let dst_tile_y = dst_y_start + dy;
+            let dst_idx = ((dst_y + dy) * tile_size + dst_x + dx) * 4;
 
-            if dst_tile_x >= tile_size || dst_tile_y >= tile_size {
-                continue;
-            }
-
-            let dst_idx = (dst_tile_y * tile_size + dst_tile_x) * 4;
-
-            let r = apply_stretch(r_data[src_idx], red_stretch, r_nodata);
-            let g = apply_stretch(g_data[src_idx], green_stretch, g_nodata);
-            let b = apply_stretch(b_data[src_idx], blue_stretch, b_nodata);
+            let r = apply_stretch(r_data[src_idx], red_stretch, request.nodata);
+            let g = apply_stretch(g_data[src_idx], green_stretch, request.nodata);
+            let b = apply_stretch(b_data[src_idx], blue_stretch, request.nodata);
 
             // If any band has valid data, show the pixel
             if r.is_some() || g.is_some() || b.is_some() {
@@ -729,7 +1018,7 @@ pub fn extract_pixel_rgb_tile(
         }
     }
 
-    encode_png(&tile_data, tile_size)
+    encode_tile(&tile_data, tile_size, request.format)
 }
 
 /// Extract a tile using pixel coordinates (for non-georeferenced images)
@@ -738,136 +1027,54 @@ pub fn extract_pixel_tile(
     dataset: &Dataset,
     request: &TileRequest,
     stretch: &StretchParams,
+    colormap: Option<&ColorRamp>,
 ) -> Result<Vec<u8>, String> {
-    let (img_width, img_height) = dataset.raster_size();
     let tile_size = request.tile_size;
-
-    // Use the same synthetic coordinate system as the frontend
-    // All non-geo images are centered at 0,0 with scale 0.01° per pixel
-    let scale = 0.01;
-    let half_width = (img_width as f64 * scale) / 2.0;
-    let half_height = (img_height as f64 * scale) / 2.0;
-    let clamped_half_height = half_height.min(85.0);
-
-    // Get tile bounds in geographic coordinates (from MapLibre)
-    let tile_geo_bounds = tile_to_geo_bounds(request.x, request.y, request.z);
-
-    // Image bounds in synthetic geographic coordinates
-    let img_geo_bounds = [
-        -half_width,
-        -clamped_half_height,
-        half_width,
-        clamped_half_height,
-    ];
-
-    // Check intersection
-    if !bounds_intersect(tile_geo_bounds, img_geo_bounds) {
+    let Some(window) = pixel_tile_window(dataset.raster_size(), request) else {
         return create_empty_tile(tile_size);
-    }
-
-    // Convert tile geographic bounds to pixel coordinates
-    // geo_x = -half_width + pixel_x * scale  =>  pixel_x = (geo_x + half_width) / scale
-    // geo_y = clamped_half_height - pixel_y * pixel_scale_y  =>  pixel_y = (clamped_half_height - geo_y) / pixel_scale_y
-    let pixel_scale_y = if half_height > 85.0 {
-        (clamped_half_height * 2.0) / img_height as f64
-    } else {
-        scale
     };
 
-    // Calculate full tile extent in source pixel coordinates (unclamped)
-    let tile_src_x_start = (tile_geo_bounds[0] + half_width) / scale;
-    let tile_src_y_start = (clamped_half_height - tile_geo_bounds[3]) / pixel_scale_y;
-    let tile_src_x_end = (tile_geo_bounds[2] + half_width) / scale;
-    let tile_src_y_end = (clamped_half_height - tile_geo_bounds[1]) / pixel_scale_y;
-
-    // Calculate the intersection with image bounds (clamped source coordinates)
-    let src_x_f = tile_src_x_start.max(0.0);
-    let src_y_f = tile_src_y_start.max(0.0);
-    let src_x_end_f = tile_src_x_end.min(img_width as f64);
-    let src_y_end_f = tile_src_y_end.min(img_height as f64);
-
-    // Integer source coordinates
-    let src_x = src_x_f.floor() as isize;
-    let src_y = src_y_f.floor() as isize;
-    let src_x_end = src_x_end_f.ceil() as isize;
-    let src_y_end = src_y_end_f.ceil() as isize;
-
-    let src_width = (src_x_end - src_x).max(0) as usize;
-    let src_height = (src_y_end - src_y).max(0) as usize;
-
-    if src_width == 0
-        || src_height == 0
-        || src_x >= img_width as isize
-        || src_y >= img_height as isize
-    {
-        return create_empty_tile(tile_size);
-    }
-
-    // Calculate where in the output tile the image data should be placed
-    // This is the key fix: partial tiles should only fill the corresponding portion
-    let tile_pixel_width = tile_src_x_end - tile_src_x_start;
-    let tile_pixel_height = tile_src_y_end - tile_src_y_start;
-
-    // Destination rectangle in tile coordinates (0 to tile_size)
-    let dst_x_start =
-        ((src_x_f - tile_src_x_start) / tile_pixel_width * tile_size as f64).round() as usize;
-    let dst_y_start =
-        ((src_y_f - tile_src_y_start) / tile_pixel_height * tile_size as f64).round() as usize;
-    let dst_x_end =
-        ((src_x_end_f - tile_src_x_start) / tile_pixel_width * tile_size as f64).round() as usize;
-    let dst_y_end =
-        ((src_y_end_f - tile_src_y_start) / tile_pixel_height * tile_size as f64).round() as usize;
-
-    let dst_width = (dst_x_end - dst_x_start)
-        .max(1)
-        .min(tile_size - dst_x_start);
-    let dst_height = (dst_y_end - dst_y_start)
-        .max(1)
-        .min(tile_size - dst_y_start);
-
     let band = dataset
         .rasterband(request.band as usize)
         .map_err(|e| format!("Failed to get band: {}", e))?;
 
-    let nodata = band.no_data_value();
+    // Paletted bands are drawn through their colour table unless the caller
+    // picked a colormap
+    let palette = colormap
+        .is_none()
+        .then(|| read_palette(&band).map(ColorRamp::Palette))
+        .flatten();
+    let colormap = colormap.or(palette.as_ref());
+    let request = &discrete_request(request, colormap);
 
     // Read source data and resample to the destination size (not full tile size)
-    let buffer = band
-        .read_as::<f64>(
-            (src_x, src_y),
-            (src_width, src_height),
-            (dst_width, dst_height),
-            None,
-        )
-        .map_err(|e| format!("Failed to read: {}", e))?;
+    let data = read_masked_part(
+        &band,
+        window.src_offset,
+        window.src_size,
+        window.dst_size,
+        Some(request.resampling.to_read_alg()),
+        request.complex,
+    )?;
 
-    let data = buffer.data();
     let mut tile_data = vec![0u8; tile_size * tile_size * 4];
 
     // Place the resampled data at the correct position in the output tile
+    let (dst_x, dst_y) = window.dst_offset;
+    let (dst_width, dst_height) = window.dst_size;
     for dy in 0..dst_height {
         for dx in 0..dst_width {
             let src_idx = dy * dst_width + dx;
-            let dst_tile_x = dst_x_start + dx;
-            let dst_tile_y = dst_y_start + dy;
-
-            if dst_tile_x >= tile_size || dst_tile_y >= tile_size {
-                continue;
-            }
-
-            let dst_idx = (dst_tile_y * tile_size + dst_tile_x) * 4;
+            let dst_idx = ((dst_y + dy) * tile_size + dst_x + dx) * 4;
             let val = data[src_idx];
 
-            if let Some(stretched) = apply_stretch(val, stretch, nodata) {
-                tile_data[dst_idx] = stretched;
-                tile_data[dst_idx + 1] = stretched;
-                tile_data[dst_idx + 2] = stretched;
-                tile_data[dst_idx + 3] = 255;
+            if let Some(rgba) = colorize(val, stretch, request.nodata, colormap) {
+                tile_data[dst_idx..dst_idx + 4].copy_from_slice(&rgba);
             }
         }
     }
 
-    encode_png(&tile_data, tile_size)
+    encode_tile(&tile_data, tile_size, request.format)
 }
 
 #[cfg(test)]
@@ -979,6 +1186,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tile_intersects_dataset_across_antimeridian() {
+        // Zoom 3 tile just east of the antimeridian (-180 to -135)
+        let tile = tile_to_geo_bounds(0, 4, 3);
+        let crossing = [170.0, -20.0, 190.0, -10.0];
+        assert!(tile_intersects_dataset(tile, crossing));
+
+        // Same dataset seen from the western side
+        let tile = tile_to_geo_bounds(7, 4, 3);
+        assert!(tile_intersects_dataset(tile, crossing));
+
+        // A dataset west of 180 only does not reach the eastern tile
+        let tile = tile_to_geo_bounds(0, 4, 3);
+        assert!(!tile_intersects_dataset(tile, [170.0, -20.0, 179.0, -10.0]));
+    }
+
     // ==================== Stretch Parameter Tests ====================
 
     #[test]
@@ -995,10 +1218,11 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 1.0,
+            ..Default::default()
         };
-        let result = apply_stretch(0.0, &stretch, None);
-        // 0.0 is treated as nodata/transparent
-        assert!(result.is_none(), "zero value should be treated as nodata");
+        let result = apply_stretch(0.0, &stretch, &NodataRules::default());
+        // 0.0 is real data unless the layer marks it as nodata
+        assert_eq!(result, Some(0), "zero value should map to 0");
     }
 
     #[test]
@@ -1007,8 +1231,9 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 1.0,
+            ..Default::default()
         };
-        let result = apply_stretch(100.0, &stretch, None);
+        let result = apply_stretch(100.0, &stretch, &NodataRules::default());
         assert_eq!(result, Some(255), "max value should map to 255");
     }
 
@@ -1018,12 +1243,16 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 1.0,
+            ..Default::default()
         };
-        let result = apply_stretch(50.0, &stretch, None);
+        let result = apply_stretch(50.0, &stretch, &NodataRules::default());
         // 50% of range = 127 or 128
         assert!(result.is_some());
         let val = result.unwrap();
-        assert!(val >= 127 && val <= 128, "mid value should map to ~127-128");
+        assert!(
+            (127..=128).contains(&val),
+            "mid value should map to ~127-128"
+        );
     }
 
     #[test]
@@ -1032,8 +1261,13 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 1.0,
+            ..Default::default()
+        };
+        let rules = NodataRules {
+            values: vec![-9999.0],
+            range: None,
         };
-        let result = apply_stretch(-9999.0, &stretch, Some(-9999.0));
+        let result = apply_stretch(-9999.0, &stretch, &rules);
         assert!(result.is_none(), "nodata value should return None");
     }
 
@@ -1043,8 +1277,9 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 0.5,
+            ..Default::default()
         };
-        let result = apply_stretch(25.0, &stretch, None);
+        let result = apply_stretch(25.0, &stretch, &NodataRules::default());
         // With gamma < 1, mid-tones should be brighter
         // 25/100 = 0.25, with gamma 0.5: 0.25^(1/0.5) = 0.25^2 = 0.0625
         // Wait, gamma correction: output = input^(1/gamma)
@@ -1061,8 +1296,9 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 2.0,
+            ..Default::default()
         };
-        let result = apply_stretch(25.0, &stretch, None);
+        let result = apply_stretch(25.0, &stretch, &NodataRules::default());
         // 0.25^(1/2) = 0.5, * 255 ≈ 127
         let val = result.unwrap();
         assert!(val > 100, "high gamma should make mid-tones brighter");
@@ -1074,8 +1310,9 @@ mod tests {
             min: 10.0,
             max: 100.0,
             gamma: 1.0,
+            ..Default::default()
         };
-        let result = apply_stretch(5.0, &stretch, None);
+        let result = apply_stretch(5.0, &stretch, &NodataRules::default());
         // Value below min should clamp to 0
         assert_eq!(result, Some(0), "value below min should clamp to 0");
     }
@@ -1086,26 +1323,210 @@ mod tests {
             min: 0.0,
             max: 100.0,
             gamma: 1.0,
+            ..Default::default()
         };
-        let result = apply_stretch(150.0, &stretch, None);
+        let result = apply_stretch(150.0, &stretch, &NodataRules::default());
         // Value above max should clamp to 255
         assert_eq!(result, Some(255), "value above max should clamp to 255");
     }
 
+    #[test]
+    fn test_apply_stretch_equalized() {
+        // Most values sit in the lowest quarter, which gets 3/4 of the levels
+        let lut = ContrastLut::from_histogram(&[60, 30, 0, 10], StretchMode::Equalize);
+        let stretch = StretchParams {
+            lut: lut.map(Arc::new),
+            ..StretchParams::new(0.0, 90.0, 1.0, StretchMode::Equalize)
+        };
+        let rules = NodataRules::default();
+        assert_eq!(apply_stretch(0.0, &stretch, &rules), Some(0));
+        assert_eq!(apply_stretch(45.0, &stretch, &rules), Some(191));
+        assert_eq!(apply_stretch(90.0, &stretch, &rules), Some(255));
+        assert_eq!(apply_stretch(200.0, &stretch, &rules), Some(255));
+    }
+
+    #[test]
+    fn test_encode_data_tile_layout() {
+        let rules = NodataRules {
+            values: vec![-9999.0],
+            range: None,
+        };
+        let bands = vec![vec![1.5, f64::NAN, -9999.0, 4.0], vec![f64::NAN; 4]];
+        let tile = encode_data_tile(&bands, 2, &rules);
+
+        assert_eq!(tile.len(), 12 + 2 * 4 * 4 + 4);
+        assert_eq!(&tile[0..4], b"HDT1");
+        let field = |i: usize| u16::from_le_bytes([tile[4 + 2 * i], tile[5 + 2 * i]]);
+        assert_eq!((field(0), field(1), field(2), field(3)), (2, 2, 2, 0));
+
+        let value = |i: usize| f32::from_le_bytes(tile[12 + 4 * i..16 + 4 * i].try_into().unwrap());
+        assert_eq!(value(0), 1.5);
+        assert!(value(1).is_nan());
+        assert!(value(2).is_nan(), "nodata rules apply");
+        assert_eq!(value(3), 4.0);
+        assert!(value(4).is_nan());
+
+        assert_eq!(&tile[44..], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_encode_empty_data_tile() {
+        let tile = encode_data_tile(&[Vec::new()], 0, &NodataRules::default());
+        assert_eq!(tile, [b'H', b'D', b'T', b'1', 0, 0, 0, 0, 1, 0, 0, 0]);
+    }
+
     #[test]
     fn test_apply_stretch_nan() {
         let stretch = StretchParams::default();
-        let result = apply_stretch(f64::NAN, &stretch, None);
+        let result = apply_stretch(f64::NAN, &stretch, &NodataRules::default());
         assert!(result.is_none(), "NaN should return None");
     }
 
     #[test]
     fn test_apply_stretch_infinity() {
         let stretch = StretchParams::default();
-        let result = apply_stretch(f64::INFINITY, &stretch, None);
+        let result = apply_stretch(f64::INFINITY, &stretch, &NodataRules::default());
         assert!(result.is_none(), "Infinity should return None");
     }
 
+    // ==================== Encoding Tests ====================
+
+    #[test]
+    fn test_encode_tile_formats() {
+        let opaque = [200u8, 100, 50, 255].repeat(16);
+        let png = encode_tile(&opaque, 4, TileFormat::Png).unwrap();
+        assert_eq!(&png[..4], b"\x89PNG");
+        let webp = encode_tile(&opaque, 4, TileFormat::Webp { quality: None }).unwrap();
+        assert_eq!((&webp[..4], &webp[8..12]), (&b"RIFF"[..], &b"WEBP"[..]));
+        assert_eq!(&webp[12..16], b"VP8L", "lossless");
+        let lossy = encode_tile(&opaque, 4, TileFormat::Webp { quality: Some(75) }).unwrap();
+        assert_eq!(&lossy[12..16], b"VP8 ");
+        let jpeg = encode_tile(&opaque, 4, TileFormat::Jpeg { quality: 80 }).unwrap();
+        assert_eq!(&jpeg[..2], &[0xFF, 0xD8]);
+    }
+
+    #[test]
+    fn test_encode_jpeg_falls_back_for_transparency() {
+        let mut edge = [200u8, 100, 50, 255].repeat(16);
+        edge[3] = 0;
+        let tile = encode_tile(&edge, 4, TileFormat::Jpeg { quality: 80 }).unwrap();
+        assert_eq!(&tile[8..12], b"WEBP");
+        // Lossy with an alpha chunk, in the extended format
+        assert_eq!(&tile[12..16], b"VP8X");
+    }
+
+    #[test]
+    fn test_tile_format_serde() {
+        let format: TileFormat = serde_json::from_str("{\"type\": \"jpeg\"}").unwrap();
+        assert_eq!(format, TileFormat::Jpeg { quality: 85 });
+        let format: TileFormat = serde_json::from_str("{\"type\": \"webp\"}").unwrap();
+        assert_eq!(format, TileFormat::Webp { quality: None });
+        let format: TileFormat =
+            serde_json::from_str("{\"type\": \"webp\", \"quality\": 60}").unwrap();
+        assert_eq!(format, TileFormat::Webp { quality: Some(60) });
+    }
+
+    // ==================== Colormap Tests ====================
+
+    #[test]
+    fn test_colorize_grayscale_without_colormap() {
+        let stretch = StretchParams::default();
+        assert_eq!(
+            colorize(255.0, &stretch, &NodataRules::default(), None),
+            Some([255, 255, 255, 255])
+        );
+    }
+
+    #[test]
+    fn test_colorize_palette_uses_raw_value() {
+        let palette = ColorRamp::Palette(vec![[0, 0, 0, 0], [10, 20, 30, 255], [1, 2, 3, 128]]);
+        // Stretch would map 2.0 to 2, but palette lookups ignore it
+        let stretch = StretchParams::default();
+        let rules = NodataRules {
+            values: vec![-9999.0],
+            range: None,
+        };
+        assert_eq!(
+            colorize(2.0, &stretch, &rules, Some(&palette)),
+            Some([1, 2, 3, 128])
+        );
+        assert_eq!(colorize(-9999.0, &stretch, &rules, Some(&palette)), None);
+    }
+
+    #[test]
+    fn test_discrete_request_forces_nearest() {
+        let request = TileRequest {
+            x: 0,
+            y: 0,
+            z: 0,
+            band: 1,
+            tile_size: 256,
+            resampling: Resampling::Cubic,
+            format: TileFormat::default(),
+            nodata: &NodataRules::default(),
+            complex: ComplexPart::default(),
+        };
+        let palette = ColorRamp::Palette(vec![[0, 0, 0, 255]]);
+        assert_eq!(
+            discrete_request(&request, Some(&palette)).resampling,
+            Resampling::Nearest
+        );
+        assert_eq!(
+            discrete_request(&request, None).resampling,
+            Resampling::Cubic
+        );
+    }
+
+    #[test]
+    fn test_colorize_valid_keeps_zero() {
+        // A flat slope of 0 is data even when the layer hides elevation 0
+        let stretch = StretchParams::default();
+        let rules = NodataRules {
+            values: vec![0.0],
+            range: None,
+        };
+        assert_eq!(colorize(0.0, &stretch, &rules, None), None);
+        assert_eq!(colorize_valid(0.0, &stretch, None), Some([0, 0, 0, 255]));
+    }
+
+    #[test]
+    fn test_shade_pixel_grey_and_colour_relief() {
+        let stretch = StretchParams::default();
+        assert_eq!(
+            shade_pixel(100.0, 0.5, &stretch, None),
+            Some([128, 128, 128, 255])
+        );
+
+        let palette = ColorRamp::Palette(vec![[0, 0, 0, 0], [200, 100, 50, 255]]);
+        assert_eq!(
+            shade_pixel(1.0, 0.5, &stretch, Some(&palette)),
+            Some([100, 50, 25, 255])
+        );
+    }
+
+    #[test]
+    fn test_mercator_row_cell_sizes() {
+        let request = TileRequest {
+            x: 0,
+            y: 0,
+            z: 0,
+            band: 1,
+            tile_size: 256,
+            resampling: Resampling::Nearest,
+            format: TileFormat::default(),
+            nodata: &NodataRules::default(),
+            complex: ComplexPart::default(),
+        };
+        let sizes = mercator_row_cell_sizes(&request);
+        let pixel_size = 20037508.342789244 * 2.0 / 256.0;
+
+        assert_eq!(sizes.len(), 256);
+        // Rows next to the equator are close to full size, polar rows shrink
+        assert!((sizes[128] / pixel_size - 1.0).abs() < 1e-3);
+        assert!(sizes[0] < pixel_size * 0.1);
+        assert!((sizes[0] - sizes[255]).abs() < 1e-6);
+    }
+
     // ==================== TileRequest Tests ====================
 
     #[test]
@@ -1116,10 +1537,132 @@ mod tests {
             z: 5,
             band: 1,
             tile_size: 256,
+            resampling: Resampling::Bilinear,
+            format: TileFormat::default(),
+            nodata: &NodataRules::default(),
+            complex: ComplexPart::default(),
         };
         let copy = req;
         assert_eq!(copy.x, 10);
         assert_eq!(copy.y, 20);
         assert_eq!(copy.z, 5);
+        assert_eq!(copy.resampling, Resampling::Bilinear);
+    }
+
+    #[test]
+    fn test_resampling_serde_names() {
+        let parsed: Resampling = serde_json::from_str("\"lanczos\"").unwrap();
+        assert_eq!(parsed, Resampling::Lanczos);
+        assert_eq!(
+            serde_json::to_string(&Resampling::Mode).unwrap(),
+            "\"mode\""
+        );
+        assert_eq!(Resampling::default(), Resampling::Nearest);
+    }
+
+    // ==================== Pixel Tile Tests ====================
+
+    /// A 2x2 image puts a sliver of one pixel in the bottom right corner of
+    /// tile 6/31/31, whose destination start rounds to the tile size
+    fn edge_tile_request(nodata: &NodataRules) -> TileRequest<'_> {
+        TileRequest {
+            x: 31,
+            y: 31,
+            z: 6,
+            band: 1,
+            tile_size: 256,
+            resampling: Resampling::Nearest,
+            format: TileFormat::Png,
+            nodata,
+            complex: ComplexPart::default(),
+        }
+    }
+
+    fn pixel_dataset(band_count: usize) -> Dataset {
+        let dataset = DriverManager::get_driver_by_name("MEM")
+            .unwrap()
+            .create_with_band_type::<u8, _>("", 2, 2, band_count)
+            .unwrap();
+        for band in 1..=band_count {
+            dataset.rasterband(band).unwrap().fill(200.0, None).unwrap();
+        }
+        dataset
+    }
+
+    #[test]
+    fn test_pixel_tile_window_edge_sliver() {
+        let nodata = NodataRules::default();
+        let window = pixel_tile_window((2, 2), &edge_tile_request(&nodata)).unwrap();
+        assert_eq!(window.src_offset, (0, 0));
+        assert_eq!(window.src_size, (1, 1));
+        assert_eq!(window.dst_offset, (255, 255));
+        assert_eq!(window.dst_size, (1, 1));
+    }
+
+    #[test]
+    fn test_pixel_tile_window_misses_image() {
+        let nodata = NodataRules::default();
+        let request = TileRequest {
+            x: 0,
+            ..edge_tile_request(&nodata)
+        };
+        assert!(pixel_tile_window((2, 2), &request).is_none());
+    }
+
+    #[test]
+    fn test_pixel_tile_window_inside_tile() {
+        let nodata = NodataRules::default();
+        let request = TileRequest {
+            x: 0,
+            y: 0,
+            z: 0,
+            ..edge_tile_request(&nodata)
+        };
+        let window = pixel_tile_window((1000, 500), &request).unwrap();
+        let (dst_x, dst_y) = window.dst_offset;
+        let (dst_width, dst_height) = window.dst_size;
+        assert_eq!(window.src_size, (1000, 500));
+        assert!(dst_x + dst_width <= 256 && dst_y + dst_height <= 256);
+    }
+
+    #[test]
+    fn test_pixel_tile_edge() {
+        let dataset = pixel_dataset(1);
+        let nodata = NodataRules::default();
+        let png = extract_pixel_tile(
+            &dataset,
+            &edge_tile_request(&nodata),
+            &StretchParams::default(),
+            None,
+        )
+        .unwrap();
+
+        let tile = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(tile.get_pixel(255, 255).0, [200, 200, 200, 255]);
+        assert_eq!(tile.get_pixel(254, 255).0[3], 0);
+        assert_eq!(tile.get_pixel(0, 0).0[3], 0);
+    }
+
+    #[test]
+    fn test_pixel_rgb_tile_edge() {
+        let dataset = pixel_dataset(3);
+        let nodata = NodataRules::default();
+        let stretch = StretchParams::default();
+        let png = extract_pixel_rgb_tile(
+            &dataset,
+            &edge_tile_request(&nodata),
+            1,
+            2,
+            3,
+            &stretch,
+            &stretch,
+            &stretch,
+        )
+        .unwrap();
+
+        let tile = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(tile.get_pixel(255, 255).0, [200, 200, 200, 255]);
+        assert_eq!(tile.get_pixel(255, 254).0[3], 0);
+        assert_eq!(tile.get_pixel(0, 0).0[3], 0);
     }
 }
@@ -0,0 +1,254 @@
+use crate::gdal::dataset_cache::pooled_overview;
+use gdal::raster::ColorInterpretation;
+use gdal::Dataset;
+use gdal_sys::{CPLErr, GDALResampleAlg};
+use std::ffi::CStr;
+use std::os::raw::c_int;
+use std::ptr;
+use std::rc::Rc;
+
+/// Read GDAL's last error message for FFI calls that only return a status
+pub fn last_gdal_error() -> String {
+    // SAFETY: CPLGetLastErrorMsg never returns null and the string stays valid
+    // until the next GDAL call on this thread; it is copied immediately.
+    unsafe { CStr::from_ptr(gdal_sys::CPLGetLastErrorMsg()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Overview to read for a warp at `target_ratio` source pixels per destination
+/// pixel, as gdalwarp's default `-ovr AUTO` picks it: the coarsest overview
+/// whose decimation factor does not exceed the target (within 0.1).
+///
+/// `ratios` are the overviews' decimation factors, finest first. `None` means
+/// the full-resolution image.
+pub fn choose_overview(ratios: &[f64], target_ratio: f64) -> Option<usize> {
+    ratios.iter().rposition(|&ratio| ratio < target_ratio + 0.1)
+}
+
+/// Decimation factor of each overview of `src`, finest first
+fn overview_ratios(src: &Dataset) -> Vec<f64> {
+    let Ok(band) = src.rasterband(1) else {
+        return Vec::new();
+    };
+    let count = band.overview_count().unwrap_or(0).max(0) as usize;
+    (0..count)
+        .map(|i| {
+            band.overview(i)
+                .map(|ovr| band.x_size() as f64 / ovr.x_size() as f64)
+        })
+        .collect::<Result<_, _>>()
+        .unwrap_or_default()
+}
+
+/// Source pixels per destination pixel across the middle of `dst`, along
+/// whichever axis is finer
+fn resolution_ratio(src: &Dataset, dst: &Dataset) -> Option<f64> {
+    let (width, height) = dst.raster_size();
+    let (w, h) = (width as f64, height as f64);
+
+    // Ends of the middle row, then of the middle column
+    let mut x = [0.0, w, w / 2.0, w / 2.0];
+    let mut y = [h / 2.0, h / 2.0, 0.0, h];
+    let mut z = [0.0; 4];
+    let mut success = [0 as c_int; 4];
+
+    // SAFETY: the transformer is destroyed before returning and the point
+    // arrays hold the 4 points passed
+    unsafe {
+        let transformer = gdal_sys::GDALCreateGenImgProjTransformer2(
+            src.c_dataset(),
+            dst.c_dataset(),
+            ptr::null_mut(),
+        );
+        if transformer.is_null() {
+            return None;
+        }
+        let ok = gdal_sys::GDALGenImgProjTransform(
+            transformer,
+            1,
+            4,
+            x.as_mut_ptr(),
+            y.as_mut_ptr(),
+            z.as_mut_ptr(),
+            success.as_mut_ptr(),
+        );
+        gdal_sys::GDALDestroyGenImgProjTransformer(transformer);
+        if ok == 0 || success.contains(&0) {
+            return None;
+        }
+    }
+
+    let across = (x[1] - x[0]).hypot(y[1] - y[0]) / w;
+    let down = (x[3] - x[2]).hypot(y[3] - y[2]) / h;
+    Some(across.min(down))
+}
+
+/// The overview of `src` matching the resolution of `dst`, if one is coarse
+/// enough to help.
+///
+/// The warper itself always reads full-resolution pixels, so the overview is
+/// a dataset of its own, kept in the thread's dataset pool next to `src`.
+/// Sources outside the pool (in-memory datasets) are warped at full
+/// resolution.
+fn matching_overview(src: &Dataset, dst: &Dataset) -> Result<Option<Rc<Dataset>>, String> {
+    let ratios = overview_ratios(src);
+    let level = match resolution_ratio(src, dst) {
+        Some(ratio) if !ratios.is_empty() => choose_overview(&ratios, ratio),
+        _ => None,
+    };
+    match level {
+        Some(level) => pooled_overview(src, level),
+        None => Ok(None),
+    }
+}
+
+/// Warp a subset of source bands into the destination dataset.
+///
+/// `src_bands` lists 1-based source band indices; source band `src_bands[i]`
+/// is written to destination band `i + 1`. Only these bands are read and
+/// resampled, so an RGB tile from a 13-band stack costs a single 3-band warp
+/// instead of warping the whole stack. `resample_alg` selects the warp kernel.
+///
+/// Pixels the source marks invalid (nodata, per-dataset mask or alpha band)
+/// are skipped. If `dst` has one band more than requested, that last band
+/// becomes a destination alpha: 0 wherever no valid source pixel landed.
+///
+/// When the destination is coarser than the source, pixels are read from the
+/// matching overview instead of the full-resolution image.
+///
+/// `gdal::raster::reproject` always maps every source band, which is why this
+/// goes through the warp API directly.
+pub fn warp_bands(
+    src: &Dataset,
+    dst: &Dataset,
+    src_bands: &[usize],
+    resample_alg: GDALResampleAlg::Type,
+) -> Result<(), String> {
+    if src_bands.is_empty() {
+        return Err("No bands requested".to_string());
+    }
+    let dst_alpha = dst.raster_count() == src_bands.len() + 1;
+    if src_bands.len() > dst.raster_count() {
+        return Err(format!(
+            "Output dataset has {} bands, {} requested",
+            dst.raster_count(),
+            src_bands.len()
+        ));
+    }
+
+    let overview = matching_overview(src, dst)?;
+    let src = overview.as_deref().unwrap_or(src);
+
+    // Source nodata per requested band, so nodata pixels are skipped instead
+    // of being blended into their neighbours
+    let mut nodata = Vec::with_capacity(src_bands.len());
+    for &band_index in src_bands {
+        let band = src
+            .rasterband(band_index)
+            .map_err(|e| format!("Failed to get band {}: {}", band_index, e))?;
+        nodata.push(band.no_data_value());
+    }
+
+    // The warper only honours a source alpha band when told which one it is;
+    // per-dataset mask bands are picked up on its own
+    let src_alpha = (1..=src.raster_count()).find(|&i| {
+        src.rasterband(i)
+            .is_ok_and(|band| band.color_interpretation() == ColorInterpretation::AlphaBand)
+    });
+
+    let (width, height) = dst.raster_size();
+
+    // SAFETY: every handle created here is destroyed before returning. The
+    // band and nodata arrays are allocated by GDAL (GDALWarpInitDefaultBandMapping
+    // and GDALWarpInitSrcNoDataReal) and released by GDALDestroyWarpOptions.
+    // Both datasets outlive the warp operation.
+    unsafe {
+        let transformer = gdal_sys::GDALCreateGenImgProjTransformer2(
+            src.c_dataset(),
+            dst.c_dataset(),
+            ptr::null_mut(),
+        );
+        if transformer.is_null() {
+            return Err(format!(
+                "Failed to create transformer: {}",
+                last_gdal_error()
+            ));
+        }
+
+        let options = gdal_sys::GDALCreateWarpOptions();
+        (*options).hSrcDS = src.c_dataset();
+        (*options).hDstDS = dst.c_dataset();
+        (*options).eResampleAlg = resample_alg;
+        (*options).pfnTransformer = Some(gdal_sys::GDALGenImgProjTransform);
+        (*options).pTransformerArg = transformer;
+        if let Some(alpha) = src_alpha {
+            (*options).nSrcAlphaBand = alpha as c_int;
+        }
+        if dst_alpha {
+            (*options).nDstAlphaBand = (src_bands.len() + 1) as c_int;
+        }
+
+        gdal_sys::GDALWarpInitDefaultBandMapping(options, src_bands.len() as c_int);
+        for (i, &band_index) in src_bands.iter().enumerate() {
+            *(*options).panSrcBands.add(i) = band_index as c_int;
+        }
+
+        if nodata.iter().any(Option::is_some) {
+            // Bands without nodata get a sentinel that never matches real data
+            gdal_sys::GDALWarpInitSrcNoDataReal(options, -1.1e20);
+            for (i, value) in nodata.iter().enumerate() {
+                if let Some(value) = value {
+                    *(*options).padfSrcNoDataReal.add(i) = *value;
+                }
+            }
+        }
+
+        let operation = gdal_sys::GDALCreateWarpOperation(options);
+        let result = if operation.is_null() {
+            Err(format!(
+                "Failed to create warp operation: {}",
+                last_gdal_error()
+            ))
+        } else {
+            let err =
+                gdal_sys::GDALChunkAndWarpImage(operation, 0, 0, width as c_int, height as c_int);
+            gdal_sys::GDALDestroyWarpOperation(operation);
+            if err == CPLErr::CE_None {
+                Ok(())
+            } else {
+                Err(format!("Failed to reproject: {}", last_gdal_error()))
+            }
+        };
+
+        gdal_sys::GDALDestroyWarpOptions(options);
+        gdal_sys::GDALDestroyGenImgProjTransformer(transformer);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATIOS: [f64; 6] = [2.0, 4.0, 8.0, 16.0, 32.0, 64.0];
+
+    #[test]
+    fn test_choose_overview_low_zoom() {
+        // A 10 m image drawn on a zoom 8 tile (~611 m per pixel at the
+        // equator) reads the 32x overview rather than every source pixel
+        assert_eq!(choose_overview(&RATIOS, 611.5 / 10.0), Some(4));
+        // Far zoomed out, the coarsest overview
+        assert_eq!(choose_overview(&RATIOS, 1000.0), Some(5));
+    }
+
+    #[test]
+    fn test_choose_overview_full_resolution() {
+        assert_eq!(choose_overview(&RATIOS, 1.0), None);
+        assert_eq!(choose_overview(&RATIOS, 1.9), None);
+        assert_eq!(choose_overview(&[], 50.0), None);
+        // An overview within 0.1 of the target counts as a match
+        assert_eq!(choose_overview(&RATIOS, 3.95), Some(1));
+    }
+}
@@ -7,17 +7,23 @@ mod gdal;
 use commands::app::{get_version, read_config, write_config};
 use commands::georef::{apply_georeference, calculate_transformation};
 use commands::raster::{
-    close_dataset, get_cross_layer_pixel_rgb_tile, get_cross_layer_rgb_tile, get_elevation_profile,
-    get_elevation_profile_pixels, get_histogram, get_pixel_rgb_tile, get_pixel_tile,
-    get_raster_stats, get_rgb_tile, get_tile, get_tile_stretched, open_raster, query_pixel_value,
-    query_pixel_value_at_pixel,
+    build_overviews, cancel_overviews, close_dataset, compute_auto_stretch, create_band_math_layer,
+    export_raster, get_cross_layer_pixel_rgb_tile, get_cross_layer_rgb_tile, get_data_tile,
+    get_elevation_profile, get_elevation_profile_pixels, get_histogram, get_histograms,
+    get_pixel_data_tile, get_pixel_rgb_tile, get_pixel_tile, get_raster_stats, get_rgb_tile,
+    get_tile, get_tile_cache_stats, get_tile_stretched, list_subdatasets, open_raster,
+    query_pixel_value, query_pixel_value_at_pixel,
 };
 use commands::stac::{
     browse_static_collection, connect_stac_api, fetch_stac_resource, fetch_stac_thumbnail,
     get_static_catalog_children, list_stac_collections, open_stac_asset, search_stac_items,
 };
 use commands::vector::open_vector;
+use gdal::band_math::remove_stale_outputs;
+use gdal::contrast::LutCache;
 use gdal::dataset_cache::DatasetCache;
+use gdal::overviews::OverviewJobs;
+use gdal::tile_cache::{TileCache, DEFAULT_TILE_CACHE_BYTES};
 
 /// Initialize GDAL configuration for remote file access via /vsicurl/
 fn init_gdal_for_remote_access() {
@@ -114,16 +120,24 @@ fn main() {
     // This must be done before any GDAL operations
     init_gdal_for_remote_access();
 
+    // Band-math results of layers open when an earlier run exited were still
+    // held open then; delete them now that nothing uses them
+    remove_stale_outputs();
+
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_fs::init())
         .manage(DatasetCache::new(10))
+        .manage(TileCache::new(DEFAULT_TILE_CACHE_BYTES))
+        .manage(LutCache::default())
+        .manage(OverviewJobs::default())
         .invoke_handler(tauri::generate_handler![
             get_version,
             read_config,
             write_config,
             open_raster,
+            list_subdatasets,
             get_tile,
             get_tile_stretched,
             get_rgb_tile,
@@ -131,14 +145,23 @@ fn main() {
             get_cross_layer_pixel_rgb_tile,
             get_pixel_tile,
             get_pixel_rgb_tile,
+            get_data_tile,
+            get_pixel_data_tile,
             get_raster_stats,
             get_histogram,
+            get_histograms,
+            compute_auto_stretch,
             close_dataset,
+            get_tile_cache_stats,
             open_vector,
             query_pixel_value,
             query_pixel_value_at_pixel,
             get_elevation_profile,
             get_elevation_profile_pixels,
+            create_band_math_layer,
+            export_raster,
+            build_overviews,
+            cancel_overviews,
             // STAC commands
             connect_stac_api,
             list_stac_collections,
@@ -0,0 +1,42 @@
+/**
+ * Tests for complex (SAR) band display helpers
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  COMPLEX_PARTS,
+  PHASE_RANGE,
+  formatComplex,
+  phaseDegrees,
+} from '../layer-manager/complex.js';
+
+describe('formatComplex', () => {
+  it('writes the sign of the imaginary part between the parts', () => {
+    expect(formatComplex({ real: 3, imaginary: 4, amplitude: 5, phase: 0.93 })).toBe('3 + 4i');
+    expect(formatComplex({ real: 3, imaginary: -4, amplitude: 5, phase: -0.93 })).toBe('3 - 4i');
+  });
+
+  it('formats each part with the given formatter', () => {
+    const value = { real: 0.5, imaginary: -0.25, amplitude: 0.56, phase: -0.46 };
+    expect(formatComplex(value, n => n.toFixed(1))).toBe('0.5 - 0.3i');
+  });
+});
+
+describe('phaseDegrees', () => {
+  it('converts radians to degrees', () => {
+    expect(phaseDegrees(Math.PI / 2)).toBe('90.0°');
+    expect(phaseDegrees(-Math.PI)).toBe('-180.0°');
+  });
+});
+
+describe('complex parts', () => {
+  it('offers every part once, amplitude first', () => {
+    const values = COMPLEX_PARTS.map(p => p.value);
+    expect(values[0]).toBe('amplitude');
+    expect(new Set(values).size).toBe(6);
+  });
+
+  it('stretches phase over the full circle', () => {
+    expect(PHASE_RANGE).toEqual([-Math.PI, Math.PI]);
+  });
+});
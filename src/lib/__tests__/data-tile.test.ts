@@ -0,0 +1,71 @@
+/**
+ * Tests for data tile decoding and CPU stretching
+ */
+
+import { describe, it, expect } from 'vitest';
+import { decodeDataTile, stretchDataTile } from '../layer-manager/data-tile.js';
+
+/** Encode a tile the way the backend's encode_data_tile does */
+function encode(width: number, height: number, bands: number[][], mask: number[]): ArrayBuffer {
+  const pixels = width * height;
+  const buffer = new ArrayBuffer(12 + bands.length * pixels * 4 + pixels);
+  const view = new DataView(buffer);
+  'HDT1'.split('').forEach((c, i) => view.setUint8(i, c.charCodeAt(0)));
+  view.setUint16(4, width, true);
+  view.setUint16(6, height, true);
+  view.setUint16(8, bands.length, true);
+  bands.forEach((values, b) => {
+    values.forEach((v, i) => view.setFloat32(12 + (b * pixels + i) * 4, v, true));
+  });
+  mask.forEach((m, i) => view.setUint8(12 + bands.length * pixels * 4 + i, m));
+  return buffer;
+}
+
+describe('decodeDataTile', () => {
+  it('decodes bands and mask', () => {
+    const buffer = encode(2, 1, [[1.5, NaN]], [255, 0]);
+    const tile = decodeDataTile(buffer)!;
+
+    expect(tile.width).toBe(2);
+    expect(tile.height).toBe(1);
+    expect(tile.bands).toHaveLength(1);
+    expect(tile.bands[0][0]).toBe(1.5);
+    expect(Number.isNaN(tile.bands[0][1])).toBe(true);
+    expect(Array.from(tile.mask)).toEqual([255, 0]);
+  });
+
+  it('returns null for empty tiles', () => {
+    expect(decodeDataTile(encode(0, 0, [], []))).toBeNull();
+  });
+
+  it('rejects other payloads', () => {
+    expect(() => decodeDataTile(new Uint8Array([137, 80, 78, 71]).buffer)).toThrow();
+    const png = new Uint8Array(16);
+    png.set([137, 80, 78, 71]);
+    expect(() => decodeDataTile(png.buffer)).toThrow('bad magic');
+  });
+});
+
+describe('stretchDataTile', () => {
+  it('stretches a single band as grayscale', () => {
+    const tile = decodeDataTile(encode(3, 1, [[0, 50, 200]], [255, 255, 0]))!;
+    const rgba = stretchDataTile(tile, [{ min: 0, max: 100, gamma: 1 }]);
+
+    expect(Array.from(rgba.slice(0, 4))).toEqual([0, 0, 0, 255]);
+    expect(Array.from(rgba.slice(4, 8))).toEqual([127, 127, 127, 255]);
+    // Masked pixels are transparent
+    expect(Array.from(rgba.slice(8, 12))).toEqual([0, 0, 0, 0]);
+  });
+
+  it('stretches each RGB channel separately', () => {
+    const tile = decodeDataTile(encode(1, 1, [[10], [NaN], [1000]], [255]))!;
+    const rgba = stretchDataTile(tile, [
+      { min: 0, max: 10, gamma: 1 },
+      { min: 0, max: 10, gamma: 1 },
+      { min: 0, max: 4000, gamma: 2 },
+    ]);
+
+    // Invalid channel values render black; gamma 2 lifts 0.25 to 0.5
+    expect(Array.from(rgba)).toEqual([255, 0, 127, 255]);
+  });
+});
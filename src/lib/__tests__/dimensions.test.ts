@@ -0,0 +1,86 @@
+/**
+ * Tests for multidimensional raster slicing
+ */
+
+import { describe, it, expect } from 'vitest';
+import {
+  dimensionBand,
+  dimensionLabel,
+  dimensionSlice,
+  rgbDimensionSlice,
+  rgbSourceBand,
+  type DimensionedLayer,
+} from '../layer-manager/dimensions.js';
+import type { RasterDimension } from '../layer-manager/types.js';
+
+const TIME: RasterDimension = {
+  name: 'time',
+  values: [0, 31, 59],
+  labels: ['2000-01-01', '2000-02-01', '2000-03-01'],
+  units: 'days since 2000-01-01',
+};
+
+const DEPTH: RasterDimension = {
+  name: 'depth',
+  values: [5, 10],
+  labels: ['5', '10'],
+  units: 'm',
+};
+
+describe('dimensionSlice', () => {
+  it('selects index 0 of unset dimensions', () => {
+    const layer: DimensionedLayer = { dimensions: [TIME, DEPTH], dimensionIndices: { depth: 1 } };
+    expect(dimensionSlice(layer)).toEqual({ time: { index: 0 }, depth: { index: 1 } });
+  });
+
+  it('is undefined for plain rasters', () => {
+    expect(dimensionSlice({})).toBeUndefined();
+    expect(dimensionSlice({ dimensions: [] })).toBeUndefined();
+  });
+});
+
+describe('rgbDimensionSlice', () => {
+  it('leaves the last dimension free for the RGB bands', () => {
+    const layer: DimensionedLayer = { dimensions: [TIME, DEPTH], dimensionIndices: { time: 2 } };
+    expect(rgbDimensionSlice(layer)).toEqual({ time: { index: 2 } });
+    expect(rgbDimensionSlice({ dimensions: [DEPTH] })).toEqual({});
+    expect(rgbDimensionSlice({})).toBeUndefined();
+  });
+});
+
+describe('rgbSourceBand', () => {
+  it('counts RGB bands along the last dimension', () => {
+    const layer: DimensionedLayer = { dimensions: [TIME, DEPTH], dimensionIndices: { time: 2 } };
+    expect(rgbSourceBand(layer, 1)).toBe(5);
+    expect(rgbSourceBand(layer, 2)).toBe(6);
+    expect(rgbSourceBand({}, 3)).toBe(3);
+  });
+});
+
+describe('dimensionBand', () => {
+  it('counts bands row-major, last dimension fastest', () => {
+    const layer: DimensionedLayer = { dimensions: [TIME, DEPTH] };
+    expect(dimensionBand(layer)).toBe(1);
+
+    layer.dimensionIndices = { time: 2, depth: 1 };
+    expect(dimensionBand(layer)).toBe(6);
+    layer.dimensionIndices = { time: 1 };
+    expect(dimensionBand(layer)).toBe(3);
+  });
+
+  it('clamps indices to the dimension', () => {
+    expect(dimensionBand({ dimensions: [TIME], dimensionIndices: { time: 9 } })).toBe(3);
+  });
+});
+
+describe('dimensionLabel', () => {
+  it('shows dates without units', () => {
+    const layer = { dimensions: [TIME], dimensionIndices: { time: 1 } };
+    expect(dimensionLabel(layer, TIME)).toBe('2000-02-01');
+  });
+
+  it('appends units to values', () => {
+    const layer = { dimensions: [DEPTH], dimensionIndices: { depth: 1 } };
+    expect(dimensionLabel(layer, DEPTH)).toBe('10 m');
+  });
+});
@@ -3,6 +3,8 @@ import {
   getFeatureBounds,
   mergeBounds,
   boundsIntersect,
+  crossesAntimeridian,
+  sourceBounds,
   buildCategoricalColorExpression,
   buildGraduatedColorExpression,
   isNumericArray,
@@ -259,6 +261,18 @@ describe('boundsIntersect', () => {
   });
 });
 
+describe('sourceBounds', () => {
+  it('keeps bounds within [-180, 180]', () => {
+    expect(crossesAntimeridian([-180, -90, 180, 90])).toBe(false);
+    expect(sourceBounds([10, 40, 20, 50])).toEqual({ bounds: [10, 40, 20, 50] });
+  });
+
+  it('omits bounds crossing the antimeridian', () => {
+    expect(crossesAntimeridian([170, -20, 190, -10])).toBe(true);
+    expect(sourceBounds([170, -20, 190, -10])).toEqual({});
+  });
+});
+
 describe('buildCategoricalColorExpression', () => {
   it('builds match expression with values', () => {
     const expr = buildCategoricalColorExpression('status', ['active', 'inactive']);
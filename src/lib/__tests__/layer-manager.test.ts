@@ -9,6 +9,11 @@ vi.mock('@tauri-apps/api/core', () => ({
   invoke: vi.fn(),
 }));
 
+// Mock Tauri events
+vi.mock('@tauri-apps/api/event', () => ({
+  listen: vi.fn(() => Promise.resolve(() => {})),
+}));
+
 // Mock maplibre-gl
 vi.mock('maplibre-gl', () => ({
   default: {
@@ -79,6 +84,7 @@ function createMockMapManager() {
 
 // Import after mocks are set up
 import { LayerManager } from '../layer-manager/index.js';
+import { formatBandStats } from '../layer-manager/histogram.js';
 import { invoke } from '@tauri-apps/api/core';
 
 describe('LayerManager', () => {
@@ -495,6 +501,170 @@ describe('LayerManager', () => {
       await expect(layerManager.removeLayer('non-existent')).resolves.not.toThrow();
     });
   });
+
+  describe('buildLayerOverviews', () => {
+    beforeEach(() => {
+      layerManager.layers.set('big', {
+        id: 'big',
+        type: 'raster',
+        path: '/path/to/big.tif',
+        visible: true,
+        overview_count: 0,
+        needs_overviews: true,
+      });
+      vi.spyOn(layerManager, 'refreshLayerTiles').mockImplementation(() => {});
+      vi.spyOn(layerManager, 'updateDynamicControls').mockImplementation(() => {});
+    });
+
+    it('should record the built levels and refresh tiles', async () => {
+      invoke.mockResolvedValue(5);
+      await layerManager.buildLayerOverviews('big', 'internal', 'mode');
+
+      expect(invoke).toHaveBeenCalledWith('build_overviews', {
+        id: 'big',
+        location: 'internal',
+        resampling: 'mode',
+      });
+      const layer = layerManager.layers.get('big');
+      expect(layer.overview_count).toBe(5);
+      expect(layer.needs_overviews).toBe(false);
+      expect(layer.buildingOverviews).toBe(false);
+      expect(layerManager.refreshLayerTiles).toHaveBeenCalledWith('big');
+    });
+
+    it('should leave the layer unchanged when cancelled', async () => {
+      invoke.mockRejectedValue('Overview build cancelled');
+      await layerManager.buildLayerOverviews('big', 'external', 'average');
+
+      const layer = layerManager.layers.get('big');
+      expect(layer.needs_overviews).toBe(true);
+      expect(layer.buildingOverviews).toBe(false);
+      expect(layerManager.refreshLayerTiles).not.toHaveBeenCalled();
+    });
+
+    it('should only cancel a running build', async () => {
+      await layerManager.cancelLayerOverviews('big');
+      expect(invoke).not.toHaveBeenCalled();
+
+      layerManager.layers.get('big').buildingOverviews = true;
+      await layerManager.cancelLayerOverviews('big');
+      expect(invoke).toHaveBeenCalledWith('cancel_overviews', { id: 'big' });
+    });
+  });
+
+  describe('computeBandStatistics', () => {
+    const exact = {
+      band: 2,
+      min: 1,
+      max: 250,
+      mean: 97.5,
+      std_dev: 12.25,
+      valid_count: 750,
+      nodata_percent: 25,
+      approximate: false,
+    };
+
+    beforeEach(() => {
+      layerManager.layers.set('dem', {
+        id: 'dem',
+        type: 'raster',
+        path: '/path/to/dem.tif',
+        visible: true,
+        band_stats: [
+          { min: 0, max: 1, mean: 0.5, std_dev: 0.1 },
+          { min: 0, max: 1, mean: 0.5, std_dev: 0.1 },
+        ],
+      });
+      vi.spyOn(layerManager, 'updateDynamicControls').mockImplementation(() => {});
+    });
+
+    it('should replace the sampled stats of the band', async () => {
+      invoke.mockResolvedValue(exact);
+      const stats = await layerManager.computeBandStatistics('dem', 2, 'exact');
+
+      expect(invoke).toHaveBeenCalledWith('get_raster_stats', {
+        id: 'dem',
+        band: 2,
+        accuracy: 'exact',
+      });
+      expect(stats).toEqual(exact);
+      expect(layerManager.layers.get('dem').band_stats[1]).toEqual(exact);
+      expect(layerManager.layers.get('dem').band_stats[0].max).toBe(1);
+    });
+
+    it('should return null when GDAL fails', async () => {
+      invoke.mockRejectedValue('Failed to compute statistics');
+      expect(await layerManager.computeBandStatistics('dem', 1, 'exact')).toBeNull();
+      expect(layerManager.layers.get('dem').band_stats[0].max).toBe(1);
+    });
+
+    it('should summarise stats with their valid pixel count', () => {
+      expect(formatBandStats(exact)).toBe('Mean: 97.50  σ: 12.25  750 valid (25.0% nodata)');
+      expect(formatBandStats({ ...exact, approximate: true })).toContain('approx.');
+      expect(formatBandStats({ ...exact, valid_count: null })).toBe(
+        'Mean: 97.50  σ: 12.25 (sampled)'
+      );
+      expect(formatBandStats(undefined)).toBe('');
+    });
+  });
+
+  describe('showHistogram', () => {
+    const histogram = {
+      band: 1,
+      min: 0,
+      max: 1,
+      bin_count: 2,
+      counts: [3, 4],
+      bin_edges: [0, 0.5, 1],
+    };
+
+    beforeEach(() => {
+      document.body.insertAdjacentHTML(
+        'beforeend',
+        `<div id="histogram-panel">
+          <canvas id="histogram-canvas"></canvas>
+          <input type="checkbox" id="histogram-viewport">
+        </div>`
+      );
+      layerManager.layers.set('scene', {
+        id: 'scene',
+        type: 'raster',
+        path: '/path/to/scene.tif',
+        visible: true,
+        bands: 1,
+        band_stats: [{ min: 0, max: 1 }],
+        is_georeferenced: true,
+      });
+      mockMapManager.map.getBounds = vi.fn(() => ({
+        getWest: () => 10,
+        getSouth: () => 45,
+        getEast: () => 11,
+        getNorth: () => 46,
+      }));
+      invoke.mockResolvedValue(histogram);
+    });
+
+    afterEach(() => {
+      document.getElementById('histogram-panel')?.remove();
+    });
+
+    it('should sample the whole layer by default', async () => {
+      await layerManager.showHistogram('scene', 1);
+      expect(invoke).toHaveBeenCalledWith(
+        'get_histogram',
+        expect.objectContaining({ id: 'scene', band: 1, bbox: undefined })
+      );
+    });
+
+    it('should restrict the histogram to the view when asked', async () => {
+      document.getElementById('histogram-viewport').checked = true;
+      await layerManager.showHistogram('scene', 1);
+      expect(invoke).toHaveBeenCalledWith(
+        'get_histogram',
+        expect.objectContaining({ bbox: [10, 45, 11, 46] })
+      );
+    });
+  });
 });
 
 describe('LayerManager - Layer Panel', () => {
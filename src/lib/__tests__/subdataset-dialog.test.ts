@@ -0,0 +1,96 @@
+/**
+ * Tests for the subdataset chooser
+ */
+
+import { describe, it, expect, afterEach } from 'vitest';
+import {
+  chooseSubdatasets,
+  describeSubdataset,
+  type SubdatasetInfo,
+} from '../layer-manager/subdataset-dialog.js';
+
+const SUBDATASETS: SubdatasetInfo[] = [
+  {
+    name: 'NETCDF:"/data/ocean.nc":sst',
+    description: '[12x720x1440] sst (32-bit floating-point)',
+    label: 'sst',
+    dimensions: [12, 720, 1440],
+    data_type: '32-bit floating-point',
+  },
+  {
+    name: 'NETCDF:"/data/ocean.nc":<b>mask</b>',
+    description: 'mask',
+    label: '<b>mask</b>',
+    dimensions: [],
+    data_type: null,
+  },
+];
+
+function checkboxes(): HTMLInputElement[] {
+  return Array.from(document.querySelectorAll('#subdataset-dialog input[type="checkbox"]'));
+}
+
+function click(action: string): void {
+  const selector = `#subdataset-dialog [data-action="${action}"]`;
+  document.querySelector<HTMLButtonElement>(selector)!.click();
+}
+
+describe('describeSubdataset', () => {
+  it('shows dimensions and data type', () => {
+    expect(describeSubdataset(SUBDATASETS[0])).toBe('12 × 720 × 1440, 32-bit floating-point');
+  });
+
+  it('falls back to the description', () => {
+    expect(describeSubdataset(SUBDATASETS[1])).toBe('mask');
+  });
+});
+
+describe('chooseSubdatasets', () => {
+  afterEach(() => {
+    document.body.innerHTML = '';
+  });
+
+  it('lists subdatasets with the first one selected', () => {
+    chooseSubdatasets('ocean.nc', SUBDATASETS);
+
+    expect(checkboxes().map(c => c.checked)).toEqual([true, false]);
+    // Labels are text, not markup
+    const labels = document.querySelectorAll('.subdataset-label');
+    expect(labels[1].textContent).toBe('<b>mask</b>');
+    expect(labels[1].querySelector('b')).toBeNull();
+  });
+
+  it('resolves with the checked subdatasets', async () => {
+    const result = chooseSubdatasets('ocean.nc', SUBDATASETS);
+    checkboxes()[1].checked = true;
+    click('open');
+
+    expect(await result).toEqual(SUBDATASETS);
+    expect(document.getElementById('subdataset-dialog')).toBeNull();
+  });
+
+  it('disables Open when nothing is checked', () => {
+    chooseSubdatasets('ocean.nc', SUBDATASETS);
+    const first = checkboxes()[0];
+    first.checked = false;
+    first.dispatchEvent(new Event('change'));
+
+    const openBtn = document.querySelector<HTMLButtonElement>('[data-action="open"]')!;
+    expect(openBtn.disabled).toBe(true);
+  });
+
+  it('resolves empty when cancelled', async () => {
+    const result = chooseSubdatasets('ocean.nc', SUBDATASETS);
+    click('cancel');
+
+    expect(await result).toEqual([]);
+    expect(document.getElementById('subdataset-dialog')).toBeNull();
+  });
+
+  it('resolves empty on Escape', async () => {
+    const result = chooseSubdatasets('ocean.nc', SUBDATASETS);
+    document.dispatchEvent(new KeyboardEvent('keydown', { key: 'Escape' }));
+
+    expect(await result).toEqual([]);
+  });
+});
@@ -44,8 +44,14 @@ export const RASTER_EXTENSIONS = [
   'asc',
   'dem',
   'nc',
+  'nc4',
   'hdf',
   'h5',
+  'he5',
+  'grib',
+  'grib2',
+  'grb',
+  'grb2',
 ];
 
 /** All supported geospatial file extensions */
@@ -108,6 +108,27 @@ export function boundsIntersect(a: FlatBounds, b: FlatBounds): boolean {
   return !(a[2] < b[0] || a[0] > b[2] || a[3] < b[1] || a[1] > b[3]);
 }
 
+/**
+ * Check if bounds cross the antimeridian, which the backend reports as an
+ * east edge above 180
+ * @param bounds - Bounds [west, south, east, north]
+ * @returns True if bounds cross the antimeridian
+ */
+export function crossesAntimeridian(bounds: FlatBounds): boolean {
+  return bounds[2] > 180;
+}
+
+/**
+ * Bounds option for a raster source. MapLibre clamps source bounds to
+ * [-180, 180], dropping the part of a dataset past the antimeridian, so such
+ * sources go without and the backend returns empty tiles outside the data.
+ * @param bounds - Bounds [west, south, east, north]
+ * @returns Object to spread into the source specification
+ */
+export function sourceBounds(bounds: FlatBounds): { bounds?: FlatBounds } {
+  return crossesAntimeridian(bounds) ? {} : { bounds };
+}
+
 /**
  * Build categorical color expression for MapLibre
  * @param fieldName - Field name to color by
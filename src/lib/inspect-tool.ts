@@ -15,6 +15,9 @@ import maplibregl, {
   type Popup,
   type LngLat,
 } from 'maplibre-gl';
+import { dimensionSlice } from './layer-manager/dimensions';
+import { formatComplex, phaseDegrees, type ComplexValue } from './layer-manager/complex';
+import type { RasterDimension } from './layer-manager/types';
 
 /**
  * Pixel extent information for non-georeferenced images
@@ -56,6 +59,16 @@ interface RasterLayer {
   path: string;
   /** Optional display name override */
   displayName?: string;
+  /** Current band for grayscale display (1-indexed) */
+  band?: number;
+  /** Derived terrain value shown instead of the elevation, if any */
+  terrainDerivative?: string;
+  /** Hillshade settings, if the layer is shaded as a DEM */
+  hillshade?: object;
+  /** Time/depth/level axes of multidimensional rasters */
+  dimensions?: RasterDimension[];
+  /** Selected index of each dimension, by name */
+  dimensionIndices?: Record<string, number>;
 }
 
 /**
@@ -78,6 +91,10 @@ interface BandValue {
   value: number;
   /** Whether this is a NoData value */
   is_nodata: boolean;
+  /** Class colour (RGBA) for paletted bands */
+  color?: [number, number, number, number] | null;
+  /** Parts of complex (SAR) pixels; `value` is then the amplitude */
+  complex?: ComplexValue | null;
 }
 
 /**
@@ -92,6 +109,22 @@ interface PixelQueryResult {
   is_valid: boolean;
   /** Values for each band at this location */
   values: BandValue[];
+  /** Slope and aspect, for layers displayed as terrain */
+  terrain?: TerrainValue | null;
+}
+
+/**
+ * Slope and aspect derived from a DEM band at the queried pixel
+ */
+interface TerrainValue {
+  /** Band the values were derived from */
+  band: number;
+  /** Slope angle in degrees */
+  slope_degrees: number;
+  /** Slope in percent */
+  slope_percent: number;
+  /** Downslope direction in degrees clockwise from north; null on flat ground */
+  aspect: number | null;
 }
 
 /**
@@ -245,7 +278,9 @@ export class InspectTool {
   ): boolean {
     if (!bounds || bounds.length !== 4) return false;
     const [minX, minY, maxX, maxY] = bounds;
-    return lng >= minX && lng <= maxX && lat >= minY && lat <= maxY;
+    if (lat < minY || lat > maxY) return false;
+    // Bounds crossing the antimeridian run past 180
+    return [lng, lng + 360].some(x => x >= minX && x <= maxX);
   }
 
   private async queryAndShowPixelValues(
@@ -271,12 +306,19 @@ export class InspectTool {
           id: layer.id,
           pixel_x: pixelX,
           pixel_y: pixelY,
+          nodata: layer.nodataRules,
+          slice: dimensionSlice(layer),
         });
       } else {
+        // Terrain layers also get slope/aspect at the clicked location
+        const isTerrain = layer.terrainDerivative !== undefined || layer.hillshade !== undefined;
         result = await invoke<PixelQueryResult>('query_pixel_value', {
           id: layer.id,
           lng,
           lat,
+          terrainBand: isTerrain ? (layer.band ?? 1) : undefined,
+          nodata: layer.nodataRules,
+          slice: dimensionSlice(layer),
         });
       }
 
@@ -306,15 +348,44 @@ export class InspectTool {
     } else {
       html += '<div class="inspect-values">';
       for (const bandValue of result.values) {
+        const swatch = bandValue.color
+          ? `<span class="class-swatch" style="display: inline-block; width: 10px; height: 10px; margin-right: 4px; background: rgba(${bandValue.color.slice(0, 3).join(', ')}, ${bandValue.color[3] / 255});"></span>`
+          : '';
         const valueStr = bandValue.is_nodata
           ? '<span class="nodata-value">NoData</span>'
-          : this.formatValue(bandValue.value);
+          : swatch + this.formatValue(bandValue.value);
         html += `
           <div class="inspect-band">
             <span class="band-label">Band ${bandValue.band}:</span>
             <span class="band-value">${valueStr}</span>
           </div>
         `;
+        if (bandValue.complex && !bandValue.is_nodata) {
+          const complex = bandValue.complex;
+          html += `
+          <div class="inspect-band">
+            <span class="band-label">Complex:</span>
+            <span class="band-value">${formatComplex(complex, v => this.formatValue(v))}</span>
+          </div>
+          <div class="inspect-band">
+            <span class="band-label">Phase:</span>
+            <span class="band-value">${phaseDegrees(complex.phase)}</span>
+          </div>
+        `;
+        }
+      }
+      if (result.terrain) {
+        const { slope_degrees, slope_percent, aspect } = result.terrain;
+        html += `
+          <div class="inspect-band">
+            <span class="band-label">Slope:</span>
+            <span class="band-value">${slope_degrees.toFixed(1)}° (${slope_percent.toFixed(1)}%)</span>
+          </div>
+          <div class="inspect-band">
+            <span class="band-label">Aspect:</span>
+            <span class="band-value">${aspect === null ? 'Flat' : `${aspect.toFixed(0)}°`}</span>
+          </div>
+        `;
       }
       html += '</div>';
     }
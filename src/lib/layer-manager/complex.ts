@@ -0,0 +1,52 @@
+/**
+ * Display of complex-valued (SAR) bands
+ * @module layer-manager/complex
+ */
+
+import type { ComplexPart } from './types';
+
+/** Parts of a complex pixel, as returned by pixel queries */
+export interface ComplexValue {
+  real: number;
+  imaginary: number;
+  amplitude: number;
+  /** Radians, -π to π */
+  phase: number;
+}
+
+/** Choices for drawing complex bands, in menu order */
+export const COMPLEX_PARTS: readonly { value: ComplexPart; label: string }[] = [
+  { value: 'amplitude', label: 'Amplitude' },
+  { value: 'intensity', label: 'Intensity' },
+  { value: 'db', label: 'Intensity (dB)' },
+  { value: 'phase', label: 'Phase' },
+  { value: 'real', label: 'Real' },
+  { value: 'imaginary', label: 'Imaginary' },
+];
+
+/** Stretch range of phase layers; the full circle, drawn with a cyclic colormap */
+export const PHASE_RANGE: [number, number] = [-Math.PI, Math.PI];
+
+/** Colormap for phase layers, whose ends meet so ±π look alike */
+export const PHASE_COLORMAP = 'twilight';
+
+/**
+ * Format a complex pixel as `a ± bi`
+ * @param value - Complex pixel value
+ * @param format - Formatter for each part
+ */
+export function formatComplex(
+  value: ComplexValue,
+  format: (n: number) => string = n => n.toString()
+): string {
+  const sign = value.imaginary < 0 || Object.is(value.imaginary, -0) ? '-' : '+';
+  return `${format(value.real)} ${sign} ${format(Math.abs(value.imaginary))}i`;
+}
+
+/**
+ * Phase in degrees, for display
+ * @param radians - Phase in radians
+ */
+export function phaseDegrees(radians: number): string {
+  return `${((radians * 180) / Math.PI).toFixed(1)}°`;
+}
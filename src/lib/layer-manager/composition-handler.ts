@@ -7,7 +7,9 @@ import {
   setupCompositionTileProtocol,
   setupCrossLayerCompositionTileProtocol,
 } from './tile-protocol';
+import { rgbSourceBand } from './dimensions';
 import { logger } from '../logger';
+import { sourceBounds } from '../geo-utils';
 import type { LayerManagerInterface, RasterLayer } from './types';
 import type maplibregl from 'maplibre-gl';
 
@@ -34,6 +36,10 @@ export async function createRgbCompositionLayer(
   const compositionId = `rgb-comp-${Date.now()}`;
   const sourceName = sourceLayer.path.split('/').pop()?.split('\\').pop() || 'Unknown';
 
+  // Multidimensional rasters count RGB bands along their last dimension
+  const statsFor = (band: number) =>
+    sourceLayer.band_stats[rgbSourceBand(sourceLayer, band) - 1] || { min: 0, max: 255 };
+
   // Create the composition layer data
   const compositionLayer: RasterLayer = {
     id: compositionId,
@@ -48,9 +54,9 @@ export async function createRgbCompositionLayer(
     bounds: sourceLayer.bounds,
     is_georeferenced: sourceLayer.is_georeferenced,
     band_stats: [
-      sourceLayer.band_stats[sourceLayer.rgbBands.r - 1] || { min: 0, max: 255 },
-      sourceLayer.band_stats[sourceLayer.rgbBands.g - 1] || { min: 0, max: 255 },
-      sourceLayer.band_stats[sourceLayer.rgbBands.b - 1] || { min: 0, max: 255 },
+      statsFor(sourceLayer.rgbBands.r),
+      statsFor(sourceLayer.rgbBands.g),
+      statsFor(sourceLayer.rgbBands.b),
     ],
     visible: true,
     opacity: 1.0,
@@ -94,7 +100,7 @@ export async function createRgbCompositionLayer(
     type: 'raster',
     tiles: [`${protocolName}://{z}/{x}/{y}`],
     tileSize: 256,
-    bounds: mapBounds,
+    ...sourceBounds(mapBounds),
     minzoom: 0,
     maxzoom: 22,
   });
@@ -234,7 +240,7 @@ export async function createCrossLayerRgbCompositionLayer(
     type: 'raster',
     tiles: [`${protocolName}://{z}/{x}/{y}`],
     tileSize: 256,
-    bounds: mapBounds,
+    ...sourceBounds(mapBounds),
     minzoom: 0,
     maxzoom: 22,
   });
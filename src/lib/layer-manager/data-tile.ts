@@ -0,0 +1,274 @@
+/**
+ * Float32 data tiles: raw band values from the backend, stretched in the frontend
+ * @module layer-manager/data-tile
+ */
+
+import type { StretchSettings } from './types';
+
+/** Magic bytes opening every data tile */
+const DATA_TILE_MAGIC = 'HDT1';
+
+/** Header: magic, then u16 width, height, band count and a reserved u16 */
+const HEADER_BYTES = 12;
+
+/** Decoded data tile */
+export interface DataTile {
+  width: number;
+  height: number;
+  /** Row-major values per band, NaN where invalid */
+  bands: Float32Array[];
+  /** 255 where any band holds a valid value, 0 elsewhere */
+  mask: Uint8Array;
+}
+
+/**
+ * Decode a tile returned by get_data_tile / get_pixel_data_tile
+ * @param buffer - Encoded tile
+ * @returns The tile, or null for tiles outside the dataset
+ */
+export function decodeDataTile(buffer: ArrayBuffer): DataTile | null {
+  if (buffer.byteLength < HEADER_BYTES) {
+    throw new Error('Invalid data tile: truncated header');
+  }
+  const magic = String.fromCharCode(...new Uint8Array(buffer, 0, 4));
+  if (magic !== DATA_TILE_MAGIC) {
+    throw new Error('Invalid data tile: bad magic');
+  }
+
+  const view = new DataView(buffer);
+  const width = view.getUint16(4, true);
+  const height = view.getUint16(6, true);
+  const bandCount = view.getUint16(8, true);
+  if (width === 0 || height === 0) {
+    return null;
+  }
+
+  const pixels = width * height;
+  const maskOffset = HEADER_BYTES + bandCount * pixels * 4;
+  if (buffer.byteLength < maskOffset + pixels) {
+    throw new Error('Invalid data tile: truncated data');
+  }
+
+  const bands: Float32Array[] = [];
+  for (let i = 0; i < bandCount; i++) {
+    bands.push(new Float32Array(buffer, HEADER_BYTES + i * pixels * 4, pixels));
+  }
+  const mask = new Uint8Array(buffer, maskOffset, pixels);
+
+  return { width, height, bands, mask };
+}
+
+/** Display level (0-255) of a value, matching the backend's linear stretch */
+function stretchLevel(value: number, stretch: StretchSettings): number {
+  if (Number.isNaN(value)) return 0;
+  const range = stretch.max > stretch.min ? stretch.max - stretch.min : 1;
+  const normalized = Math.min(Math.max((value - stretch.min) / range, 0), 1);
+  return Math.floor(Math.pow(normalized, 1 / stretch.gamma) * 255);
+}
+
+/**
+ * The band and stretch feeding each of the red, green and blue channels:
+ * a single band is shown as grayscale
+ */
+function channels(tile: DataTile, stretches: StretchSettings[]) {
+  return [0, 1, 2].map(c => ({
+    values: tile.bands[Math.min(c, tile.bands.length - 1)],
+    stretch: stretches[Math.min(c, stretches.length - 1)],
+  }));
+}
+
+/**
+ * Stretch a data tile to RGBA on the CPU
+ * @param tile - Decoded tile with one (grayscale) or three (RGB) bands
+ * @param stretches - One stretch per band
+ * @returns RGBA pixels, transparent where the mask is unset
+ */
+export function stretchDataTile(tile: DataTile, stretches: StretchSettings[]): Uint8ClampedArray {
+  const rgba = new Uint8ClampedArray(tile.width * tile.height * 4);
+  const rgb = channels(tile, stretches);
+
+  for (let i = 0; i < tile.mask.length; i++) {
+    if (!tile.mask[i]) continue;
+    for (let c = 0; c < 3; c++) {
+      rgba[i * 4 + c] = stretchLevel(rgb[c].values[i], rgb[c].stretch);
+    }
+    rgba[i * 4 + 3] = 255;
+  }
+
+  return rgba;
+}
+
+// ==================== WebGL ====================
+
+const VERTEX_SHADER = `#version 300 es
+in vec2 position;
+out vec2 uv;
+void main() {
+  // Tile rows run top to bottom
+  uv = vec2(position.x + 1.0, 1.0 - position.y) * 0.5;
+  gl_Position = vec4(position, 0.0, 1.0);
+}`;
+
+const FRAGMENT_SHADER = `#version 300 es
+precision highp float;
+uniform highp sampler2D red;
+uniform highp sampler2D green;
+uniform highp sampler2D blue;
+uniform lowp sampler2D mask;
+uniform vec3 minimum;
+uniform vec3 maximum;
+uniform vec3 gamma;
+in vec2 uv;
+out vec4 color;
+void main() {
+  if (texture(mask, uv).r == 0.0) {
+    color = vec4(0.0);
+    return;
+  }
+  vec3 values = vec3(texture(red, uv).r, texture(green, uv).r, texture(blue, uv).r);
+  vec3 range = maximum - minimum;
+  range = mix(vec3(1.0), range, greaterThan(range, vec3(0.0)));
+  vec3 level = pow(clamp((values - minimum) / range, 0.0, 1.0), 1.0 / gamma);
+  color = vec4(mix(level, vec3(0.0), isnan(values)), 1.0);
+}`;
+
+/** WebGL2 context and program shared by every data tile */
+interface GpuStretcher {
+  canvas: OffscreenCanvas;
+  gl: WebGL2RenderingContext;
+  program: WebGLProgram;
+}
+
+/** Shared stretcher; null once WebGL2 turned out to be unavailable */
+let gpu: GpuStretcher | null | undefined;
+
+function compileShader(gl: WebGL2RenderingContext, type: number, source: string): WebGLShader {
+  const shader = gl.createShader(type)!;
+  gl.shaderSource(shader, source);
+  gl.compileShader(shader);
+  if (!gl.getShaderParameter(shader, gl.COMPILE_STATUS)) {
+    throw new Error(gl.getShaderInfoLog(shader) ?? 'Shader compilation failed');
+  }
+  return shader;
+}
+
+function createGpuStretcher(): GpuStretcher | null {
+  if (typeof OffscreenCanvas === 'undefined') return null;
+
+  const canvas = new OffscreenCanvas(256, 256);
+  const gl = canvas.getContext('webgl2', {
+    premultipliedAlpha: false,
+    preserveDrawingBuffer: true,
+  });
+  if (!gl) return null;
+
+  try {
+    const program = gl.createProgram()!;
+    gl.attachShader(program, compileShader(gl, gl.VERTEX_SHADER, VERTEX_SHADER));
+    gl.attachShader(program, compileShader(gl, gl.FRAGMENT_SHADER, FRAGMENT_SHADER));
+    gl.linkProgram(program);
+    if (!gl.getProgramParameter(program, gl.LINK_STATUS)) {
+      throw new Error(gl.getProgramInfoLog(program) ?? 'Program link failed');
+    }
+    gl.useProgram(program);
+
+    // Two triangles covering the viewport
+    const buffer = gl.createBuffer();
+    gl.bindBuffer(gl.ARRAY_BUFFER, buffer);
+    gl.bufferData(
+      gl.ARRAY_BUFFER,
+      new Float32Array([-1, -1, 1, -1, -1, 1, -1, 1, 1, -1, 1, 1]),
+      gl.STATIC_DRAW
+    );
+    const position = gl.getAttribLocation(program, 'position');
+    gl.enableVertexAttribArray(position);
+    gl.vertexAttribPointer(position, 2, gl.FLOAT, false, 0, 0);
+
+    ['red', 'green', 'blue', 'mask'].forEach((name, unit) => {
+      gl.uniform1i(gl.getUniformLocation(program, name), unit);
+    });
+    gl.pixelStorei(gl.UNPACK_ALIGNMENT, 1);
+
+    return { canvas, gl, program };
+  } catch {
+    return null;
+  }
+}
+
+/** Upload single-channel data to a texture unit, unfiltered so values stay exact */
+function uploadTexture(
+  gl: WebGL2RenderingContext,
+  unit: number,
+  tile: DataTile,
+  data: Float32Array | Uint8Array
+): WebGLTexture {
+  const texture = gl.createTexture()!;
+  gl.activeTexture(gl.TEXTURE0 + unit);
+  gl.bindTexture(gl.TEXTURE_2D, texture);
+  gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MIN_FILTER, gl.NEAREST);
+  gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_MAG_FILTER, gl.NEAREST);
+  gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_S, gl.CLAMP_TO_EDGE);
+  gl.texParameteri(gl.TEXTURE_2D, gl.TEXTURE_WRAP_T, gl.CLAMP_TO_EDGE);
+  const [internalFormat, type] =
+    data instanceof Float32Array ? [gl.R32F, gl.FLOAT] : [gl.R8, gl.UNSIGNED_BYTE];
+  gl.texImage2D(gl.TEXTURE_2D, 0, internalFormat, tile.width, tile.height, 0, gl.RED, type, data);
+  return texture;
+}
+
+/** Stretch a tile into the shared canvas */
+function drawOnGpu(
+  { canvas, gl, program }: GpuStretcher,
+  tile: DataTile,
+  stretches: StretchSettings[]
+): void {
+  canvas.width = tile.width;
+  canvas.height = tile.height;
+  gl.viewport(0, 0, tile.width, tile.height);
+
+  const rgb = channels(tile, stretches);
+  const textures = [
+    ...rgb.map(({ values }, unit) => uploadTexture(gl, unit, tile, values)),
+    uploadTexture(gl, 3, tile, tile.mask),
+  ];
+  const uniform = (name: string, pick: (s: StretchSettings) => number) => {
+    const [r, g, b] = rgb.map(({ stretch }) => pick(stretch));
+    gl.uniform3f(gl.getUniformLocation(program, name), r, g, b);
+  };
+  uniform('minimum', s => s.min);
+  uniform('maximum', s => s.max);
+  uniform('gamma', s => s.gamma);
+
+  gl.drawArrays(gl.TRIANGLES, 0, 6);
+  textures.forEach(texture => gl.deleteTexture(texture));
+}
+
+/**
+ * Stretch a data tile and encode it as PNG for MapLibre. Uses WebGL2 when
+ * available, the CPU otherwise.
+ * @param tile - Decoded tile with one (grayscale) or three (RGB) bands
+ * @param stretches - One stretch per band
+ */
+export async function renderDataTile(
+  tile: DataTile,
+  stretches: StretchSettings[]
+): Promise<Uint8Array> {
+  if (gpu === undefined) {
+    gpu = createGpuStretcher();
+  }
+
+  let canvas: OffscreenCanvas;
+  if (gpu) {
+    // convertToBlob copies the canvas when called, so concurrent tiles can
+    // share it as long as nothing awaits between drawing and encoding
+    drawOnGpu(gpu, tile, stretches);
+    canvas = gpu.canvas;
+  } else {
+    canvas = new OffscreenCanvas(tile.width, tile.height);
+    const ctx = canvas.getContext('2d')!;
+    const pixels = new ImageData(stretchDataTile(tile, stretches), tile.width, tile.height);
+    ctx.putImageData(pixels, 0, 0);
+  }
+
+  const blob = await canvas.convertToBlob({ type: 'image/png' });
+  return new Uint8Array(await blob.arrayBuffer());
+}
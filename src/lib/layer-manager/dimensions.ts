@@ -0,0 +1,95 @@
+/**
+ * Slicing multidimensional rasters (netCDF, Zarr, HDF5 cubes) by time, depth
+ * or level instead of by band number
+ * @module layer-manager/dimensions
+ */
+
+import type { RasterDimension, RasterLayer } from './types';
+
+/** Selected index of each dimension, as the tile and query commands take it */
+export type DimensionSlice = Record<string, { index: number }>;
+
+/** The parts of a raster layer that describe its dimensions */
+export type DimensionedLayer = Pick<RasterLayer, 'dimensions' | 'dimensionIndices'>;
+
+/**
+ * Selected index of one of a layer's dimensions
+ * @param layer - Raster layer
+ * @param dimension - One of the layer's dimensions
+ * @returns The index, clamped to the dimension
+ */
+export function dimensionIndex(layer: DimensionedLayer, dimension: RasterDimension): number {
+  const index = layer.dimensionIndices?.[dimension.name] ?? 0;
+  return Math.min(Math.max(index, 0), dimension.values.length - 1);
+}
+
+/**
+ * Slice to pass to tile and query commands
+ * @param layer - Raster layer
+ * @returns The selected index of every dimension, or undefined for plain rasters
+ */
+export function dimensionSlice(layer: DimensionedLayer): DimensionSlice | undefined {
+  if (!layer.dimensions?.length) return undefined;
+  return Object.fromEntries(
+    layer.dimensions.map(d => [d.name, { index: dimensionIndex(layer, d) }])
+  );
+}
+
+/**
+ * Slice for RGB composites: every dimension but the last, so the red, green
+ * and blue band numbers are positions along the last dimension at the
+ * selected steps of the others (e.g. three wavelengths at one time step)
+ * @param layer - Raster layer
+ * @returns The slice, or undefined for plain rasters
+ */
+export function rgbDimensionSlice(layer: DimensionedLayer): DimensionSlice | undefined {
+  if (!layer.dimensions?.length) return undefined;
+  return Object.fromEntries(
+    layer.dimensions.slice(0, -1).map(d => [d.name, { index: dimensionIndex(layer, d) }])
+  );
+}
+
+/**
+ * Band an RGB channel of a multidimensional raster reads (see
+ * {@link rgbDimensionSlice}); plain rasters use the band number as is
+ * @param layer - Raster layer
+ * @param band - 1-indexed position along the last dimension
+ * @returns 1-indexed band number
+ */
+export function rgbSourceBand(layer: DimensionedLayer, band: number): number {
+  const dimensions = layer.dimensions ?? [];
+  const last = dimensions[dimensions.length - 1];
+  if (!last) return band;
+  const step = dimensions
+    .slice(0, -1)
+    .reduce((index, d) => index * d.values.length + dimensionIndex(layer, d), 0);
+  return step * last.values.length + band;
+}
+
+/**
+ * Band holding the selected slice; bands run row-major over the dimensions,
+ * the last one varying fastest
+ * @param layer - Raster layer with dimensions
+ * @returns 1-indexed band number
+ */
+export function dimensionBand(layer: DimensionedLayer): number {
+  return (
+    (layer.dimensions ?? []).reduce(
+      (band, d) => band * d.values.length + dimensionIndex(layer, d),
+      0
+    ) + 1
+  );
+}
+
+/**
+ * Text for the selected position along a dimension
+ * @param layer - Raster layer
+ * @param dimension - One of the layer's dimensions
+ * @returns The label, with units unless it is a date
+ */
+export function dimensionLabel(layer: DimensionedLayer, dimension: RasterDimension): string {
+  const index = dimensionIndex(layer, dimension);
+  const label = dimension.labels[index] ?? String(index);
+  const isDate = /^\d{4}-\d{2}-\d{2}/.test(label);
+  return dimension.units && !isDate ? `${label} ${dimension.units}` : label;
+}
@@ -5,7 +5,8 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import { logger } from '../logger';
-import type { LayerManagerInterface, RasterLayer, HistogramData } from './types';
+import { computeBandStatistics, viewportRegion } from './raster-handler';
+import type { LayerManagerInterface, RasterLayer, HistogramData, BandStats } from './types';
 
 const log = logger.child('LayerManager:Histogram');
 
@@ -33,6 +34,7 @@ export async function showHistogram(
   const logScaleCheckbox = document.getElementById(
     'histogram-log-scale'
   ) as HTMLInputElement | null;
+  const viewportCheckbox = document.getElementById('histogram-viewport') as HTMLInputElement | null;
   const tooltip = document.getElementById('histogram-tooltip');
 
   if (!panel || !canvas) return;
@@ -62,6 +64,34 @@ export async function showHistogram(
     };
   }
 
+  // Bin only the part of the layer in view, so a small area of interest is
+  // not drowned out by the rest of the scene
+  if (viewportCheckbox) {
+    viewportCheckbox.onchange = async () => {
+      await showHistogram(manager, layerId, band);
+    };
+  }
+
+  // Band statistics, with a button to compute them over every pixel
+  const summary = document.getElementById('histogram-summary');
+  const exactStatsBtn = document.getElementById(
+    'histogram-exact-stats'
+  ) as HTMLButtonElement | null;
+  if (summary) summary.textContent = formatBandStats(rasterLayer.band_stats[band - 1]);
+  if (exactStatsBtn) {
+    // GDAL statistics know only the file's own nodata and complex amplitudes
+    exactStatsBtn.disabled =
+      !!rasterLayer.isComposition ||
+      !!rasterLayer.nodataRules ||
+      (rasterLayer.complexPart ?? 'amplitude') !== 'amplitude';
+    exactStatsBtn.onclick = async () => {
+      exactStatsBtn.disabled = true;
+      const stats = await computeBandStatistics(manager, layerId, band, 'exact');
+      exactStatsBtn.disabled = false;
+      if (stats && summary) summary.textContent = formatBandStats(stats);
+    };
+  }
+
   // Show panel with loading state
   panel.classList.add('visible');
   if (minSpan) minSpan.textContent = 'Loading...';
@@ -81,6 +111,9 @@ export async function showHistogram(
       id: layerId,
       band,
       numBins: 256,
+      nodata: rasterLayer.nodataRules,
+      complex: rasterLayer.complexPart,
+      ...(viewportCheckbox?.checked ? viewportRegion(manager, rasterLayer) : {}),
     });
 
     // Store histogram data for redraw
@@ -138,6 +171,22 @@ export async function showHistogram(
   }
 }
 
+/**
+ * Summarise band statistics: mean and standard deviation, plus the valid
+ * pixel count and nodata share when GDAL computed them
+ * @param stats - Band statistics, if known
+ */
+export function formatBandStats(stats: BandStats | undefined): string {
+  if (stats?.mean === undefined || stats.std_dev === undefined) return '';
+
+  const moments = `Mean: ${stats.mean.toFixed(2)}  σ: ${stats.std_dev.toFixed(2)}`;
+  if (stats.valid_count == null) return `${moments} (sampled)`;
+
+  const nodata = (stats.nodata_percent ?? 0).toFixed(1);
+  const accuracy = stats.approximate ? ', approx.' : '';
+  return `${moments}  ${stats.valid_count.toLocaleString()} valid (${nodata}% nodata${accuracy})`;
+}
+
 /**
  * Setup hover interaction for histogram
  * @param manager - The LayerManager instance
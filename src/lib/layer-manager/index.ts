@@ -23,7 +23,20 @@ import type {
   VectorLayer,
   MapManagerInterface,
   HistogramData,
+  BandStats,
   DisplayMode,
+  Resampling,
+  Colormap,
+  HillshadeSettings,
+  TerrainDerivative,
+  TileFormat,
+  NodataRules,
+  AutoStretchMethod,
+  ComplexPart,
+  StatsAccuracy,
+  OverviewLocation,
+  OverviewResampling,
+  StretchMode,
   VectorStyle,
   LayerManagerOptions,
 } from './types';
@@ -46,11 +59,30 @@ interface PixelQueryResult {
 // Import handlers
 import {
   addRasterLayer,
+  openRasterFile,
+  addBandMathLayer,
+  exportRasterLayer,
+  buildLayerOverviews,
+  cancelLayerOverviews,
+  computeBandStatistics,
   setLayerStretch,
+  autoStretchLayer,
   setLayerBand,
+  setLayerDimension,
+  setLayerComplexPart,
   setLayerDisplayMode,
   setRgbBands,
   setRgbStretch,
+  setLayerStretchMode,
+  setRgbStretchMode,
+  setLayerResampling,
+  setLayerColormap,
+  setLayerHillshade,
+  setLayerTerrainDerivative,
+  setLayerNodataRules,
+  setLayerDataTiles,
+  setLayerTileSize,
+  setLayerTileFormat,
   refreshLayerTiles,
 } from './raster-handler';
 import {
@@ -74,6 +106,7 @@ import {
 } from './tile-protocol';
 import { showHistogram, setupHistogramHover, drawHistogram } from './histogram';
 import { updateLayerPanel, updateDynamicControls } from './layer-ui';
+import { dimensionSlice } from './dimensions';
 
 const log = logger.child('LayerManager');
 
@@ -241,12 +274,16 @@ export class LayerManager {
             id: topmostRaster.id,
             pixel_x: pixelX,
             pixel_y: pixelY,
+            nodata: topmostRaster.nodataRules,
+            slice: dimensionSlice(topmostRaster),
           });
         } else {
           result = await invoke<PixelQueryResult>('query_pixel_value', {
             id: topmostRaster.id,
             lng,
             lat,
+            nodata: topmostRaster.nodataRules,
+            slice: dimensionSlice(topmostRaster),
           });
         }
 
@@ -445,6 +482,16 @@ export class LayerManager {
     return addRasterLayer(this, filePath) as Promise<RasterLayer>;
   }
 
+  /**
+   * Open a raster file chosen by the user, asking which subdatasets to open
+   * for containers such as netCDF or HDF5 files.
+   * @param filePath - Path to the raster file
+   * @returns Metadata of the opened layers; empty when the choice is cancelled
+   */
+  async openRasterFile(filePath: string): Promise<RasterLayer[]> {
+    return openRasterFile(this, filePath) as Promise<RasterLayer[]>;
+  }
+
   /**
    * Set layer stretch parameters for grayscale display.
    * @param id - Layer ID
@@ -456,6 +503,20 @@ export class LayerManager {
     setLayerStretch(this, id, min, max, gamma);
   }
 
+  /**
+   * Auto stretch a layer (each channel for RGB) from its sampled values.
+   * @param id - Layer ID
+   * @param method - Percentile, standard deviation or min/max
+   * @param viewportOnly - Sample only the part of the layer in view
+   */
+  async autoStretchLayer(
+    id: string,
+    method: AutoStretchMethod,
+    viewportOnly: boolean
+  ): Promise<void> {
+    await autoStretchLayer(this, id, method, viewportOnly);
+  }
+
   /**
    * Set the displayed band for a raster layer.
    * @param id - Layer ID
@@ -465,6 +526,25 @@ export class LayerManager {
     setLayerBand(this, id, band);
   }
 
+  /**
+   * Select a time step, depth or level of a multidimensional raster layer.
+   * @param id - Layer ID
+   * @param name - Dimension name
+   * @param index - Index along the dimension
+   */
+  setLayerDimension(id: string, name: string, index: number): void {
+    setLayerDimension(this, id, name, index);
+  }
+
+  /**
+   * Choose how a complex (SAR) raster layer is drawn.
+   * @param id - Layer ID
+   * @param part - Amplitude, intensity, dB, phase, real or imaginary
+   */
+  async setLayerComplexPart(id: string, part: ComplexPart): Promise<void> {
+    await setLayerComplexPart(this, id, part);
+  }
+
   /**
    * Set the display mode for a raster layer.
    * @param id - Layer ID
@@ -503,6 +583,163 @@ export class LayerManager {
     setRgbStretch(this, id, channel, min, max, gamma);
   }
 
+  /**
+   * Set the contrast mode of a grayscale layer's stretch.
+   * @param id - Layer ID
+   * @param mode - Linear, histogram equalization or clipped equalization
+   */
+  setLayerStretchMode(id: string, mode: StretchMode): void {
+    setLayerStretchMode(this, id, mode);
+  }
+
+  /**
+   * Set the contrast mode of one RGB channel's stretch.
+   * @param id - Layer ID
+   * @param channel - Channel ('r', 'g', 'b')
+   * @param mode - Linear, histogram equalization or clipped equalization
+   */
+  setRgbStretchMode(id: string, channel: 'r' | 'g' | 'b', mode: StretchMode): void {
+    setRgbStretchMode(this, id, channel, mode);
+  }
+
+  /**
+   * Set the resampling algorithm used when rendering a raster layer's tiles.
+   * @param id - Layer ID
+   * @param resampling - Resampling algorithm ('nearest', 'bilinear', 'cubic', ...)
+   */
+  setLayerResampling(id: string, resampling: Resampling): void {
+    setLayerResampling(this, id, resampling);
+  }
+
+  /**
+   * Set the colormap used for grayscale display of a raster layer.
+   * @param id - Layer ID
+   * @param colormap - Named ramp, custom stops or class map; null for plain grayscale
+   */
+  setLayerColormap(id: string, colormap: Colormap | null): void {
+    setLayerColormap(this, id, colormap);
+  }
+
+  /**
+   * Shade a raster layer as a DEM, combined with its colormap if one is set.
+   * @param id - Layer ID
+   * @param hillshade - Sun azimuth/altitude, z-factor and mode; null to turn shading off
+   */
+  setLayerHillshade(id: string, hillshade: HillshadeSettings | null): void {
+    setLayerHillshade(this, id, hillshade);
+  }
+
+  /**
+   * Display slope or aspect computed from a DEM layer instead of its elevation.
+   * @param id - Layer ID
+   * @param derivative - 'slope_degrees', 'slope_percent' or 'aspect'; null for the elevation
+   */
+  setLayerTerrainDerivative(id: string, derivative: TerrainDerivative | null): void {
+    setLayerTerrainDerivative(this, id, derivative);
+  }
+
+  /**
+   * Hide extra values of a raster layer, on top of the file's own nodata, mask and alpha.
+   * @param id - Layer ID
+   * @param rules - Values and/or an inclusive [min, max] range; null to hide nothing extra
+   */
+  setLayerNodataRules(id: string, rules: NodataRules | null): void {
+    setLayerNodataRules(this, id, rules);
+  }
+
+  /**
+   * Fetch raw Float32 tiles for a raster layer and stretch them in the frontend,
+   * so stretch changes re-render without going back to the backend.
+   * @param id - Layer ID
+   * @param enabled - Whether to use data tiles
+   */
+  setLayerDataTiles(id: string, enabled: boolean): void {
+    setLayerDataTiles(this, id, enabled);
+  }
+
+  /**
+   * Set the tile size of a raster layer and whether its tiles render at the
+   * device pixel ratio.
+   * @param id - Layer ID
+   * @param tileSize - MapLibre tile size in CSS pixels (256, 512, ...)
+   * @param hidpi - Whether to render tiles at the device pixel ratio
+   */
+  setLayerTileSize(id: string, tileSize: number, hidpi: boolean): void {
+    setLayerTileSize(this, id, tileSize, hidpi);
+  }
+
+  /**
+   * Set the image format of a raster layer's tiles. JPEG tiles with transparent
+   * pixels are sent as WebP instead.
+   * @param id - Layer ID
+   * @param format - WebP or JPEG (with quality); null for PNG
+   */
+  setLayerTileFormat(id: string, format: TileFormat | null): void {
+    setLayerTileFormat(this, id, format);
+  }
+
+  /**
+   * Create a layer from a band-math expression such as `(b8 - b4) / (b8 + b4)`.
+   * @param id - Layer whose bands are `b1`, `b2`, ...; the result uses its grid
+   * @param expression - Band-math expression
+   * @param inputs - Other layer ids by alias, referenced as `alias.b1`
+   * @returns Metadata of the new layer
+   */
+  async createBandMathLayer(
+    id: string,
+    expression: string,
+    inputs: Record<string, string> = {}
+  ): Promise<RasterLayer> {
+    return addBandMathLayer(this, id, expression, inputs) as Promise<RasterLayer>;
+  }
+
+  /**
+   * Export a raster layer to a GeoTIFF chosen in a save dialog.
+   * @param id - Layer ID
+   */
+  async exportRasterLayer(id: string): Promise<void> {
+    return exportRasterLayer(this, id);
+  }
+
+  /**
+   * Build overviews for a raster layer's file, reporting progress in the
+   * loading indicator.
+   * @param id - Layer ID
+   * @param location - Inside the file, or a `.ovr` file beside it
+   * @param resampling - How overview pixels are computed
+   */
+  async buildLayerOverviews(
+    id: string,
+    location: OverviewLocation,
+    resampling: OverviewResampling
+  ): Promise<void> {
+    return buildLayerOverviews(this, id, location, resampling);
+  }
+
+  /**
+   * Compute a band's statistics over the whole file with GDAL, stored
+   * alongside it for later opens.
+   * @param id - Layer ID
+   * @param band - Band number
+   * @param accuracy - 'exact' reads every pixel
+   * @returns The statistics, or null on failure
+   */
+  async computeBandStatistics(
+    id: string,
+    band: number,
+    accuracy: StatsAccuracy
+  ): Promise<BandStats | null> {
+    return computeBandStatistics(this, id, band, accuracy);
+  }
+
+  /**
+   * Stop a running overview build for a raster layer.
+   * @param id - Layer ID
+   */
+  async cancelLayerOverviews(id: string): Promise<void> {
+    return cancelLayerOverviews(this, id);
+  }
+
   /**
    * Refresh tiles for a raster layer (force reload).
    * @param id - Layer ID
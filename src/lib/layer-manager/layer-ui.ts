@@ -3,12 +3,27 @@
  * @module layer-manager/layer-ui
  */
 
+import { DEFAULT_TILE_SIZE, NAMED_COLORMAPS, TERRAIN_DERIVATIVE_RANGES } from './types';
+import { dimensionIndex, dimensionLabel, rgbSourceBand } from './dimensions';
+import { COMPLEX_PARTS, PHASE_RANGE } from './complex';
 import type {
+  Colormap,
+  ComplexPart,
+  HillshadeMode,
+  HillshadeSettings,
   LayerManagerInterface,
   RasterLayer,
   VectorLayer,
   CrossLayerRgbConfig,
   LayerManagerOptions,
+  NodataRules,
+  AutoStretchMethod,
+  OverviewLocation,
+  OverviewResampling,
+  StretchMode,
+  Resampling,
+  TerrainDerivative,
+  TileFormat,
 } from './types';
 
 /** Extended LayerManager interface with UI-specific properties */
@@ -22,7 +37,30 @@ interface LayerManagerWithUI extends LayerManagerInterface {
   setLayerOpacity: (id: string, opacity: number) => void;
   setLayerDisplayMode: (id: string, mode: string) => void;
   setLayerBand: (id: string, band: number) => void;
+  setLayerDimension: (id: string, name: string, index: number) => void;
+  setLayerComplexPart: (id: string, part: ComplexPart) => Promise<void>;
+  setLayerResampling: (id: string, resampling: Resampling) => void;
+  setLayerColormap: (id: string, colormap: Colormap | null) => void;
+  setLayerHillshade: (id: string, hillshade: HillshadeSettings | null) => void;
+  setLayerTerrainDerivative: (id: string, derivative: TerrainDerivative | null) => void;
+  setLayerNodataRules: (id: string, rules: NodataRules | null) => void;
+  setLayerDataTiles: (id: string, enabled: boolean) => void;
+  setLayerTileSize: (id: string, tileSize: number, hidpi: boolean) => void;
+  setLayerTileFormat: (id: string, format: TileFormat | null) => void;
+  createBandMathLayer: (
+    id: string,
+    expression: string,
+    inputs?: Record<string, string>
+  ) => Promise<unknown>;
+  exportRasterLayer: (id: string) => Promise<void>;
+  buildLayerOverviews: (
+    id: string,
+    location: OverviewLocation,
+    resampling: OverviewResampling
+  ) => Promise<void>;
+  cancelLayerOverviews: (id: string) => Promise<void>;
   setLayerStretch: (id: string, min: number, max: number, gamma: number) => void;
+  autoStretchLayer: (id: string, method: AutoStretchMethod, viewportOnly: boolean) => Promise<void>;
   setRgbBands: (id: string, r: number, g: number, b: number) => void;
   setRgbStretch: (
     id: string,
@@ -31,6 +69,8 @@ interface LayerManagerWithUI extends LayerManagerInterface {
     max: number,
     gamma: number
   ) => void;
+  setLayerStretchMode: (id: string, mode: StretchMode) => void;
+  setRgbStretchMode: (id: string, channel: 'r' | 'g' | 'b', mode: StretchMode) => void;
   setVectorStyle: (id: string, property: string, value: string | number) => void;
   setColorByField: (id: string, fieldName: string | null) => void;
   showAttributeTable: (layerId: string) => void;
@@ -43,8 +83,98 @@ interface LayerManagerWithUI extends LayerManagerInterface {
 /** Extended raster layer with UI state */
 interface RasterLayerWithUI extends RasterLayer {
   showRgbStretch?: boolean;
+  /** Key of the selected AUTO_STRETCH_OPTIONS entry */
+  autoStretchOption?: string;
+  autoStretchViewport?: boolean;
+}
+
+/** Contrast modes offered for grayscale and each RGB channel */
+const STRETCH_MODE_OPTIONS: readonly [StretchMode['type'], string][] = [
+  ['linear', 'Linear'],
+  ['equalize', 'Equalize'],
+  ['clipped_equalize', 'Clipped equalize'],
+];
+
+/**
+ * Options for a contrast mode select
+ */
+function stretchModeOptions(mode: StretchMode | undefined): string {
+  const selected = mode?.type ?? 'linear';
+  return STRETCH_MODE_OPTIONS.map(
+    ([value, label]) =>
+      `<option value="${value}" ${selected === value ? 'selected' : ''}>${label}</option>`
+  ).join('');
+}
+
+/** Auto stretch choices: key, label and the method sent to the backend */
+const AUTO_STRETCH_OPTIONS: readonly [string, string, AutoStretchMethod][] = [
+  ['p2', '2–98%', { type: 'percentile', low: 2, high: 98 }],
+  ['p1', '1–99%', { type: 'percentile', low: 1, high: 99 }],
+  ['sd2', 'Mean ± 2σ', { type: 'std_dev', n: 2 }],
+  ['minmax', 'Min/Max', { type: 'min_max' }],
+];
+
+/** Resampling choices shown in the raster controls */
+const RESAMPLING_OPTIONS: readonly [Resampling, string][] = [
+  ['nearest', 'Nearest'],
+  ['bilinear', 'Bilinear'],
+  ['cubic', 'Cubic'],
+  ['lanczos', 'Lanczos'],
+  ['average', 'Average'],
+  ['mode', 'Mode'],
+];
+
+/** Tile sizes offered in the raster controls, in CSS pixels */
+const TILE_SIZE_OPTIONS: readonly number[] = [256, 512, 1024];
+
+/** Tile formats offered in the raster controls; lossy ones take a quality */
+const TILE_FORMAT_OPTIONS: readonly [string, string][] = [
+  ['png', 'PNG'],
+  ['webp', 'WebP (lossless)'],
+  ['webp-lossy', 'WebP (lossy)'],
+  ['jpeg', 'JPEG'],
+];
+
+/** Tile format option for a layer's format */
+function tileFormatOption(format: TileFormat): string {
+  return format.type === 'webp' && format.quality !== undefined ? 'webp-lossy' : format.type;
+}
+
+/** Whether a tile format option takes a quality */
+function isLossyTileFormat(option: string): boolean {
+  return option === 'jpeg' || option === 'webp-lossy';
 }
 
+/** Overview resampling choices; average suits imagery, mode classified rasters */
+const OVERVIEW_RESAMPLING_OPTIONS: readonly [OverviewResampling, string][] = [
+  ['average', 'Average'],
+  ['nearest', 'Nearest'],
+  ['bilinear', 'Bilinear'],
+  ['cubic', 'Cubic'],
+  ['lanczos', 'Lanczos'],
+  ['mode', 'Mode'],
+  ['gauss', 'Gauss'],
+];
+
+/** Hillshade illumination models offered in the grayscale controls */
+const HILLSHADE_MODES: readonly HillshadeMode[] = ['standard', 'multidirectional', 'combined'];
+
+/** Values a DEM layer can display: the elevation itself or a derived value */
+const TERRAIN_OPTIONS: readonly [TerrainDerivative | '', string][] = [
+  ['', 'Elevation'],
+  ['slope_degrees', 'Slope (°)'],
+  ['slope_percent', 'Slope (%)'],
+  ['aspect', 'Aspect'],
+];
+
+/** Hillshade settings used when shading is first turned on (gdaldem defaults) */
+const DEFAULT_HILLSHADE: HillshadeSettings = {
+  azimuth: 315,
+  altitude: 45,
+  z_factor: 1,
+  mode: 'standard',
+};
+
 /**
  * Update the layer panel UI with current layers.
  * Renders each layer as a draggable item with visibility toggle, opacity slider, and remove button.
@@ -257,13 +387,10 @@ export function updateLayerPanel(manager: LayerManagerWithUI): void {
       const autoStretchBtn = document.createElement('button');
       autoStretchBtn.className = 'layer-action-btn';
       autoStretchBtn.innerHTML = '<span class="action-icon">⚡</span> Auto';
-      autoStretchBtn.title = 'Auto stretch to min/max values';
+      autoStretchBtn.title = 'Auto stretch to the 2–98% percentile range';
       autoStretchBtn.addEventListener('click', e => {
         e.stopPropagation();
-        const bandStats = rasterLayer.band_stats[rasterLayer.band - 1];
-        if (bandStats) {
-          manager.setLayerStretch(id, bandStats.min, bandStats.max, 1.0);
-        }
+        manager.autoStretchLayer(id, AUTO_STRETCH_OPTIONS[0][2], false);
       });
 
       // Histogram button (toggle)
@@ -562,11 +689,51 @@ function renderRasterControls(
     ${canCrossLayerRgb ? `<option value="crossLayerRgb" ${layer.displayMode === 'crossLayerRgb' ? 'selected' : ''}>Cross-Layer RGB</option>` : ''}
   `;
 
+  const resampling = layer.resampling ?? 'nearest';
+  const resamplingOptions = RESAMPLING_OPTIONS.map(
+    ([value, label]) =>
+      `<option value="${value}" ${resampling === value ? 'selected' : ''}>${label}</option>`
+  ).join('');
+
+  const tileSize = layer.tileSize ?? DEFAULT_TILE_SIZE;
+  const tileSizeOptions = TILE_SIZE_OPTIONS.map(
+    size => `<option value="${size}" ${tileSize === size ? 'selected' : ''}>${size} px</option>`
+  ).join('');
+
+  const format = layer.tileFormat ?? { type: 'png' };
+  const formatOption = tileFormatOption(format);
+  const formatOptions = TILE_FORMAT_OPTIONS.map(
+    ([value, label]) =>
+      `<option value="${value}" ${formatOption === value ? 'selected' : ''}>${label}</option>`
+  ).join('');
+  const quality = (format.type !== 'png' && format.quality) || 85;
+
   let controlsHtml = `
     <div class="control-section">
       <label>Display Mode</label>
       <select id="display-mode">${modeOptions}</select>
     </div>
+    <div class="control-section">
+      <label>Resampling</label>
+      <select id="resampling-select">${resamplingOptions}</select>
+      ${renderDataTilesToggle(layer)}
+    </div>
+    <div class="control-section">
+      <label>Tile Size</label>
+      <select id="tile-size-select">${tileSizeOptions}</select>
+      <label class="stretch-toggle-label">
+        <input type="checkbox" id="hidpi-toggle" ${layer.hidpi ? 'checked' : ''}>
+        HiDPI tiles (×${window.devicePixelRatio})
+      </label>
+      <label>Tile Format</label>
+      <select id="tile-format-select">${formatOptions}</select>
+      <div id="tile-format-quality-row" class="mini-control ${isLossyTileFormat(formatOption) ? '' : 'hidden'}">
+        <span class="mini-label">Quality</span>
+        <input type="number" id="tile-format-quality" min="1" max="100" value="${quality}">
+      </div>
+    </div>
+    ${renderNodataControls(layer)}
+    ${renderOverviewControls(layer)}
   `;
 
   if (layer.displayMode === 'grayscale') {
@@ -577,22 +744,243 @@ function renderRasterControls(
     controlsHtml += renderRgbControls(layer);
   }
 
+  controlsHtml += renderAutoStretchControls(layer);
+  controlsHtml += renderBandMathControls(layer);
+
   controlsPanel.innerHTML = controlsHtml;
 
   // Attach event listeners
   attachRasterEventListeners(manager, layer);
 }
 
+/**
+ * Render the client-side stretch toggle; cross-layer and composition layers
+ * always render in the backend
+ */
+function renderDataTilesToggle(layer: RasterLayer): string {
+  if (layer.isComposition || layer.displayMode === 'crossLayerRgb') return '';
+
+  return `
+    <label class="stretch-toggle-label" title="Fetch raw values once and stretch them on the GPU">
+      <input type="checkbox" id="data-tiles-toggle" ${layer.dataTiles ? 'checked' : ''}>
+      Client-side stretch
+    </label>
+  `;
+}
+
+/**
+ * Render the extra nodata values and range hidden on top of the file's own
+ * nodata, mask and alpha band
+ */
+function renderNodataControls(layer: RasterLayer): string {
+  const rules = layer.nodataRules;
+  const values = rules?.values.join(', ') ?? '';
+  const [rangeMin, rangeMax] = rules?.range ?? ['', ''];
+
+  return `
+    <div class="control-section">
+      <label>Nodata Values</label>
+      <input type="text" id="nodata-values" placeholder="e.g. 0, 255" value="${values}">
+      <label>Nodata Range</label>
+      <input type="number" id="nodata-range-min" placeholder="Min" value="${rangeMin}">
+      <input type="number" id="nodata-range-max" placeholder="Max" value="${rangeMax}">
+      <button id="nodata-apply" class="control-btn">Apply Nodata</button>
+    </div>
+  `;
+}
+
+/**
+ * Render the overview level count and the build options; layers computed in
+ * memory have no file to build them for
+ */
+function renderOverviewControls(layer: RasterLayer): string {
+  if (layer.isComposition || layer.isCrossLayerComposition || layer.bandMathExpression) {
+    return '';
+  }
+
+  const levels = layer.overview_count ? `${layer.overview_count} levels` : 'None';
+  const resamplingOptions = OVERVIEW_RESAMPLING_OPTIONS.map(
+    ([value, label]) => `<option value="${value}">${label}</option>`
+  ).join('');
+  const button = layer.buildingOverviews
+    ? '<button id="build-overviews" class="control-btn">Cancel Build</button>'
+    : '<button id="build-overviews" class="control-btn">Build Overviews</button>';
+  const disabled = layer.buildingOverviews ? 'disabled' : '';
+
+  return `
+    <div class="control-section">
+      <label>Overviews <span class="value-display">${levels}</span></label>
+      <select id="overview-location" ${disabled}>
+        <option value="external">External (.ovr)</option>
+        <option value="internal">Internal</option>
+      </select>
+      <select id="overview-resampling" ${disabled}>${resamplingOptions}</select>
+      ${button}
+    </div>
+  `;
+}
+
+/**
+ * Render the auto stretch method, sample area and button, shared by all
+ * display modes (RGB modes stretch each channel)
+ */
+function renderAutoStretchControls(layer: RasterLayerWithUI): string {
+  const selected = layer.autoStretchOption ?? AUTO_STRETCH_OPTIONS[0][0];
+  const options = AUTO_STRETCH_OPTIONS.map(
+    ([key, label]) =>
+      `<option value="${key}" ${selected === key ? 'selected' : ''}>${label}</option>`
+  ).join('');
+
+  return `
+    <div class="control-section">
+      <label>Auto Stretch</label>
+      <select id="auto-stretch-method">${options}</select>
+      <label class="stretch-toggle-label">
+        <input type="checkbox" id="auto-stretch-viewport" ${layer.autoStretchViewport ? 'checked' : ''}>
+        Current view only
+      </label>
+      <button id="auto-stretch" class="control-btn">Auto Stretch</button>
+    </div>
+  `;
+}
+
+/**
+ * Read the nodata controls; null when nothing extra is hidden
+ */
+function readNodataControls(): NodataRules | null {
+  const valuesInput = document.getElementById('nodata-values') as HTMLInputElement | null;
+  const minInput = document.getElementById('nodata-range-min') as HTMLInputElement | null;
+  const maxInput = document.getElementById('nodata-range-max') as HTMLInputElement | null;
+
+  const values = (valuesInput?.value ?? '')
+    .split(/[\s,]+/)
+    .filter(v => v !== '')
+    .map(Number)
+    .filter(Number.isFinite);
+  const min = parseFloat(minInput?.value ?? '');
+  const max = parseFloat(maxInput?.value ?? '');
+  const range: [number, number] | undefined =
+    Number.isFinite(min) && Number.isFinite(max)
+      ? [Math.min(min, max), Math.max(min, max)]
+      : undefined;
+
+  if (values.length === 0 && !range) return null;
+  return { values, range };
+}
+
+/**
+ * Value range of the grayscale stretch sliders: the band statistics, or the
+ * natural range of a derived terrain value
+ */
+function stretchRange(layer: RasterLayer): { min: number; max: number } {
+  if (layer.terrainDerivative) {
+    const [min, max] = TERRAIN_DERIVATIVE_RANGES[layer.terrainDerivative];
+    return { min, max };
+  }
+  if (layer.complexPart === 'phase') {
+    const [min, max] = PHASE_RANGE;
+    return { min, max };
+  }
+  const stats = layer.band_stats[layer.band - 1] || { min: 0, max: 255 };
+  // Band stats are amplitudes; other complex parts have their own range
+  if (layer.complexPart && layer.complexPart !== 'amplitude') {
+    return {
+      min: Math.min(stats.min, layer.stretch.min),
+      max: Math.max(stats.max, layer.stretch.max),
+    };
+  }
+  return stats;
+}
+
+/** Escape text from a file (dimension names, labels) for use in markup */
+function escapeHtml(text: string): string {
+  const div = document.createElement('div');
+  div.textContent = text;
+  return div.innerHTML.replace(/"/g, '&quot;');
+}
+
+/**
+ * Render a slider per time/depth/level dimension, which replaces the band
+ * list for multidimensional rasters
+ */
+function renderDimensionControls(layer: RasterLayer): string {
+  return (layer.dimensions ?? [])
+    .map(
+      d => `
+    <div class="control-section">
+      <label>${escapeHtml(d.name)} <span class="value-display">${escapeHtml(dimensionLabel(layer, d))}</span></label>
+      <input type="range" class="dimension-slider" data-dimension="${escapeHtml(d.name)}" min="0" max="${d.values.length - 1}" value="${dimensionIndex(layer, d)}" step="1">
+    </div>`
+    )
+    .join('');
+}
+
 /**
  * Render grayscale stretch controls
  */
 function renderGrayscaleControls(layer: RasterLayer, bandOptions: string): string {
-  const bandStats = layer.band_stats[layer.band - 1] || { min: 0, max: 255 };
-  return `
+  const bandStats = stretchRange(layer);
+  const colormap = layer.colormap;
+  const namedColormap = colormap?.type === 'named' ? colormap.name : null;
+  const colormapOptions = [
+    `<option value="" ${!colormap ? 'selected' : ''}>Grayscale</option>`,
+    ...NAMED_COLORMAPS.map(
+      name => `<option value="${name}" ${namedColormap === name ? 'selected' : ''}>${name}</option>`
+    ),
+    colormap && colormap.type !== 'named' ? '<option value="custom" selected>Custom</option>' : '',
+  ].join('');
+  const inverted = colormap?.type === 'named' && colormap.invert ? 'checked' : '';
+  const hillshade = layer.hillshade ?? DEFAULT_HILLSHADE;
+  const hillshadeModes = HILLSHADE_MODES.map(
+    mode => `<option value="${mode}" ${hillshade.mode === mode ? 'selected' : ''}>${mode}</option>`
+  ).join('');
+  const terrainOptions = TERRAIN_OPTIONS.map(
+    ([value, label]) =>
+      `<option value="${value}" ${(layer.terrainDerivative ?? '') === value ? 'selected' : ''}>${label}</option>`
+  ).join('');
+  // Terrain modes need ground distances, so they are only offered for georeferenced rasters
+  const hillshadeControls = layer.is_georeferenced
+    ? `
+    <div class="control-section">
+      <label>Display</label>
+      <select id="terrain-derivative">${terrainOptions}</select>
+    </div>
+    <div class="control-section">
+      <label class="stretch-toggle-label">
+        <input type="checkbox" id="hillshade-toggle" ${layer.hillshade ? 'checked' : ''}>
+        Hillshade
+      </label>
+      <select id="hillshade-mode" ${layer.hillshade ? '' : 'disabled'}>${hillshadeModes}</select>
+      <label>Azimuth <span class="value-display">${hillshade.azimuth.toFixed(0)}°</span></label>
+      <input type="range" id="hillshade-azimuth" min="0" max="360" value="${hillshade.azimuth}" step="1" ${layer.hillshade ? '' : 'disabled'}>
+      <label>Altitude <span class="value-display">${hillshade.altitude.toFixed(0)}°</span></label>
+      <input type="range" id="hillshade-altitude" min="0" max="90" value="${hillshade.altitude}" step="1" ${layer.hillshade ? '' : 'disabled'}>
+      <label>Z-factor</label>
+      <input type="number" id="hillshade-z-factor" min="0" value="${hillshade.z_factor}" step="0.1" ${layer.hillshade ? '' : 'disabled'}>
+    </div>`
+    : '';
+
+  const bandControls = layer.dimensions?.length
+    ? renderDimensionControls(layer)
+    : `
     <div class="control-section">
       <label>Band</label>
       <select id="band-select">${bandOptions}</select>
-    </div>
+    </div>`;
+  const complexPart = layer.complexPart ?? 'amplitude';
+  const complexOptions = COMPLEX_PARTS.map(
+    ({ value, label }) =>
+      `<option value="${value}" ${complexPart === value ? 'selected' : ''}>${label}</option>`
+  ).join('');
+  const complexControls = layer.is_complex
+    ? `
+    <div class="control-section">
+      <label>Complex</label>
+      <select id="complex-part">${complexOptions}</select>
+    </div>`
+    : '';
+
+  return `${bandControls}${complexControls}
     <div class="control-section">
       <label>Min <span class="value-display">${layer.stretch.min.toFixed(1)}</span></label>
       <input type="range" id="stretch-min" min="${bandStats.min}" max="${bandStats.max}" value="${layer.stretch.min}" step="0.1">
@@ -605,11 +993,40 @@ function renderGrayscaleControls(layer: RasterLayer, bandOptions: string): strin
       <label>Gamma <span class="value-display">${layer.stretch.gamma.toFixed(2)}</span></label>
       <input type="range" id="stretch-gamma" min="0.1" max="3.0" value="${layer.stretch.gamma}" step="0.05">
     </div>
-    <button id="auto-stretch" class="control-btn">Auto Stretch</button>
+    <div class="control-section">
+      <label>Contrast</label>
+      <select id="stretch-mode">${stretchModeOptions(layer.stretch.mode)}</select>
+    </div>
+    <div class="control-section">
+      <label>Colormap</label>
+      <select id="colormap-select">${colormapOptions}</select>
+      <label class="stretch-toggle-label">
+        <input type="checkbox" id="colormap-invert" ${inverted} ${namedColormap ? '' : 'disabled'}>
+        Invert
+      </label>
+    </div>${hillshadeControls}
     <button id="show-histogram" class="control-btn">Show Histogram</button>
   `;
 }
 
+/**
+ * Render the raster calculator, plus GeoTIFF export for band-math layers
+ */
+function renderBandMathControls(layer: RasterLayer): string {
+  const exportButton = layer.bandMathExpression
+    ? '<button id="export-geotiff" class="control-btn">Export GeoTIFF</button>'
+    : '';
+
+  return `
+    <div class="control-section">
+      <label>Band Math</label>
+      <input type="text" id="band-math-expression" placeholder="(b2 - b1) / (b2 + b1)">
+      <button id="band-math-create" class="control-btn">Create Layer</button>
+      ${exportButton}
+    </div>
+  `;
+}
+
 /**
  * Render cross-layer RGB controls
  */
@@ -666,16 +1083,26 @@ function renderCrossLayerRgbControls(manager: LayerManagerWithUI, layer: RasterL
  * Render RGB band and stretch controls
  */
 function renderRgbControls(layer: RasterLayerWithUI): string {
+  // Multidimensional rasters pick the channels along their last dimension
+  const lastDimension = layer.dimensions?.[layer.dimensions.length - 1];
+  const channelLabel = (i: number): string =>
+    lastDimension
+      ? escapeHtml(`${lastDimension.name} ${lastDimension.labels[i] ?? i}`)
+      : `Band ${i + 1}`;
   const rgbBandOptions = (selected: number): string =>
     Array.from(
-      { length: layer.bands },
-      (_, i) =>
-        `<option value="${i + 1}" ${selected === i + 1 ? 'selected' : ''}>Band ${i + 1}</option>`
+      { length: lastDimension?.values.length ?? layer.bands },
+      (_, i) => {
+        const selectedAttr = selected === i + 1 ? 'selected' : '';
+        return `<option value="${i + 1}" ${selectedAttr}>${channelLabel(i)}</option>`;
+      }
     ).join('');
 
-  const rStats = layer.band_stats[layer.rgbBands.r - 1] || { min: 0, max: 255 };
-  const gStats = layer.band_stats[layer.rgbBands.g - 1] || { min: 0, max: 255 };
-  const bStats = layer.band_stats[layer.rgbBands.b - 1] || { min: 0, max: 255 };
+  const statsFor = (band: number) =>
+    layer.band_stats[rgbSourceBand(layer, band) - 1] || { min: 0, max: 255 };
+  const rStats = statsFor(layer.rgbBands.r);
+  const gStats = statsFor(layer.rgbBands.g);
+  const bStats = statsFor(layer.rgbBands.b);
   const showStretch = layer.showRgbStretch || false;
 
   return `
@@ -696,6 +1123,10 @@ function renderRgbControls(layer: RasterLayerWithUI): string {
             <input type="range" id="rgb-r-max" min="${rStats.min}" max="${rStats.max}" value="${layer.rgbStretch.r.max}" step="0.1">
             <span class="mini-value">${layer.rgbStretch.r.max.toFixed(0)}</span>
           </div>
+          <div class="mini-control">
+            <span class="mini-label">Mode</span>
+            <select id="rgb-r-mode">${stretchModeOptions(layer.rgbStretch.r.mode)}</select>
+          </div>
         </div>
       </div>
       <div class="rgb-channel-group">
@@ -714,6 +1145,10 @@ function renderRgbControls(layer: RasterLayerWithUI): string {
             <input type="range" id="rgb-g-max" min="${gStats.min}" max="${gStats.max}" value="${layer.rgbStretch.g.max}" step="0.1">
             <span class="mini-value">${layer.rgbStretch.g.max.toFixed(0)}</span>
           </div>
+          <div class="mini-control">
+            <span class="mini-label">Mode</span>
+            <select id="rgb-g-mode">${stretchModeOptions(layer.rgbStretch.g.mode)}</select>
+          </div>
         </div>
       </div>
       <div class="rgb-channel-group">
@@ -732,6 +1167,10 @@ function renderRgbControls(layer: RasterLayerWithUI): string {
             <input type="range" id="rgb-b-max" min="${bStats.min}" max="${bStats.max}" value="${layer.rgbStretch.b.max}" step="0.1">
             <span class="mini-value">${layer.rgbStretch.b.max.toFixed(0)}</span>
           </div>
+          <div class="mini-control">
+            <span class="mini-label">Mode</span>
+            <select id="rgb-b-mode">${stretchModeOptions(layer.rgbStretch.b.mode)}</select>
+          </div>
         </div>
       </div>
     </div>
@@ -741,7 +1180,6 @@ function renderRgbControls(layer: RasterLayerWithUI): string {
         Show Stretch Controls
       </label>
     </div>
-    <button id="auto-stretch-rgb" class="control-btn">Auto Stretch All</button>
     ${!layer.isComposition ? '<button id="create-rgb-layer" class="control-btn" style="margin-top: 8px;">Create RGB Layer</button>' : ''}
   `;
 }
@@ -760,6 +1198,96 @@ function attachRasterEventListeners(manager: LayerManagerWithUI, layer: RasterLa
     });
   }
 
+  const resamplingSelect = document.getElementById('resampling-select') as HTMLSelectElement | null;
+  if (resamplingSelect) {
+    resamplingSelect.addEventListener('change', e => {
+      const target = e.target as HTMLSelectElement;
+      if (manager.selectedLayerId) {
+        manager.setLayerResampling(manager.selectedLayerId, target.value as Resampling);
+      }
+    });
+  }
+
+  const tileSizeSelect = document.getElementById('tile-size-select') as HTMLSelectElement | null;
+  const hidpiToggle = document.getElementById('hidpi-toggle') as HTMLInputElement | null;
+  const applyTileSize = (): void => {
+    if (manager.selectedLayerId) {
+      manager.setLayerTileSize(
+        manager.selectedLayerId,
+        Number(tileSizeSelect?.value ?? DEFAULT_TILE_SIZE),
+        hidpiToggle?.checked ?? false
+      );
+    }
+  };
+  tileSizeSelect?.addEventListener('change', applyTileSize);
+  hidpiToggle?.addEventListener('change', applyTileSize);
+
+  const formatSelect = document.getElementById('tile-format-select') as HTMLSelectElement | null;
+  const qualityInput = document.getElementById('tile-format-quality') as HTMLInputElement | null;
+  const applyTileFormat = (): void => {
+    if (!manager.selectedLayerId) return;
+    const option = formatSelect?.value ?? 'png';
+    const lossy = isLossyTileFormat(option);
+    document.getElementById('tile-format-quality-row')?.classList.toggle('hidden', !lossy);
+    const quality = Math.min(Math.max(Number(qualityInput?.value) || 85, 1), 100);
+    let format: TileFormat = { type: 'png' };
+    if (option === 'jpeg') {
+      format = { type: 'jpeg', quality };
+    } else if (option.startsWith('webp')) {
+      format = { type: 'webp', quality: lossy ? quality : undefined };
+    }
+    manager.setLayerTileFormat(manager.selectedLayerId, format);
+  };
+  formatSelect?.addEventListener('change', applyTileFormat);
+  qualityInput?.addEventListener('change', applyTileFormat);
+
+  document.getElementById('data-tiles-toggle')?.addEventListener('change', e => {
+    const target = e.target as HTMLInputElement;
+    if (manager.selectedLayerId) {
+      manager.setLayerDataTiles(manager.selectedLayerId, target.checked);
+    }
+  });
+
+  document.getElementById('nodata-apply')?.addEventListener('click', () => {
+    if (manager.selectedLayerId) {
+      manager.setLayerNodataRules(manager.selectedLayerId, readNodataControls());
+    }
+  });
+
+  const overviewLocation = document.getElementById('overview-location') as HTMLSelectElement | null;
+  const overviewResampling = document.getElementById(
+    'overview-resampling'
+  ) as HTMLSelectElement | null;
+  document.getElementById('build-overviews')?.addEventListener('click', () => {
+    if (!manager.selectedLayerId) return;
+    if (layer.buildingOverviews) {
+      manager.cancelLayerOverviews(manager.selectedLayerId);
+      return;
+    }
+    manager.buildLayerOverviews(
+      manager.selectedLayerId,
+      (overviewLocation?.value ?? 'external') as OverviewLocation,
+      (overviewResampling?.value ?? 'average') as OverviewResampling
+    );
+  });
+
+  const autoStretchMethod = document.getElementById(
+    'auto-stretch-method'
+  ) as HTMLSelectElement | null;
+  const autoStretchViewport = document.getElementById(
+    'auto-stretch-viewport'
+  ) as HTMLInputElement | null;
+  document.getElementById('auto-stretch')?.addEventListener('click', () => {
+    const option =
+      AUTO_STRETCH_OPTIONS.find(([key]) => key === autoStretchMethod?.value) ??
+      AUTO_STRETCH_OPTIONS[0];
+    layer.autoStretchOption = option[0];
+    layer.autoStretchViewport = autoStretchViewport?.checked ?? false;
+    if (manager.selectedLayerId) {
+      manager.autoStretchLayer(manager.selectedLayerId, option[2], layer.autoStretchViewport);
+    }
+  });
+
   if (layer.displayMode === 'grayscale') {
     attachGrayscaleListeners(manager, layer);
   } else if (layer.displayMode === 'crossLayerRgb') {
@@ -767,6 +1295,24 @@ function attachRasterEventListeners(manager: LayerManagerWithUI, layer: RasterLa
   } else {
     attachRgbListeners(manager, layer);
   }
+
+  const bandMathInput = document.getElementById('band-math-expression') as HTMLInputElement | null;
+  const createBandMath = (): void => {
+    const expression = bandMathInput?.value.trim();
+    if (!manager.selectedLayerId || !expression) return;
+    // Errors are reported by the layer manager
+    manager.createBandMathLayer(manager.selectedLayerId, expression).catch(() => {});
+  };
+  document.getElementById('band-math-create')?.addEventListener('click', createBandMath);
+  bandMathInput?.addEventListener('keydown', e => {
+    if (e.key === 'Enter') createBandMath();
+  });
+
+  document.getElementById('export-geotiff')?.addEventListener('click', () => {
+    if (manager.selectedLayerId) {
+      manager.exportRasterLayer(manager.selectedLayerId);
+    }
+  });
 }
 
 /**
@@ -783,10 +1329,42 @@ function attachGrayscaleListeners(manager: LayerManagerWithUI, layer: RasterLaye
     });
   }
 
+  // Dimension sliders show the step while dragging and load it on release
+  document.querySelectorAll<HTMLInputElement>('.dimension-slider').forEach(slider => {
+    const dimension = layer.dimensions?.find(d => d.name === slider.dataset.dimension);
+    if (!dimension) return;
+    const valueDisplay = slider.previousElementSibling?.querySelector('.value-display');
+    slider.addEventListener('input', () => {
+      const index = parseInt(slider.value, 10);
+      const preview = { dimensionIndices: { [dimension.name]: index } };
+      if (valueDisplay) valueDisplay.textContent = dimensionLabel(preview, dimension);
+    });
+    slider.addEventListener('change', () => {
+      const index = parseInt(slider.value, 10);
+      if (manager.selectedLayerId) {
+        manager.setLayerDimension(manager.selectedLayerId, dimension.name, index);
+      }
+    });
+  });
+
+  const complexSelect = document.getElementById('complex-part') as HTMLSelectElement | null;
+  complexSelect?.addEventListener('change', () => {
+    if (manager.selectedLayerId) {
+      manager.setLayerComplexPart(manager.selectedLayerId, complexSelect.value as ComplexPart);
+    }
+  });
+
   const minSlider = document.getElementById('stretch-min') as HTMLInputElement | null;
   const maxSlider = document.getElementById('stretch-max') as HTMLInputElement | null;
   const gammaSlider = document.getElementById('stretch-gamma') as HTMLInputElement | null;
 
+  document.getElementById('stretch-mode')?.addEventListener('change', e => {
+    const target = e.target as HTMLSelectElement;
+    if (manager.selectedLayerId) {
+      manager.setLayerStretchMode(manager.selectedLayerId, { type: target.value } as StretchMode);
+    }
+  });
+
   if (minSlider) {
     minSlider.addEventListener('input', e => {
       const target = e.target as HTMLInputElement;
@@ -847,16 +1425,62 @@ function attachGrayscaleListeners(manager: LayerManagerWithUI, layer: RasterLaye
     });
   }
 
-  const autoStretchBtn = document.getElementById('auto-stretch');
-  if (autoStretchBtn) {
-    autoStretchBtn.addEventListener('click', () => {
-      const bandStats = layer.band_stats[layer.band - 1];
-      if (bandStats && manager.selectedLayerId) {
-        manager.setLayerStretch(manager.selectedLayerId, bandStats.min, bandStats.max, 1.0);
-        manager.updateDynamicControls();
-      }
-    });
-  }
+  const colormapSelect = document.getElementById('colormap-select') as HTMLSelectElement | null;
+  const colormapInvert = document.getElementById('colormap-invert') as HTMLInputElement | null;
+  const applyColormap = (): void => {
+    if (!manager.selectedLayerId || !colormapSelect) return;
+    const name = colormapSelect.value;
+    if (name === 'custom') return;
+    manager.setLayerColormap(
+      manager.selectedLayerId,
+      name ? { type: 'named', name, invert: colormapInvert?.checked ?? false } : null
+    );
+    manager.updateDynamicControls();
+  };
+  colormapSelect?.addEventListener('change', applyColormap);
+  colormapInvert?.addEventListener('change', applyColormap);
+
+  const hillshadeToggle = document.getElementById('hillshade-toggle') as HTMLInputElement | null;
+  const hillshadeMode = document.getElementById('hillshade-mode') as HTMLSelectElement | null;
+  const hillshadeAzimuth = document.getElementById('hillshade-azimuth') as HTMLInputElement | null;
+  const hillshadeAltitude = document.getElementById('hillshade-altitude') as HTMLInputElement | null;
+  const hillshadeZFactor = document.getElementById('hillshade-z-factor') as HTMLInputElement | null;
+  const readNumber = (input: HTMLInputElement | null, fallback: number): number => {
+    const value = parseFloat(input?.value ?? '');
+    return Number.isFinite(value) ? value : fallback;
+  };
+  const applyHillshade = (): void => {
+    if (!manager.selectedLayerId || !hillshadeToggle) return;
+    manager.setLayerHillshade(
+      manager.selectedLayerId,
+      hillshadeToggle.checked
+        ? {
+            azimuth: readNumber(hillshadeAzimuth, DEFAULT_HILLSHADE.azimuth),
+            altitude: readNumber(hillshadeAltitude, DEFAULT_HILLSHADE.altitude),
+            z_factor: readNumber(hillshadeZFactor, DEFAULT_HILLSHADE.z_factor),
+            mode: (hillshadeMode?.value as HillshadeMode | undefined) ?? DEFAULT_HILLSHADE.mode,
+          }
+        : null
+    );
+    manager.updateDynamicControls();
+  };
+  const terrainDerivative = document.getElementById(
+    'terrain-derivative'
+  ) as HTMLSelectElement | null;
+  terrainDerivative?.addEventListener('change', () => {
+    if (!manager.selectedLayerId) return;
+    manager.setLayerTerrainDerivative(
+      manager.selectedLayerId,
+      (terrainDerivative.value as TerrainDerivative) || null
+    );
+    manager.updateDynamicControls();
+  });
+
+  hillshadeToggle?.addEventListener('change', applyHillshade);
+  hillshadeMode?.addEventListener('change', applyHillshade);
+  hillshadeAzimuth?.addEventListener('change', applyHillshade);
+  hillshadeAltitude?.addEventListener('change', applyHillshade);
+  hillshadeZFactor?.addEventListener('change', applyHillshade);
 
   const showHistogramBtn = document.getElementById('show-histogram');
   if (showHistogramBtn) {
@@ -884,6 +1508,8 @@ function attachCrossLayerRgbListeners(manager: LayerManagerWithUI, layer: Raster
       const bLayerId = bSelect?.value;
 
       if (rLayerId && gLayerId && bLayerId) {
+        // New sources: stretch from their band stats until auto stretched
+        layer.crossLayerStretch = undefined;
         layer.crossLayerRgb = {
           rLayerId,
           rBand: 1,
@@ -942,6 +1568,14 @@ function attachRgbListeners(manager: LayerManagerWithUI, layer: RasterLayerWithU
     const minSlider = document.getElementById(`rgb-${ch}-min`) as HTMLInputElement | null;
     const maxSlider = document.getElementById(`rgb-${ch}-max`) as HTMLInputElement | null;
 
+    document.getElementById(`rgb-${ch}-mode`)?.addEventListener('change', e => {
+      const target = e.target as HTMLSelectElement;
+      if (manager.selectedLayerId) {
+        const mode = { type: target.value } as StretchMode;
+        manager.setRgbStretchMode(manager.selectedLayerId, ch, mode);
+      }
+    });
+
     if (minSlider) {
       minSlider.addEventListener('input', e => {
         const target = e.target as HTMLInputElement;
@@ -983,24 +1617,6 @@ function attachRgbListeners(manager: LayerManagerWithUI, layer: RasterLayerWithU
     }
   });
 
-  const autoStretchRgbBtn = document.getElementById('auto-stretch-rgb');
-  if (autoStretchRgbBtn) {
-    autoStretchRgbBtn.addEventListener('click', () => {
-      const rStats = layer.band_stats[layer.rgbBands.r - 1];
-      const gStats = layer.band_stats[layer.rgbBands.g - 1];
-      const bStats = layer.band_stats[layer.rgbBands.b - 1];
-
-      if (rStats) layer.rgbStretch.r = { min: rStats.min, max: rStats.max, gamma: 1.0 };
-      if (gStats) layer.rgbStretch.g = { min: gStats.min, max: gStats.max, gamma: 1.0 };
-      if (bStats) layer.rgbStretch.b = { min: bStats.min, max: bStats.max, gamma: 1.0 };
-
-      if (manager.selectedLayerId) {
-        manager.refreshLayerTiles(manager.selectedLayerId);
-        manager.updateDynamicControls();
-      }
-    });
-  }
-
   const showStretchCheckbox = document.getElementById(
     'show-rgb-stretch'
   ) as HTMLInputElement | null;
@@ -4,10 +4,38 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { save } from '@tauri-apps/plugin-dialog';
 import { showToast, showError, showLoading, hideLoading } from '../notifications';
 import { setupTileProtocol } from './tile-protocol';
+import { chooseSubdatasets, type SubdatasetInfo } from './subdataset-dialog';
+import { dimensionBand, rgbSourceBand } from './dimensions';
+import { PHASE_COLORMAP, PHASE_RANGE } from './complex';
 import { logger } from '../logger';
-import type { LayerManagerInterface, RasterLayer, BandStats, DisplayMode } from './types';
+import { sourceBounds } from '../geo-utils';
+import type {
+  LayerManagerInterface,
+  RasterLayer,
+  BandStats,
+  DisplayMode,
+  Resampling,
+  Colormap,
+  HillshadeSettings,
+  TerrainDerivative,
+  NodataRules,
+  AutoStretchMethod,
+  ComplexPart,
+  OverviewLocation,
+  OverviewResampling,
+  RasterDimension,
+  StatsAccuracy,
+  ViewportRegion,
+  StretchSettings,
+  RgbStretchSettings,
+  StretchMode,
+  TileFormat,
+} from './types';
+import { DEFAULT_TILE_SIZE, TERRAIN_DERIVATIVE_RANGES } from './types';
 
 const log = logger.child('LayerManager:Raster');
 
@@ -21,6 +49,17 @@ interface RasterMetadata {
   bounds: [number, number, number, number];
   band_stats: BandStats[];
   is_georeferenced: boolean;
+  palette: [number, number, number, number][] | null;
+  dimensions: RasterDimension[];
+  is_complex: boolean;
+  overview_count: number;
+  needs_overviews: boolean;
+}
+
+/** Payload of `overview-progress` events */
+interface OverviewProgress {
+  id: string;
+  progress: number;
 }
 
 /**
@@ -41,139 +80,375 @@ export async function addRasterLayer(
     log.debug('Opened raster', { id: metadata.id, fileName, bands: metadata.bands });
     log.debug('Band stats', { band_stats: metadata.band_stats });
 
-    // Get stats for first band (default)
-    const defaultBandStats = metadata.band_stats[0] || { min: 0, max: 255 };
-
-    // Store layer info with stretch parameters
-    const layerData: RasterLayer = {
-      ...metadata,
-      visible: true,
-      opacity: 1.0,
-      type: 'raster',
-      // Display mode: 'grayscale', 'rgb', or 'crossLayerRgb'
-      displayMode: metadata.bands >= 3 ? 'rgb' : 'grayscale',
-      // Grayscale settings
-      band: 1,
-      stretch: {
-        min: defaultBandStats.min,
-        max: defaultBandStats.max,
+    showRasterLayer(manager, metadata);
+
+    showToast(`Loaded ${fileName}`, 'success', 2000);
+    return metadata;
+  } catch (error) {
+    log.error('Failed to add raster layer', { error: String(error) });
+    showError('Failed to load raster', error instanceof Error ? error : String(error));
+    throw error;
+  } finally {
+    hideLoading();
+  }
+}
+
+/**
+ * Open a raster file chosen by the user. Containers with subdatasets (netCDF
+ * variables, HDF5 arrays, multi-image TIFFs) ask which ones to open, each
+ * becoming its own layer.
+ * @param manager - The LayerManager instance
+ * @param filePath - Path to the raster file
+ * @returns Metadata of the opened layers; empty when the choice is cancelled
+ */
+export async function openRasterFile(
+  manager: LayerManagerInterface,
+  filePath: string
+): Promise<RasterMetadata[]> {
+  let subdatasets: SubdatasetInfo[] = [];
+  try {
+    subdatasets = await invoke<SubdatasetInfo[]>('list_subdatasets', { path: filePath });
+  } catch (error) {
+    // open_raster reports why the file cannot be read
+    log.debug('Failed to list subdatasets', { filePath, error: String(error) });
+  }
+  if (subdatasets.length === 0) {
+    return [await addRasterLayer(manager, filePath)];
+  }
+
+  const fileName = filePath.split('/').pop()?.split('\\').pop() || 'Unknown';
+  const chosen = await chooseSubdatasets(fileName, subdatasets);
+  if (chosen.length === 0) return [];
+
+  showLoading(`Loading ${fileName}...`);
+  try {
+    const opened: RasterMetadata[] = [];
+    for (const subdataset of chosen) {
+      const metadata = await invoke<RasterMetadata>('open_raster', { path: subdataset.name });
+      log.debug('Opened subdataset', { id: metadata.id, name: subdataset.name });
+      showRasterLayer(manager, metadata, { displayName: `${fileName}: ${subdataset.label}` });
+      opened.push(metadata);
+    }
+
+    showToast(`Loaded ${opened.length} of ${subdatasets.length} datasets`, 'success', 2000);
+    return opened;
+  } catch (error) {
+    log.error('Failed to add subdataset layer', { error: String(error) });
+    showError('Failed to load subdataset', error instanceof Error ? error : String(error));
+    throw error;
+  } finally {
+    hideLoading();
+  }
+}
+
+/**
+ * Add an opened raster to the map, select it and zoom to it
+ * @param manager - The LayerManager instance
+ * @param metadata - Metadata returned by the backend for the opened raster
+ * @param extra - Additional layer properties (e.g. display name)
+ */
+function showRasterLayer(
+  manager: LayerManagerInterface,
+  metadata: RasterMetadata,
+  extra: Partial<RasterLayer> = {}
+): void {
+  // Get stats for first band (default)
+  const defaultBandStats = metadata.band_stats[0] || { min: 0, max: 255 };
+
+  // Store layer info with stretch parameters
+  const layerData: RasterLayer = {
+    ...metadata,
+    visible: true,
+    opacity: 1.0,
+    type: 'raster',
+    // Display mode: 'grayscale', 'rgb', or 'crossLayerRgb'
+    displayMode: metadata.bands >= 3 ? 'rgb' : 'grayscale',
+    // Grayscale settings
+    band: 1,
+    stretch: {
+      min: defaultBandStats.min,
+      max: defaultBandStats.max,
+      gamma: 1.0,
+    },
+    // RGB settings
+    rgbBands: { r: 1, g: 2, b: 3 },
+    rgbStretch: {
+      r: {
+        min: metadata.band_stats[0]?.min || 0,
+        max: metadata.band_stats[0]?.max || 255,
         gamma: 1.0,
       },
-      // RGB settings
-      rgbBands: { r: 1, g: 2, b: 3 },
-      rgbStretch: {
-        r: {
-          min: metadata.band_stats[0]?.min || 0,
-          max: metadata.band_stats[0]?.max || 255,
-          gamma: 1.0,
-        },
-        g: {
-          min: metadata.band_stats[1]?.min || 0,
-          max: metadata.band_stats[1]?.max || 255,
-          gamma: 1.0,
-        },
-        b: {
-          min: metadata.band_stats[2]?.min || 0,
-          max: metadata.band_stats[2]?.max || 255,
-          gamma: 1.0,
-        },
+      g: {
+        min: metadata.band_stats[1]?.min || 0,
+        max: metadata.band_stats[1]?.max || 255,
+        gamma: 1.0,
       },
-    };
-
-    // Handle non-georeferenced images
-    if (!metadata.is_georeferenced) {
-      // Auto-disable basemap for non-georeferenced images
-      manager.mapManager.setBasemap('none');
-      const basemapSelect = document.getElementById('basemap-select') as HTMLSelectElement | null;
-      if (basemapSelect) basemapSelect.value = 'none';
-    } else {
-      // For geo-referenced images, exit pixel coordinate mode if we were in it
-      if (manager.mapManager.isPixelCoordMode()) {
-        manager.mapManager.setPixelCoordMode(false, null);
-      }
+      b: {
+        min: metadata.band_stats[2]?.min || 0,
+        max: metadata.band_stats[2]?.max || 255,
+        gamma: 1.0,
+      },
+    },
+    ...extra,
+  };
+
+  // Handle non-georeferenced images
+  if (!metadata.is_georeferenced) {
+    // Auto-disable basemap for non-georeferenced images
+    manager.mapManager.setBasemap('none');
+    const basemapSelect = document.getElementById('basemap-select') as HTMLSelectElement | null;
+    if (basemapSelect) basemapSelect.value = 'none';
+  } else {
+    // For geo-referenced images, exit pixel coordinate mode if we were in it
+    if (manager.mapManager.isPixelCoordMode()) {
+      manager.mapManager.setPixelCoordMode(false, null);
     }
+  }
 
-    manager.layers.set(metadata.id, layerData);
-
-    // Add to layer order (on top)
-    manager.layerOrder.push(metadata.id);
-
-    // Create a unique protocol for this layer's current settings
-    const protocolName = `raster-${metadata.id}`;
-    setupTileProtocol(manager, protocolName, metadata.id, layerData);
-
-    // Add to map
-    const sourceId = `raster-source-${metadata.id}`;
-    const layerId = `raster-layer-${metadata.id}`;
-
-    // For non-georeferenced images, use pseudo-geographic bounds
-    let mapBounds = metadata.bounds;
-    if (!metadata.is_georeferenced) {
-      // Scale image to fit within valid lat/lon bounds
-      const scale = 0.01;
-      const halfWidth = (metadata.width * scale) / 2;
-      const halfHeight = (metadata.height * scale) / 2;
-      const clampedHalfHeight = Math.min(halfHeight, 85);
-      mapBounds = [-halfWidth, -clampedHalfHeight, halfWidth, clampedHalfHeight];
-      // Store the scale for coordinate display
-      layerData.pixelScale = scale;
-      layerData.pixelOffset = { x: halfWidth, y: clampedHalfHeight };
-      // Update layer bounds to use map coordinates
-      layerData.bounds = mapBounds;
-      // Set pixel coordinate mode with scale info
-      manager.mapManager.setPixelCoordMode(true, {
-        width: metadata.width,
-        height: metadata.height,
-        scale,
-        offsetX: halfWidth,
-        offsetY: clampedHalfHeight,
-      });
-    }
+  manager.layers.set(metadata.id, layerData);
+
+  // Add to layer order (on top)
+  manager.layerOrder.push(metadata.id);
+
+  // Create a unique protocol for this layer's current settings
+  const protocolName = `raster-${metadata.id}`;
+  setupTileProtocol(manager, protocolName, metadata.id, layerData);
+
+  // Add to map
+  const sourceId = `raster-source-${metadata.id}`;
+  const layerId = `raster-layer-${metadata.id}`;
+
+  // For non-georeferenced images, use pseudo-geographic bounds
+  let mapBounds = metadata.bounds;
+  if (!metadata.is_georeferenced) {
+    // Scale image to fit within valid lat/lon bounds
+    const scale = 0.01;
+    const halfWidth = (metadata.width * scale) / 2;
+    const halfHeight = (metadata.height * scale) / 2;
+    const clampedHalfHeight = Math.min(halfHeight, 85);
+    mapBounds = [-halfWidth, -clampedHalfHeight, halfWidth, clampedHalfHeight];
+    // Store the scale for coordinate display
+    layerData.pixelScale = scale;
+    layerData.pixelOffset = { x: halfWidth, y: clampedHalfHeight };
+    // Update layer bounds to use map coordinates
+    layerData.bounds = mapBounds;
+    // Set pixel coordinate mode with scale info
+    manager.mapManager.setPixelCoordMode(true, {
+      width: metadata.width,
+      height: metadata.height,
+      scale,
+      offsetX: halfWidth,
+      offsetY: clampedHalfHeight,
+    });
+  }
+
+  manager.mapManager.addSource(sourceId, {
+    type: 'raster',
+    tiles: [`${protocolName}://{z}/{x}/{y}`],
+    tileSize: DEFAULT_TILE_SIZE,
+    ...sourceBounds(mapBounds),
+    minzoom: 0,
+    maxzoom: 22,
+  });
+
+  manager.mapManager.addLayer({
+    id: layerId,
+    type: 'raster',
+    source: sourceId,
+    paint: {
+      'raster-opacity': 1,
+    },
+  });
+
+  // Select this layer for controls
+  manager.selectedLayerId = metadata.id;
+
+  // Update UI
+  manager.updateLayerPanel();
+  manager.updateDynamicControls();
+
+  // Fit to layer bounds
+  if (!metadata.is_georeferenced) {
+    manager.mapManager.fitBounds([
+      [mapBounds[0], mapBounds[1]],
+      [mapBounds[2], mapBounds[3]],
+    ]);
+  } else {
+    manager.mapManager.fitBounds([
+      [metadata.bounds[0], metadata.bounds[1]],
+      [metadata.bounds[2], metadata.bounds[3]],
+    ]);
+  }
 
-    manager.mapManager.addSource(sourceId, {
-      type: 'raster',
-      tiles: [`${protocolName}://{z}/{x}/{y}`],
-      tileSize: 256,
-      bounds: mapBounds,
-      minzoom: 0,
-      maxzoom: 22,
+  if (metadata.needs_overviews) {
+    showToast(
+      'This raster has no overviews, so zoomed-out views are slow. Build them from the layer controls.',
+      'info',
+      6000
+    );
+  }
+}
+
+/**
+ * Create a layer from a band-math expression, e.g. `(b8 - b4) / (b8 + b4)`
+ * @param manager - The LayerManager instance
+ * @param id - Layer whose bands `b1`, `b2`, ... refer to (and whose grid is used)
+ * @param expression - Band-math expression
+ * @param inputs - Other layers by alias, referenced as `alias.b1`
+ * @returns Metadata of the new layer
+ */
+export async function addBandMathLayer(
+  manager: LayerManagerInterface,
+  id: string,
+  expression: string,
+  inputs: Record<string, string> = {}
+): Promise<RasterMetadata> {
+  showLoading('Computing band math...');
+  try {
+    const metadata = await invoke<RasterMetadata>('create_band_math_layer', {
+      id,
+      expression,
+      inputs,
     });
+    log.debug('Created band math layer', { id: metadata.id, expression });
 
-    manager.mapManager.addLayer({
-      id: layerId,
-      type: 'raster',
-      source: sourceId,
-      paint: {
-        'raster-opacity': 1,
-      },
+    showRasterLayer(manager, metadata, {
+      displayName: expression,
+      bandMathExpression: expression,
     });
 
-    // Select this layer for controls
-    manager.selectedLayerId = metadata.id;
+    showToast('Band math layer created', 'success', 2000);
+    return metadata;
+  } catch (error) {
+    log.error('Failed to create band math layer', { error: String(error) });
+    showError('Failed to compute band math', error instanceof Error ? error : String(error));
+    throw error;
+  } finally {
+    hideLoading();
+  }
+}
 
-    // Update UI
-    manager.updateLayerPanel();
-    manager.updateDynamicControls();
+/**
+ * Export a raster layer to a GeoTIFF chosen in a save dialog
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ */
+export async function exportRasterLayer(manager: LayerManagerInterface, id: string): Promise<void> {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
 
-    // Fit to layer bounds
-    if (!metadata.is_georeferenced) {
-      manager.mapManager.fitBounds([
-        [mapBounds[0], mapBounds[1]],
-        [mapBounds[2], mapBounds[3]],
-      ]);
+  const outputPath = await save({
+    defaultPath: 'band-math.tif',
+    filters: [{ name: 'GeoTIFF', extensions: ['tif', 'tiff'] }],
+  });
+  if (!outputPath) return;
+
+  showLoading('Exporting GeoTIFF...');
+  try {
+    await invoke('export_raster', { id, outputPath });
+    showToast('Exported GeoTIFF', 'success', 2000);
+  } catch (error) {
+    log.error('Failed to export raster', { error: String(error) });
+    showError('Failed to export raster', error instanceof Error ? error : String(error));
+  } finally {
+    hideLoading();
+  }
+}
+
+/**
+ * Build overviews for a layer's file so zoomed-out tiles stop reading it at
+ * full resolution. Progress shows in the loading indicator; the build can be
+ * stopped with `cancelLayerOverviews`.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param location - Inside the file, or a `.ovr` file beside it
+ * @param resampling - How overview pixels are computed
+ */
+export async function buildLayerOverviews(
+  manager: LayerManagerInterface,
+  id: string,
+  location: OverviewLocation,
+  resampling: OverviewResampling
+): Promise<void> {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer || layer.buildingOverviews) return;
+
+  layer.buildingOverviews = true;
+  manager.updateDynamicControls();
+  const loadingText = document.getElementById('loading-indicator')?.querySelector('span');
+  let unlisten: UnlistenFn | null = null;
+
+  showLoading('Building overviews...');
+  try {
+    unlisten = await listen<OverviewProgress>('overview-progress', event => {
+      if (event.payload.id !== id || !loadingText) return;
+      loadingText.textContent = `Building overviews (${Math.round(event.payload.progress * 100)}%)`;
+    });
+
+    const levels = await invoke<number>('build_overviews', { id, location, resampling });
+    layer.overview_count = levels;
+    layer.needs_overviews = false;
+    manager.refreshLayerTiles(id);
+    showToast(`Built ${levels} overview levels`, 'success', 2000);
+  } catch (error) {
+    if (String(error).includes('cancelled')) {
+      showToast('Overview build cancelled', 'info', 2000);
     } else {
-      manager.mapManager.fitBounds([
-        [metadata.bounds[0], metadata.bounds[1]],
-        [metadata.bounds[2], metadata.bounds[3]],
-      ]);
+      log.error('Failed to build overviews', { error: String(error) });
+      showError('Failed to build overviews', error instanceof Error ? error : String(error));
     }
+  } finally {
+    if (unlisten) unlisten();
+    hideLoading();
+    layer.buildingOverviews = false;
+    manager.updateDynamicControls();
+  }
+}
 
-    showToast(`Loaded ${fileName}`, 'success', 2000);
-    return metadata;
+/**
+ * Stop a running overview build; it removes what it wrote
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ */
+export async function cancelLayerOverviews(
+  manager: LayerManagerInterface,
+  id: string
+): Promise<void> {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer?.buildingOverviews) return;
+
+  await invoke('cancel_overviews', { id });
+}
+
+/**
+ * Compute a band's statistics with GDAL over the whole file, replacing the
+ * sampled ones. The backend stores them in the file's `.aux.xml`, so the
+ * next open reads them back instead of sampling.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param band - Band number
+ * @param accuracy - Every pixel, or overviews and a subsample
+ * @returns The statistics, or null when they could not be computed
+ */
+export async function computeBandStatistics(
+  manager: LayerManagerInterface,
+  id: string,
+  band: number,
+  accuracy: StatsAccuracy
+): Promise<BandStats | null> {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return null;
+
+  showLoading('Computing statistics...');
+  try {
+    const stats = await invoke<BandStats>('get_raster_stats', { id, band, accuracy });
+    layer.band_stats[band - 1] = stats;
+    manager.updateDynamicControls();
+    return stats;
   } catch (error) {
-    log.error('Failed to add raster layer', { error: String(error) });
-    showError('Failed to load raster', error instanceof Error ? error : String(error));
-    throw error;
+    log.error('Failed to compute statistics', { error: String(error) });
+    showError('Failed to compute statistics', error instanceof Error ? error : String(error));
+    return null;
   } finally {
     hideLoading();
   }
@@ -197,10 +472,150 @@ export function setLayerStretch(
   const layer = manager.layers.get(id) as RasterLayer | undefined;
   if (!layer) return;
 
-  layer.stretch = { min, max, gamma };
+  layer.stretch = { ...layer.stretch, min, max, gamma };
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set how a grayscale layer maps its stretch range to display levels
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param mode - Linear, histogram equalization or clipped equalization
+ */
+export function setLayerStretchMode(
+  manager: LayerManagerInterface,
+  id: string,
+  mode: StretchMode
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.stretch = { ...layer.stretch, mode };
   manager.refreshLayerTiles(id);
 }
 
+/**
+ * Current viewport as a region for the backend: an EPSG:4326 bbox for
+ * georeferenced layers, pixel coordinates for non-georeferenced ones
+ */
+export function viewportRegion(
+  manager: LayerManagerInterface,
+  layer: RasterLayer
+): ViewportRegion {
+  const bounds = manager.mapManager.map.getBounds();
+  const extent = manager.mapManager.pixelExtent;
+
+  if (!layer.is_georeferenced && extent) {
+    const scale = extent.scale || 0.01;
+    const offsetX = extent.offsetX || 0;
+    const offsetY = extent.offsetY || 0;
+    return {
+      bbox: [
+        (bounds.getWest() + offsetX) / scale,
+        (offsetY - bounds.getNorth()) / scale,
+        (bounds.getEast() + offsetX) / scale,
+        (offsetY - bounds.getSouth()) / scale,
+      ],
+      bboxCrs: 'pixel',
+    };
+  }
+
+  return {
+    bbox: [
+      Math.max(bounds.getWest(), -180),
+      Math.max(bounds.getSouth(), -90),
+      Math.min(bounds.getEast(), 180),
+      Math.min(bounds.getNorth(), 90),
+    ],
+    bboxCrs: 'epsg:4326',
+  };
+}
+
+/**
+ * New per-channel stretch ranges, keeping each channel's contrast mode
+ */
+function keepModes(
+  current: RgbStretchSettings | undefined,
+  next: RgbStretchSettings
+): RgbStretchSettings {
+  return {
+    r: { ...next.r, mode: current?.r.mode },
+    g: { ...next.g, mode: current?.g.mode },
+    b: { ...next.b, mode: current?.b.mode },
+  };
+}
+
+/**
+ * Auto stretch a layer from its sampled values, computed by the backend.
+ * Grayscale layers get a new stretch; RGB and cross-layer RGB layers get one
+ * per channel.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param method - Percentile, standard deviation or min/max
+ * @param viewportOnly - Sample only the part of the layer in view
+ */
+export async function autoStretchLayer(
+  manager: LayerManagerInterface,
+  id: string,
+  method: AutoStretchMethod,
+  viewportOnly: boolean
+): Promise<void> {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  const stretchFor = async (
+    datasetId: string,
+    bands: number[],
+    complex?: ComplexPart
+  ): Promise<StretchSettings[]> => {
+    const source = manager.layers.get(datasetId) as RasterLayer | undefined;
+    return invoke<StretchSettings[]>('compute_auto_stretch', {
+      id: datasetId,
+      bands,
+      method,
+      ...(viewportOnly && source ? viewportRegion(manager, source) : {}),
+      nodata: layer.nodataRules,
+      complex,
+    });
+  };
+
+  try {
+    if (layer.displayMode === 'crossLayerRgb' && layer.crossLayerRgb) {
+      const cross = layer.crossLayerRgb;
+      const [[r], [g], [b]] = await Promise.all([
+        stretchFor(cross.rLayerId, [cross.rBand || 1]),
+        stretchFor(cross.gLayerId, [cross.gBand || 1]),
+        stretchFor(cross.bLayerId, [cross.bBand || 1]),
+      ]);
+      // Cross-layer composition layers keep their stretch in rgbStretch
+      if (layer.isComposition) {
+        layer.rgbStretch = keepModes(layer.rgbStretch, { r, g, b });
+      } else {
+        layer.crossLayerStretch = keepModes(layer.crossLayerStretch, { r, g, b });
+      }
+    } else if (layer.displayMode === 'rgb') {
+      const datasetId = layer.sourceLayerId ?? id;
+      // Multidimensional rasters count RGB bands along their last dimension
+      const source = (manager.layers.get(datasetId) as RasterLayer | undefined) ?? layer;
+      const { r: rBand, g: gBand, b: bBand } = layer.rgbBands;
+      const bands = [rBand, gBand, bBand].map(band => rgbSourceBand(source, band));
+      const [r, g, b] = await stretchFor(datasetId, bands);
+      layer.rgbStretch = keepModes(layer.rgbStretch, { r, g, b });
+    } else {
+      // Derived slope/aspect values keep their fixed range
+      if (layer.terrainDerivative) return;
+      const [stretch] = await stretchFor(id, [layer.band], layer.complexPart);
+      layer.stretch = { ...stretch, mode: layer.stretch.mode };
+    }
+
+    manager.refreshLayerTiles(id);
+    manager.updateDynamicControls();
+  } catch (error) {
+    log.error('Auto stretch failed', { error: String(error) });
+    showError('Failed to auto stretch', error instanceof Error ? error : String(error));
+  }
+}
+
 /**
  * Set layer band
  * @param manager - The LayerManager instance
@@ -222,6 +637,63 @@ export function setLayerBand(manager: LayerManagerInterface, id: string, band: n
   manager.updateDynamicControls();
 }
 
+/**
+ * Select a position along one of a multidimensional raster's dimensions (a
+ * time step, depth or level). The stretch is kept, so colours stay comparable
+ * while scrubbing through the steps.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param name - Dimension name
+ * @param index - Index along the dimension
+ */
+export function setLayerDimension(
+  manager: LayerManagerInterface,
+  id: string,
+  name: string,
+  index: number
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer?.dimensions?.some(d => d.name === name)) return;
+
+  layer.dimensionIndices = { ...layer.dimensionIndices, [name]: index };
+  layer.band = dimensionBand(layer);
+  manager.refreshLayerTiles(id);
+  manager.updateDynamicControls();
+}
+
+/**
+ * Choose how a complex (SAR) layer is drawn. Phase gets the full -π to π
+ * range on a cyclic colormap; the other parts are auto stretched, since
+ * amplitude, intensity and dB values have very different ranges.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param part - Amplitude, intensity, dB, phase, real or imaginary
+ */
+export async function setLayerComplexPart(
+  manager: LayerManagerInterface,
+  id: string,
+  part: ComplexPart
+): Promise<void> {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer?.is_complex) return;
+
+  const wasPhase = layer.complexPart === 'phase';
+  layer.complexPart = part;
+
+  if (part === 'phase') {
+    const [min, max] = PHASE_RANGE;
+    layer.stretch = { min, max, gamma: 1.0 };
+    layer.colormap = { type: 'named', name: PHASE_COLORMAP };
+    manager.refreshLayerTiles(id);
+    manager.updateDynamicControls();
+    return;
+  }
+
+  // The cyclic colormap only suits phase
+  if (wasPhase) layer.colormap = undefined;
+  await autoStretchLayer(manager, id, { type: 'percentile', low: 2, high: 98 }, false);
+}
+
 /**
  * Set layer display mode
  * @param manager - The LayerManager instance
@@ -266,9 +738,12 @@ export function setRgbBands(
   const gStats = layer.band_stats[g - 1];
   const bStats = layer.band_stats[b - 1];
 
-  if (rStats) layer.rgbStretch.r = { min: rStats.min, max: rStats.max, gamma: 1.0 };
-  if (gStats) layer.rgbStretch.g = { min: gStats.min, max: gStats.max, gamma: 1.0 };
-  if (bStats) layer.rgbStretch.b = { min: bStats.min, max: bStats.max, gamma: 1.0 };
+  if (rStats)
+    layer.rgbStretch.r = { ...layer.rgbStretch.r, min: rStats.min, max: rStats.max, gamma: 1.0 };
+  if (gStats)
+    layer.rgbStretch.g = { ...layer.rgbStretch.g, min: gStats.min, max: gStats.max, gamma: 1.0 };
+  if (bStats)
+    layer.rgbStretch.b = { ...layer.rgbStretch.b, min: bStats.min, max: bStats.max, gamma: 1.0 };
 
   manager.refreshLayerTiles(id);
   manager.updateDynamicControls();
@@ -294,10 +769,216 @@ export function setRgbStretch(
   const layer = manager.layers.get(id) as RasterLayer | undefined;
   if (!layer) return;
 
-  layer.rgbStretch[channel] = { min, max, gamma };
+  layer.rgbStretch[channel] = { ...layer.rgbStretch[channel], min, max, gamma };
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set how one RGB channel maps its stretch range to display levels
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param channel - Channel ('r', 'g', 'b')
+ * @param mode - Linear, histogram equalization or clipped equalization
+ */
+export function setRgbStretchMode(
+  manager: LayerManagerInterface,
+  id: string,
+  channel: 'r' | 'g' | 'b',
+  mode: StretchMode
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.rgbStretch[channel] = { ...layer.rgbStretch[channel], mode };
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set the tile resampling algorithm for a layer
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param resampling - Resampling algorithm
+ */
+export function setLayerResampling(
+  manager: LayerManagerInterface,
+  id: string,
+  resampling: Resampling
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.resampling = resampling;
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set the colormap used for grayscale display
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param colormap - Colormap, or null for plain grayscale
+ */
+export function setLayerColormap(
+  manager: LayerManagerInterface,
+  id: string,
+  colormap: Colormap | null
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.colormap = colormap ?? undefined;
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set hillshade rendering for a DEM layer
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param hillshade - Hillshade settings, or null to turn shading off
+ */
+export function setLayerHillshade(
+  manager: LayerManagerInterface,
+  id: string,
+  hillshade: HillshadeSettings | null
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.hillshade = hillshade ?? undefined;
   manager.refreshLayerTiles(id);
 }
 
+/**
+ * Display slope or aspect derived from a DEM instead of its elevation
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param derivative - Derived value, or null for the elevation itself
+ */
+export function setLayerTerrainDerivative(
+  manager: LayerManagerInterface,
+  id: string,
+  derivative: TerrainDerivative | null
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.terrainDerivative = derivative ?? undefined;
+
+  // The elevation stretch means nothing for slopes or angles, so reset it
+  const stats = layer.band_stats[layer.band - 1];
+  const [min, max] = derivative
+    ? TERRAIN_DERIVATIVE_RANGES[derivative]
+    : [stats?.min ?? 0, stats?.max ?? 255];
+  layer.stretch = { min, max, gamma: 1.0 };
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set the extra values a layer treats as nodata in tiles, stats and queries
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param rules - Values and/or an inclusive range to hide, or null for none
+ */
+export function setLayerNodataRules(
+  manager: LayerManagerInterface,
+  id: string,
+  rules: NodataRules | null
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.nodataRules = rules ?? undefined;
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Switch a layer between backend-rendered tiles and Float32 data tiles stretched
+ * in the frontend. Data tiles are only used for linear stretches without a
+ * colormap or terrain shading; other settings keep rendering in the backend.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param enabled - Whether to use data tiles
+ */
+export function setLayerDataTiles(
+  manager: LayerManagerInterface,
+  id: string,
+  enabled: boolean
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.dataTiles = enabled || undefined;
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set a raster layer's tile size and whether tiles render at the device pixel
+ * ratio. Larger tiles mean fewer requests; HiDPI tiles keep imagery sharp on
+ * high-density screens.
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param tileSize - MapLibre tile size in CSS pixels (256, 512, ...)
+ * @param hidpi - Whether to render tiles at the device pixel ratio
+ */
+export function setLayerTileSize(
+  manager: LayerManagerInterface,
+  id: string,
+  tileSize: number,
+  hidpi: boolean
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  const resized = (layer.tileSize ?? DEFAULT_TILE_SIZE) !== tileSize;
+  layer.tileSize = tileSize === DEFAULT_TILE_SIZE ? undefined : tileSize;
+  layer.hidpi = hidpi || undefined;
+
+  if (resized) {
+    rebuildRasterSource(manager, id, tileSize);
+  }
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Set the image format a raster layer's tiles are encoded in
+ * @param manager - The LayerManager instance
+ * @param id - Layer ID
+ * @param format - WebP (lossy with a quality) or JPEG, or null for PNG
+ */
+export function setLayerTileFormat(
+  manager: LayerManagerInterface,
+  id: string,
+  format: TileFormat | null
+): void {
+  const layer = manager.layers.get(id) as RasterLayer | undefined;
+  if (!layer) return;
+
+  layer.tileFormat = format?.type === 'png' ? undefined : (format ?? undefined);
+  manager.refreshLayerTiles(id);
+}
+
+/**
+ * Re-create a raster layer's MapLibre source with a new tile size, which
+ * MapLibre can't change in place. The layer keeps its position, paint and
+ * layout properties.
+ */
+function rebuildRasterSource(manager: LayerManagerInterface, id: string, tileSize: number): void {
+  const { map } = manager.mapManager;
+  const sourceId = `raster-source-${id}`;
+  const layerId = `raster-layer-${id}`;
+
+  const style = map.getStyle();
+  const source = style.sources[sourceId] as maplibregl.RasterSourceSpecification | undefined;
+  const index = style.layers.findIndex(l => l.id === layerId);
+  if (!source || index < 0) return;
+
+  const layerSpec = style.layers[index];
+  const beforeId = style.layers[index + 1]?.id;
+  map.removeLayer(layerId);
+  map.removeSource(sourceId);
+  map.addSource(sourceId, { ...source, tileSize });
+  map.addLayer(layerSpec, beforeId);
+}
+
 /**
  * Refresh layer tiles (force reload)
  * @param manager - The LayerManager instance
@@ -0,0 +1,110 @@
+/**
+ * Dialog for choosing which subdatasets of a container file to open
+ * @module layer-manager/subdataset-dialog
+ */
+
+/** Subdataset listed by the backend's list_subdatasets command */
+export interface SubdatasetInfo {
+  /** GDAL connection string, opened like a file path */
+  name: string;
+  description: string;
+  /** Variable or array path, or the description */
+  label: string;
+  /** Array dimensions, slowest varying first; empty when unknown */
+  dimensions: number[];
+  data_type: string | null;
+}
+
+/**
+ * Summary shown under a subdataset's label
+ * @param subdataset - Subdataset to describe
+ * @returns Dimensions and data type, or the driver's description
+ */
+export function describeSubdataset(subdataset: SubdatasetInfo): string {
+  const parts: string[] = [];
+  if (subdataset.dimensions.length > 0) parts.push(subdataset.dimensions.join(' × '));
+  if (subdataset.data_type) parts.push(subdataset.data_type);
+  return parts.length > 0 ? parts.join(', ') : subdataset.description;
+}
+
+/**
+ * Ask which subdatasets to open as layers
+ * @param fileName - Container file name, shown in the title
+ * @param subdatasets - Subdatasets listed for the file
+ * @returns The chosen subdatasets in list order; empty when cancelled
+ */
+export function chooseSubdatasets(
+  fileName: string,
+  subdatasets: SubdatasetInfo[]
+): Promise<SubdatasetInfo[]> {
+  return new Promise(resolve => {
+    const dialog = document.createElement('div');
+    dialog.id = 'subdataset-dialog';
+    dialog.className = 'modal-dialog';
+    dialog.innerHTML = `
+      <div class="modal-content">
+        <div class="modal-header">
+          <h4></h4>
+          <button class="modal-close" data-action="cancel">&times;</button>
+        </div>
+        <div class="modal-body">
+          <p class="form-help">This file holds several datasets. Choose which to open.</p>
+          <div class="subdataset-list"></div>
+        </div>
+        <div class="modal-footer">
+          <button class="btn btn-secondary" data-action="cancel">Cancel</button>
+          <button class="btn btn-primary" data-action="open">Open</button>
+        </div>
+      </div>
+    `;
+    dialog.querySelector('h4')!.textContent = fileName;
+
+    // Labels come from the file, so they are set as text rather than markup
+    const list = dialog.querySelector('.subdataset-list')!;
+    const checkboxes = subdatasets.map((subdataset, i) => {
+      const item = document.createElement('label');
+      item.className = 'subdataset-item';
+      const checkbox = document.createElement('input');
+      checkbox.type = 'checkbox';
+      checkbox.checked = i === 0;
+      const label = document.createElement('span');
+      label.className = 'subdataset-label';
+      label.textContent = subdataset.label;
+      const details = document.createElement('span');
+      details.className = 'subdataset-details';
+      details.textContent = describeSubdataset(subdataset);
+      item.append(checkbox, label, details);
+      list.appendChild(item);
+      return checkbox;
+    });
+
+    const openBtn = dialog.querySelector<HTMLButtonElement>('[data-action="open"]')!;
+    const updateOpenBtn = (): void => {
+      openBtn.disabled = !checkboxes.some(c => c.checked);
+    };
+    checkboxes.forEach(c => c.addEventListener('change', updateOpenBtn));
+
+    const close = (chosen: SubdatasetInfo[]): void => {
+      document.removeEventListener('keydown', onKeydown);
+      dialog.remove();
+      resolve(chosen);
+    };
+    const onKeydown = (e: KeyboardEvent): void => {
+      if (e.key === 'Escape') close([]);
+    };
+
+    openBtn.addEventListener('click', () => {
+      close(subdatasets.filter((_, i) => checkboxes[i].checked));
+    });
+    dialog.querySelectorAll('[data-action="cancel"]').forEach(btn => {
+      btn.addEventListener('click', () => close([]));
+    });
+    dialog.addEventListener('click', e => {
+      if (e.target === dialog) close([]);
+    });
+    document.addEventListener('keydown', onKeydown);
+
+    document.body.appendChild(dialog);
+    openBtn.focus();
+  });
+}
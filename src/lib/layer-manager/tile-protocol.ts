@@ -10,11 +10,21 @@ import {
   type LayerManagerInterface,
   type RasterLayer,
   type Layer,
+  type StretchSettings,
 } from './types';
+import { decodeDataTile, renderDataTile, type DataTile } from './data-tile';
+import { dimensionSlice, rgbDimensionSlice } from './dimensions';
+import { LRUCache } from '../lru-cache';
 import { logger } from '../logger';
 
 const log = logger.child('LayerManager:TileProtocol');
 
+/**
+ * Decoded data tiles for every layer, keyed by dataset, bands, resampling, nodata
+ * rules and tile, so stretch changes re-render without going back to the backend
+ */
+const dataTileCache = new LRUCache<DataTile | null>(128);
+
 /** Protocol request parameters */
 interface ProtocolParams {
   url: string;
@@ -25,6 +35,107 @@ interface ProtocolResponse {
   data: Uint8Array;
 }
 
+/**
+ * Tile size arguments shared by every tile command: the source's tile size,
+ * scaled by the device pixel ratio for HiDPI layers
+ * @param layer - Raster layer
+ */
+function tileSizeArgs(layer: RasterLayer): { tileSize?: number; pixelRatio?: number } {
+  return {
+    tileSize: layer.tileSize,
+    pixelRatio: layer.hidpi ? window.devicePixelRatio : undefined,
+  };
+}
+
+/**
+ * Image arguments shared by the image tile commands: the tile size arguments
+ * plus the layer's tile format
+ * @param layer - Raster layer
+ */
+function tileImageArgs(layer: RasterLayer) {
+  return { ...tileSizeArgs(layer), format: layer.tileFormat };
+}
+
+const isLinear = (stretch: StretchSettings): boolean =>
+  !stretch.mode || stretch.mode.type === 'linear';
+
+/**
+ * Whether a layer's tiles are stretched in the frontend from data tiles. Colormaps,
+ * terrain shading and equalizing stretches are still rendered by the backend.
+ * @param layer - Raster layer
+ */
+export function usesDataTiles(layer: RasterLayer): boolean {
+  if (!layer.dataTiles || layer.isComposition || layer.displayMode === 'crossLayerRgb') {
+    return false;
+  }
+  if (layer.displayMode === 'rgb' && layer.bands >= 3) {
+    const { r, g, b } = layer.rgbStretch;
+    return isLinear(r) && isLinear(g) && isLinear(b);
+  }
+  return isLinear(layer.stretch) && !layer.colormap && !layer.hillshade && !layer.terrainDerivative;
+}
+
+/**
+ * Load a data tile (from the cache when possible) and stretch it with the
+ * layer's current settings
+ * @returns PNG tile data, or an empty array outside the dataset
+ */
+async function loadDataTile(
+  datasetId: string,
+  layer: RasterLayer,
+  z: number,
+  x: number,
+  y: number
+): Promise<Uint8Array> {
+  const rgb = layer.displayMode === 'rgb';
+  // With a slice, band numbers count within it; a slice of every dimension
+  // holds just the band to draw
+  const slice = rgb ? rgbDimensionSlice(layer) : dimensionSlice(layer);
+  const bands = rgb
+    ? [layer.rgbBands.r, layer.rgbBands.g, layer.rgbBands.b]
+    : [slice ? 1 : layer.band];
+  const stretches = rgb
+    ? [layer.rgbStretch.r, layer.rgbStretch.g, layer.rgbStretch.b]
+    : [layer.stretch];
+
+  const complex = rgb ? undefined : layer.complexPart;
+
+  const size = tileSizeArgs(layer);
+  const { resampling, nodataRules } = layer;
+  const key = JSON.stringify([
+    datasetId,
+    bands,
+    slice,
+    complex,
+    resampling,
+    nodataRules,
+    size,
+    z,
+    x,
+    y,
+  ]);
+  let tile = dataTileCache.get(key);
+  if (tile === undefined) {
+    const command = layer.is_georeferenced ? 'get_data_tile' : 'get_pixel_data_tile';
+    const buffer = await invoke<ArrayBuffer>(command, {
+      id: datasetId,
+      x,
+      y,
+      z,
+      bands,
+      slice,
+      resampling,
+      nodata: nodataRules,
+      complex,
+      ...size,
+    });
+    tile = decodeDataTile(buffer);
+    dataTileCache.set(key, tile);
+  }
+
+  return tile ? renderDataTile(tile, stretches) : new Uint8Array(0);
+}
+
 /**
  * Setup tile protocol for a raster layer
  * @param manager - The LayerManager instance
@@ -64,11 +175,23 @@ export function setupTileProtocol(
       }
 
       try {
-        let tileData: number[];
+        let tileData: ArrayBuffer;
+
+        if (usesDataTiles(layer)) {
+          const data = await loadDataTile(
+            datasetId,
+            layer,
+            parseInt(z, 10),
+            parseInt(x, 10),
+            parseInt(y, 10)
+          );
+          return { data };
+        }
 
         if (layer.displayMode === 'crossLayerRgb' && layer.crossLayerRgb) {
           // Cross-layer RGB: get bands from different datasets
           const cross = layer.crossLayerRgb;
+          const stretch = layer.crossLayerStretch;
           const rLayer = manager.layers.get(cross.rLayerId) as RasterLayer | undefined;
           const gLayer = manager.layers.get(cross.gLayerId) as RasterLayer | undefined;
           const bLayer = manager.layers.get(cross.bLayerId) as RasterLayer | undefined;
@@ -81,7 +204,7 @@ export function setupTileProtocol(
               ? 'get_cross_layer_pixel_rgb_tile'
               : 'get_cross_layer_rgb_tile';
 
-            tileData = await invoke<number[]>(command, {
+            tileData = await invoke<ArrayBuffer>(command, {
               redId: cross.rLayerId,
               redBand: cross.rBand,
               greenId: cross.gLayerId,
@@ -91,15 +214,21 @@ export function setupTileProtocol(
               x: parseInt(x, 10),
               y: parseInt(y, 10),
               z: parseInt(z, 10),
-              redMin: rLayer.band_stats[0]?.min || 0,
-              redMax: rLayer.band_stats[0]?.max || 255,
-              redGamma: 1.0,
-              greenMin: gLayer.band_stats[0]?.min || 0,
-              greenMax: gLayer.band_stats[0]?.max || 255,
-              greenGamma: 1.0,
-              blueMin: bLayer.band_stats[0]?.min || 0,
-              blueMax: bLayer.band_stats[0]?.max || 255,
-              blueGamma: 1.0,
+              redMin: stretch?.r.min ?? (rLayer.band_stats[0]?.min || 0),
+              redMax: stretch?.r.max ?? (rLayer.band_stats[0]?.max || 255),
+              redGamma: stretch?.r.gamma ?? 1.0,
+              redMode: stretch?.r.mode,
+              greenMin: stretch?.g.min ?? (gLayer.band_stats[0]?.min || 0),
+              greenMax: stretch?.g.max ?? (gLayer.band_stats[0]?.max || 255),
+              greenGamma: stretch?.g.gamma ?? 1.0,
+              greenMode: stretch?.g.mode,
+              blueMin: stretch?.b.min ?? (bLayer.band_stats[0]?.min || 0),
+              blueMax: stretch?.b.max ?? (bLayer.band_stats[0]?.max || 255),
+              blueGamma: stretch?.b.gamma ?? 1.0,
+              blueMode: stretch?.b.mode,
+              resampling: layer.resampling,
+              nodata: layer.nodataRules,
+              ...tileImageArgs(layer),
             });
           } else {
             // Missing layer, return empty
@@ -114,7 +243,7 @@ export function setupTileProtocol(
             isGeoreferenced: layer.is_georeferenced,
           });
           const command = layer.is_georeferenced ? 'get_rgb_tile' : 'get_pixel_rgb_tile';
-          tileData = await invoke<number[]>(command, {
+          tileData = await invoke<ArrayBuffer>(command, {
             id: datasetId,
             x: parseInt(x, 10),
             y: parseInt(y, 10),
@@ -122,40 +251,63 @@ export function setupTileProtocol(
             redBand: layer.rgbBands.r,
             greenBand: layer.rgbBands.g,
             blueBand: layer.rgbBands.b,
+            slice: rgbDimensionSlice(layer),
             redMin: layer.rgbStretch.r.min,
             redMax: layer.rgbStretch.r.max,
             redGamma: layer.rgbStretch.r.gamma,
+            redMode: layer.rgbStretch.r.mode,
             greenMin: layer.rgbStretch.g.min,
             greenMax: layer.rgbStretch.g.max,
             greenGamma: layer.rgbStretch.g.gamma,
+            greenMode: layer.rgbStretch.g.mode,
             blueMin: layer.rgbStretch.b.min,
             blueMax: layer.rgbStretch.b.max,
             blueGamma: layer.rgbStretch.b.gamma,
+            blueMode: layer.rgbStretch.b.mode,
+            resampling: layer.resampling,
+            nodata: layer.nodataRules,
+            ...tileImageArgs(layer),
           });
         } else {
           // Grayscale mode with stretch
           // Use pixel tile for non-georeferenced images
           if (!layer.is_georeferenced) {
-            tileData = await invoke<number[]>('get_pixel_tile', {
+            tileData = await invoke<ArrayBuffer>('get_pixel_tile', {
               id: datasetId,
               x: parseInt(x, 10),
               y: parseInt(y, 10),
               z: parseInt(z, 10),
               band: layer.band,
+              slice: dimensionSlice(layer),
               min: layer.stretch.min,
               max: layer.stretch.max,
               gamma: layer.stretch.gamma,
+              stretchMode: layer.stretch.mode,
+              resampling: layer.resampling,
+              colormap: layer.colormap,
+              nodata: layer.nodataRules,
+              complex: layer.complexPart,
+              ...tileImageArgs(layer),
             });
           } else {
-            tileData = await invoke<number[]>('get_tile_stretched', {
+            tileData = await invoke<ArrayBuffer>('get_tile_stretched', {
               id: datasetId,
               x: parseInt(x, 10),
               y: parseInt(y, 10),
               z: parseInt(z, 10),
               band: layer.band,
+              slice: dimensionSlice(layer),
               min: layer.stretch.min,
               max: layer.stretch.max,
               gamma: layer.stretch.gamma,
+              stretchMode: layer.stretch.mode,
+              resampling: layer.resampling,
+              colormap: layer.colormap,
+              hillshade: layer.hillshade,
+              derivative: layer.terrainDerivative,
+              nodata: layer.nodataRules,
+              complex: layer.complexPart,
+              ...tileImageArgs(layer),
             });
           }
         }
@@ -210,10 +362,11 @@ export function setupCompositionTileProtocol(
       }
 
       const { sourceLayerId } = layer;
+      const sourceLayer = manager.layers.get(sourceLayerId ?? '') as RasterLayer | undefined;
 
       try {
         // Always use RGB mode for composition layers
-        const tileData = await invoke<number[]>('get_rgb_tile', {
+        const tileData = await invoke<ArrayBuffer>('get_rgb_tile', {
           id: sourceLayerId,
           x: parseInt(x, 10),
           y: parseInt(y, 10),
@@ -221,15 +374,22 @@ export function setupCompositionTileProtocol(
           redBand: layer.rgbBands.r,
           greenBand: layer.rgbBands.g,
           blueBand: layer.rgbBands.b,
+          slice: sourceLayer && rgbDimensionSlice(sourceLayer),
           redMin: layer.rgbStretch.r.min,
           redMax: layer.rgbStretch.r.max,
           redGamma: layer.rgbStretch.r.gamma,
+          redMode: layer.rgbStretch.r.mode,
           greenMin: layer.rgbStretch.g.min,
           greenMax: layer.rgbStretch.g.max,
           greenGamma: layer.rgbStretch.g.gamma,
+          greenMode: layer.rgbStretch.g.mode,
           blueMin: layer.rgbStretch.b.min,
           blueMax: layer.rgbStretch.b.max,
           blueGamma: layer.rgbStretch.b.gamma,
+          blueMode: layer.rgbStretch.b.mode,
+          resampling: layer.resampling,
+          nodata: layer.nodataRules,
+          ...tileImageArgs(layer),
         });
 
         return { data: new Uint8Array(tileData) };
@@ -298,7 +458,7 @@ export function setupCrossLayerCompositionTileProtocol(
           ? 'get_cross_layer_pixel_rgb_tile'
           : 'get_cross_layer_rgb_tile';
 
-        const tileData = await invoke<number[]>(command, {
+        const tileData = await invoke<ArrayBuffer>(command, {
           redId: rLayerId,
           redBand: rBand || 1,
           greenId: gLayerId,
@@ -311,12 +471,18 @@ export function setupCrossLayerCompositionTileProtocol(
           redMin: layer.rgbStretch?.r?.min ?? rLayer.band_stats[0]?.min ?? 0,
           redMax: layer.rgbStretch?.r?.max ?? rLayer.band_stats[0]?.max ?? 255,
           redGamma: layer.rgbStretch?.r?.gamma ?? 1.0,
+          redMode: layer.rgbStretch?.r?.mode,
           greenMin: layer.rgbStretch?.g?.min ?? gLayer.band_stats[0]?.min ?? 0,
           greenMax: layer.rgbStretch?.g?.max ?? gLayer.band_stats[0]?.max ?? 255,
           greenGamma: layer.rgbStretch?.g?.gamma ?? 1.0,
+          greenMode: layer.rgbStretch?.g?.mode,
           blueMin: layer.rgbStretch?.b?.min ?? bLayer.band_stats[0]?.min ?? 0,
           blueMax: layer.rgbStretch?.b?.max ?? bLayer.band_stats[0]?.max ?? 255,
           blueGamma: layer.rgbStretch?.b?.gamma ?? 1.0,
+          blueMode: layer.rgbStretch?.b?.mode,
+          resampling: layer.resampling,
+          nodata: layer.nodataRules,
+          ...tileImageArgs(layer),
         });
 
         return { data: new Uint8Array(tileData) };
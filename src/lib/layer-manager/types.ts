@@ -53,15 +53,46 @@ export interface BandStats {
   max: number;
   mean?: number;
   std_dev?: number;
+  /** Valid pixels of the whole band, when computed by GDAL */
+  valid_count?: number | null;
+  /** Share of nodata pixels, 0-100 */
+  nodata_percent?: number | null;
+  /** From a subsample rather than every pixel */
+  approximate?: boolean;
+}
+
+/** How thoroughly GDAL reads a band for statistics */
+export type StatsAccuracy = 'approximate' | 'exact';
+
+/** Coordinate space of a stats or histogram region */
+export type RegionCrs = 'epsg:4326' | 'pixel';
+
+/** Part of a layer in view, as the region arguments of stats commands */
+export interface ViewportRegion {
+  bbox: [number, number, number, number];
+  bboxCrs: RegionCrs;
 }
 
 // ==================== Stretch Settings ====================
 
+/**
+ * How values between min and max map to display levels: linear, histogram
+ * equalization, or equalization of a clipped histogram (clip_limit is a
+ * multiple of the mean histogram bin count). Equalizing lookup tables are
+ * built by the backend from the whole dataset, not per region as in CLAHE.
+ */
+export type StretchMode =
+  | { type: 'linear' }
+  | { type: 'equalize' }
+  | { type: 'clipped_equalize'; clip_limit?: number };
+
 /** Stretch parameters for grayscale display */
 export interface StretchSettings {
   min: number;
   max: number;
   gamma: number;
+  /** Unset = linear */
+  mode?: StretchMode;
 }
 
 /** RGB stretch settings for each channel */
@@ -95,6 +126,115 @@ export interface CrossLayerRgbConfig {
 /** Display mode for raster layers */
 export type DisplayMode = 'grayscale' | 'rgb' | 'crossLayerRgb';
 
+/** Colour stop for a user-defined ramp (position is 0-1 within the stretch range) */
+export interface ColorStop {
+  position: number;
+  color: string;
+}
+
+/** Discrete class for a class map */
+export interface ColorClass {
+  value: number;
+  color: string;
+  label?: string;
+}
+
+/** Colormap for single-band rendering */
+export type Colormap =
+  | { type: 'named'; name: string; invert?: boolean }
+  | { type: 'stops'; stops: ColorStop[]; invert?: boolean }
+  | { type: 'classes'; classes: ColorClass[] };
+
+/** Built-in colour ramps */
+export const NAMED_COLORMAPS: readonly string[] = [
+  'viridis',
+  'magma',
+  'inferno',
+  'plasma',
+  'cividis',
+  'terrain',
+  'rdylgn',
+  'rdbu',
+  'spectral',
+  'twilight',
+  'greys',
+] as const;
+
+/** Hillshade illumination model */
+export type HillshadeMode = 'standard' | 'multidirectional' | 'combined';
+
+/** Hillshade settings for DEM layers (angles in degrees) */
+export interface HillshadeSettings {
+  azimuth: number;
+  altitude: number;
+  z_factor: number;
+  mode: HillshadeMode;
+}
+
+/** Slope/aspect rendered from a DEM in place of the elevation */
+export type TerrainDerivative = 'slope_degrees' | 'slope_percent' | 'aspect';
+
+/** Natural value range of each derived terrain value, used as its stretch */
+export const TERRAIN_DERIVATIVE_RANGES: Record<TerrainDerivative, readonly [number, number]> = {
+  slope_degrees: [0, 90],
+  slope_percent: [0, 100],
+  aspect: [0, 360],
+};
+
+/**
+ * Extra values a layer treats as nodata, on top of the file's own nodata value,
+ * mask and alpha band
+ */
+export interface NodataRules {
+  values: number[];
+  /** Inclusive [min, max] range to hide */
+  range?: [number, number];
+}
+
+/**
+ * How the backend picks an auto stretch from sampled band values: the full
+ * range, a percentile cut (0-100) or mean ± n standard deviations
+ */
+export type AutoStretchMethod =
+  | { type: 'min_max' }
+  | { type: 'percentile'; low: number; high: number }
+  | { type: 'std_dev'; n: number };
+
+/**
+ * Image format of rendered tiles. WebP is lossless and smaller than PNG, or
+ * lossy with a quality (1-100); JPEG is smallest, with transparent edge tiles
+ * sent as lossy WebP at the same quality instead.
+ */
+export type TileFormat =
+  | { type: 'png' }
+  | { type: 'webp'; quality?: number }
+  | { type: 'jpeg'; quality?: number };
+
+/** MapLibre tile size of raster sources unless a layer picks another */
+export const DEFAULT_TILE_SIZE = 256;
+
+/** Resampling algorithm used when rendering raster tiles */
+export type Resampling = 'nearest' | 'bilinear' | 'cubic' | 'lanczos' | 'average' | 'mode';
+
+/**
+ * How complex (SAR) bands are drawn: |z|, |z|², 10·log10(|z|²), arg(z) in
+ * radians, or one of the parts
+ */
+export type ComplexPart = 'amplitude' | 'intensity' | 'db' | 'phase' | 'real' | 'imaginary';
+
+/** Where built overviews are written: inside the file, or a `.ovr` beside it */
+export type OverviewLocation = 'internal' | 'external';
+
+/** How overview pixels are computed from the full-resolution ones */
+export type OverviewResampling =
+  | 'nearest'
+  | 'average'
+  | 'bilinear'
+  | 'cubic'
+  | 'lanczos'
+  | 'mode'
+  | 'gauss';
+
 /** Base layer properties shared by all layer types */
 interface BaseLayer {
   id: string;
@@ -105,6 +245,19 @@ interface BaseLayer {
   displayName?: string;
 }
 
+/**
+ * Non-spatial dimension of a multidimensional raster (time, depth, level),
+ * flattened into bands by the backend
+ */
+export interface RasterDimension {
+  name: string;
+  /** Coordinate value at each index (the index itself when the file has none) */
+  values: number[];
+  /** Display text at each index: dates for CF time axes, otherwise the value */
+  labels: string[];
+  units: string | null;
+}
+
 /** Raster layer data */
 export interface RasterLayer extends BaseLayer {
   type: 'raster';
@@ -116,12 +269,49 @@ export interface RasterLayer extends BaseLayer {
   displayMode: DisplayMode;
   /** Current band for grayscale display (1-indexed) */
   band: number;
+  /** Time/depth/level axes behind the bands, slowest varying first (empty for plain rasters) */
+  dimensions?: RasterDimension[];
+  /** Selected index of each dimension, by name (unset = 0) */
+  dimensionIndices?: Record<string, number>;
+  /** Whether the bands hold complex values (e.g. SAR single-look complex) */
+  is_complex?: boolean;
+  /** How complex bands are drawn (unset = amplitude) */
+  complexPart?: ComplexPart;
+  /** Overview levels of the file */
+  overview_count?: number;
+  /** Large local file without overviews, slow to draw when zoomed out */
+  needs_overviews?: boolean;
+  /** Whether overviews are being built for the layer */
+  buildingOverviews?: boolean;
   /** Grayscale stretch settings */
   stretch: StretchSettings;
   /** RGB band assignments */
   rgbBands: RgbBands;
   /** RGB stretch settings */
   rgbStretch: RgbStretchSettings;
+  /** Tile resampling algorithm (backend default is nearest) */
+  resampling?: Resampling;
+  /** MapLibre tile size of the layer's source, in CSS pixels (unset = 256) */
+  tileSize?: number;
+  /** Render tiles at the device pixel ratio, for sharp imagery on HiDPI screens */
+  hidpi?: boolean;
+  /** Tile image format (unset = PNG) */
+  tileFormat?: TileFormat;
+  /** Colormap for grayscale display (unset = plain grayscale) */
+  colormap?: Colormap;
+  /** Hillshade settings; when set the band is shaded as elevation */
+  hillshade?: HillshadeSettings;
+  /** Derived terrain value to display instead of the elevation */
+  terrainDerivative?: TerrainDerivative;
+  /** Expression the layer was computed from, for band-math layers */
+  bandMathExpression?: string;
+  /** Extra nodata values for tiles, stats and queries (unset = the file's own only) */
+  nodataRules?: NodataRules;
+  /**
+   * Fetch raw Float32 tiles and stretch them in the frontend, so stretch changes
+   * don't refetch. Only used for linear stretches without colormap or terrain shading.
+   */
+  dataTiles?: boolean;
   /** Whether this is a composition layer */
   isComposition?: boolean;
   /** Whether this is a cross-layer composition */
@@ -130,6 +320,8 @@ export interface RasterLayer extends BaseLayer {
   sourceLayerId?: string;
   /** Cross-layer RGB configuration */
   crossLayerRgb?: CrossLayerRgbConfig;
+  /** Per-channel stretch for cross-layer RGB display (unset = source band stats) */
+  crossLayerStretch?: RgbStretchSettings;
   /** Pixel scale for non-georeferenced images */
   pixelScale?: number;
   /** Pixel offset for non-georeferenced images */
@@ -18,6 +18,8 @@ import maplibregl, {
 } from 'maplibre-gl';
 import type { Feature, LineString } from 'geojson';
 import { showToast, showError, showLoading, hideLoading } from './notifications';
+import { dimensionSlice } from './layer-manager/dimensions';
+import type { RasterDimension } from './layer-manager/types';
 
 /**
  * Pixel extent information for non-georeferenced images
@@ -55,6 +57,10 @@ interface RasterLayer {
   type: 'raster';
   /** Whether layer is currently visible */
   visible: boolean;
+  /** Time/depth/level axes of multidimensional rasters */
+  dimensions?: RasterDimension[];
+  /** Selected index of each dimension, by name */
+  dimensionIndices?: Record<string, number>;
 }
 
 /**
@@ -410,12 +416,16 @@ export class ProfileTool {
           id: rasterLayer.id,
           pixel_coords: pixelCoords,
           num_samples: 200,
+          nodata: rasterLayer.nodataRules,
+          slice: dimensionSlice(rasterLayer),
         });
       } else {
         result = await invoke<ElevationProfileResult>('get_elevation_profile', {
           id: rasterLayer.id,
           coords: this.points,
           num_samples: 200,
+          nodata: rasterLayer.nodataRules,
+          slice: dimensionSlice(rasterLayer),
         });
       }
 
@@ -11,6 +11,7 @@ import { ask } from '@tauri-apps/plugin-dialog';
 import maplibregl from 'maplibre-gl';
 import { showToast, showError, showLoading, hideLoading } from './notifications';
 import { logger } from './logger';
+import { sourceBounds } from './geo-utils';
 import type { LayerManager } from './layer-manager/index';
 import type { MapManager } from './map-manager';
 import type { ConfigManager } from './config-manager';
@@ -2240,7 +2241,7 @@ export class StacBrowser {
       type: 'raster',
       tiles: [`${protocolName}://{z}/{x}/{y}`],
       tileSize: 256,
-      bounds: mapBounds,
+      ...sourceBounds(mapBounds),
       minzoom: 0,
       maxzoom: 22,
     });
@@ -70,7 +70,7 @@ async function setupDragAndDrop(layerManager: LayerManager): Promise<void> {
         if (VECTOR_EXTENSIONS.includes(ext)) {
           await layerManager.addVectorLayer(filePath);
         } else {
-          await layerManager.addRasterLayer(filePath);
+          await layerManager.openRasterFile(filePath);
         }
       } catch (error) {
         console.error('Failed to open dropped file:', error);
@@ -1008,7 +1008,7 @@ export async function openFileDialog(layerManager: LayerManager): Promise<void>
         if (VECTOR_EXTENSIONS.includes(ext)) {
           await layerManager.addVectorLayer(file);
         } else {
-          await layerManager.addRasterLayer(file);
+          await layerManager.openRasterFile(file);
         }
       }
     }
@@ -11,8 +11,14 @@ export interface BandStats {
   max: number;
   mean: number;
   std_dev: number;
+  valid_count: number | null; // Valid pixels of the whole band, when GDAL reports it
+  nodata_percent: number | null;
+  approximate: boolean; // From a subsample rather than every pixel
 }
 
+// Statistics computed by GDAL and stored in the file's .aux.xml
+export type StatsAccuracy = 'approximate' | 'exact';
+
 // Histogram data
 export interface HistogramData {
   band: number;
@@ -37,9 +43,64 @@ export interface RasterMetadata {
   nodata?: number;
   band_stats: BandStats[];
   is_georeferenced: boolean;
+  palette: [number, number, number, number][] | null; // RGBA colour table, indexed by value
+  dimensions: RasterDimension[]; // Time/depth/level axes behind the bands, slowest first
+  is_complex: boolean; // Band 1 holds complex (e.g. SAR) values
+  overview_count: number;
+  needs_overviews: boolean; // Large local file without overviews
+}
+
+// Non-spatial dimension of a multidimensional raster (netCDF, Zarr, HDF5)
+export interface RasterDimension {
+  name: string;
+  values: number[]; // Coordinate value at each index (the index when the file has none)
+  labels: string[]; // Dates for CF time axes, otherwise the value
+  units: string | null;
+}
+
+// How complex bands are drawn (default amplitude); phase is in radians
+export type ComplexPart = 'amplitude' | 'intensity' | 'db' | 'phase' | 'real' | 'imaginary';
+
+// Where build_overviews writes: inside the file, or a .ovr beside it (default)
+export type OverviewLocation = 'internal' | 'external';
+
+// How overview pixels are computed (default average)
+export type OverviewResampling =
+  | 'nearest'
+  | 'average'
+  | 'bilinear'
+  | 'cubic'
+  | 'lanczos'
+  | 'mode'
+  | 'gauss';
+
+// Payload of overview-progress events
+export interface OverviewProgress {
+  id: string;
+  progress: number; // 0.0 to 1.0
 }
 
+// Coordinate space of a stats or histogram bbox and polygon; defaults to
+// EPSG:4326, or pixels if not georeferenced
+export type RegionCrs = 'epsg:4326' | 'pixel';
+
+// Area for stats and histograms: rings of [x, y] (first the outline, then
+// holes), in the region's RegionCrs
+export type RegionPolygon = [number, number][][];
+
+// Position along dimensions by name: an index or the nearest coordinate value
+export type DimensionSlice = Record<string, { index: number } | { value: number }>;
+
 // Vector field definition
+// Entry of a container's SUBDATASETS metadata domain (list_subdatasets)
+export interface SubdatasetInfo {
+  name: string; // GDAL connection string, opened with open_raster
+  description: string;
+  label: string; // Variable or array path, or the description
+  dimensions: number[]; // Slowest varying first; empty when unknown
+  data_type: string | null;
+}
+
 export interface VectorField {
   name: string;
   field_type: string;
@@ -136,12 +197,83 @@ export interface StacSearchResult {
   };
 }
 
+// Tile image format; JPEG tiles with transparent pixels are sent as lossless WebP
+export type TileFormat =
+  | { type: 'png' }
+  | { type: 'webp'; quality?: number }
+  | { type: 'jpeg'; quality?: number };
+
+// Resampling algorithm for tile rendering
+export type Resampling = 'nearest' | 'bilinear' | 'cubic' | 'lanczos' | 'average' | 'mode';
+
+// Colormap for single-band tiles (hex colours, stop positions 0-1 within the stretch)
+export type Colormap =
+  | { type: 'named'; name: string; invert?: boolean }
+  | { type: 'stops'; stops: { position: number; color: string }[]; invert?: boolean }
+  | { type: 'classes'; classes: { value: number; color: string; label?: string }[] };
+
+// Hillshade parameters for DEM tiles (degrees; defaults follow gdaldem)
+export interface HillshadeParams {
+  azimuth?: number;
+  altitude?: number;
+  z_factor?: number;
+  mode?: 'standard' | 'multidirectional' | 'combined';
+}
+
+// Slope/aspect rendered from a DEM in place of the elevation
+export type TerrainDerivative = 'slope_degrees' | 'slope_percent' | 'aspect';
+
+// Extra values a layer treats as nodata, on top of the file's nodata, mask and alpha
+export interface NodataRules {
+  values?: number[];
+  range?: [number, number]; // inclusive
+}
+
+// Contrast mode of a stretch; equalizing lookup tables are built from the whole dataset
+export type StretchMode =
+  | { type: 'linear' }
+  | { type: 'equalize' }
+  | { type: 'clipped_equalize'; clip_limit?: number };
+
+// Stretch range and gamma for one band
+export interface StretchParams {
+  min: number;
+  max: number;
+  gamma: number;
+  mode?: StretchMode; // omitted when linear
+}
+
+// How compute_auto_stretch picks a range from sampled values (percentiles 0-100)
+export type AutoStretchMethod =
+  | { type: 'min_max' }
+  | { type: 'percentile'; low: number; high: number }
+  | { type: 'std_dev'; n: number };
+
 // Tauri command function signatures
 export interface TauriCommands {
   // Raster commands
   open_raster(path: string): Promise<RasterMetadata>;
+  list_subdatasets(path: string): Promise<SubdatasetInfo[]>;
   close_dataset(id: string): Promise<void>;
-  get_tile(id: string, z: number, x: number, y: number, band: number): Promise<Uint8Array>;
+  // Tile commands render tile_size × pixel_ratio pixels square (default 256 × 1),
+  // encoded as format (default PNG)
+  get_tile(
+    id: string,
+    z: number,
+    x: number,
+    y: number,
+    band: number,
+    resampling?: Resampling,
+    colormap?: Colormap,
+    hillshade?: HillshadeParams,
+    derivative?: TerrainDerivative,
+    nodata?: NodataRules,
+    tile_size?: number,
+    pixel_ratio?: number,
+    format?: TileFormat,
+    slice?: DimensionSlice, // picks the band of multidimensional rasters
+    complex?: ComplexPart
+  ): Promise<ArrayBuffer>;
   get_tile_stretched(
     id: string,
     z: number,
@@ -150,8 +282,19 @@ export interface TauriCommands {
     band: number,
     min: number,
     max: number,
-    gamma: number
-  ): Promise<Uint8Array>;
+    gamma: number,
+    stretch_mode?: StretchMode,
+    resampling?: Resampling,
+    colormap?: Colormap,
+    hillshade?: HillshadeParams,
+    derivative?: TerrainDerivative,
+    nodata?: NodataRules,
+    tile_size?: number,
+    pixel_ratio?: number,
+    format?: TileFormat,
+    slice?: DimensionSlice, // picks the band of multidimensional rasters
+    complex?: ComplexPart
+  ): Promise<ArrayBuffer>;
   get_rgb_tile(
     id: string,
     z: number,
@@ -160,6 +303,7 @@ export interface TauriCommands {
     r: number,
     g: number,
     b: number,
+    slice?: DimensionSlice, // bands count along the free dimensions
     r_min: number,
     r_max: number,
     r_gamma: number,
@@ -168,8 +312,16 @@ export interface TauriCommands {
     g_gamma: number,
     b_min: number,
     b_max: number,
-    b_gamma: number
-  ): Promise<Uint8Array>;
+    b_gamma: number,
+    resampling?: Resampling,
+    nodata?: NodataRules,
+    r_mode?: StretchMode,
+    g_mode?: StretchMode,
+    b_mode?: StretchMode,
+    tile_size?: number,
+    pixel_ratio?: number,
+    format?: TileFormat
+  ): Promise<ArrayBuffer>;
   get_cross_layer_rgb_tile(
     r_id: string,
     g_id: string,
@@ -180,6 +332,7 @@ export interface TauriCommands {
     r_band: number,
     g_band: number,
     b_band: number,
+    slice?: DimensionSlice, // bands count along the free dimensions
     r_min: number,
     r_max: number,
     r_gamma: number,
@@ -188,8 +341,16 @@ export interface TauriCommands {
     g_gamma: number,
     b_min: number,
     b_max: number,
-    b_gamma: number
-  ): Promise<Uint8Array>;
+    b_gamma: number,
+    resampling?: Resampling,
+    nodata?: NodataRules,
+    r_mode?: StretchMode,
+    g_mode?: StretchMode,
+    b_mode?: StretchMode,
+    tile_size?: number,
+    pixel_ratio?: number,
+    format?: TileFormat
+  ): Promise<ArrayBuffer>;
   get_pixel_tile(
     id: string,
     z: number,
@@ -198,12 +359,114 @@ export interface TauriCommands {
     band: number,
     min: number,
     max: number,
-    gamma: number
-  ): Promise<Uint8Array>;
-  get_raster_stats(id: string, band: number): Promise<BandStats>;
-  get_histogram(id: string, band: number, bin_count: number): Promise<HistogramData>;
-  query_pixel_value(id: string, lon: number, lat: number, band: number): Promise<number | null>;
-  get_elevation_profile(id: string, coords: [number, number][]): Promise<number[]>;
+    gamma: number,
+    stretch_mode?: StretchMode,
+    resampling?: Resampling,
+    colormap?: Colormap,
+    nodata?: NodataRules,
+    tile_size?: number,
+    pixel_ratio?: number,
+    format?: TileFormat,
+    slice?: DimensionSlice, // picks the band of multidimensional rasters
+    complex?: ComplexPart
+  ): Promise<ArrayBuffer>;
+  // Data tiles: 'HDT1', u16 width, height, band count, reserved; then one
+  // little-endian f32 plane per band (NaN = invalid) and a u8 validity mask.
+  // Width and height are 0 outside the dataset.
+  get_data_tile(
+    id: string,
+    z: number,
+    x: number,
+    y: number,
+    bands: number[],
+    slice?: DimensionSlice, // bands count along the free dimensions
+    resampling?: Resampling,
+    nodata?: NodataRules,
+    tile_size?: number,
+    pixel_ratio?: number,
+    complex?: ComplexPart
+  ): Promise<ArrayBuffer>;
+  get_pixel_data_tile(
+    id: string,
+    z: number,
+    x: number,
+    y: number,
+    bands: number[],
+    slice?: DimensionSlice, // bands count along the free dimensions
+    resampling?: Resampling,
+    nodata?: NodataRules,
+    tile_size?: number,
+    pixel_ratio?: number,
+    complex?: ComplexPart
+  ): Promise<ArrayBuffer>;
+  get_raster_stats(
+    id: string,
+    band: number,
+    nodata?: NodataRules,
+    complex?: ComplexPart,
+    accuracy?: StatsAccuracy, // Full statistics; no nodata rules, complex part or region
+    bbox?: [number, number, number, number],
+    polygon?: RegionPolygon,
+    bbox_crs?: RegionCrs
+  ): Promise<BandStats>;
+  get_histogram(
+    id: string,
+    band: number,
+    bin_count: number,
+    nodata?: NodataRules,
+    complex?: ComplexPart,
+    bbox?: [number, number, number, number],
+    polygon?: RegionPolygon,
+    bbox_crs?: RegionCrs
+  ): Promise<HistogramData>;
+  get_histograms(
+    id: string,
+    bands: number[], // e.g. the three bands of an RGB composite
+    num_bins?: number,
+    nodata?: NodataRules,
+    complex?: ComplexPart,
+    bbox?: [number, number, number, number],
+    polygon?: RegionPolygon,
+    bbox_crs?: RegionCrs
+  ): Promise<HistogramData[]>;
+  compute_auto_stretch(
+    id: string,
+    bands: number[],
+    method?: AutoStretchMethod,
+    bbox?: [number, number, number, number],
+    polygon?: RegionPolygon,
+    bbox_crs?: RegionCrs,
+    nodata?: NodataRules,
+    complex?: ComplexPart
+  ): Promise<StretchParams[]>;
+  query_pixel_value(
+    id: string,
+    lon: number,
+    lat: number,
+    band: number,
+    terrain_band?: number,
+    nodata?: NodataRules,
+    slice?: DimensionSlice
+  ): Promise<number | null>;
+  get_elevation_profile(
+    id: string,
+    coords: [number, number][],
+    num_samples?: number,
+    nodata?: NodataRules,
+    slice?: DimensionSlice // picks the band of multidimensional rasters
+  ): Promise<number[]>;
+  create_band_math_layer(
+    id: string,
+    expression: string,
+    inputs?: Record<string, string>
+  ): Promise<RasterMetadata>;
+  export_raster(id: string, output_path: string): Promise<void>;
+  build_overviews(
+    id: string,
+    location?: OverviewLocation,
+    resampling?: OverviewResampling
+  ): Promise<number>;
+  cancel_overviews(id: string): Promise<boolean>;
 
   // Vector commands
   open_vector(path: string): Promise<VectorLayerData>;